@@ -1,30 +1,36 @@
-use std::{time::{Duration, Instant}, thread};
+use std::{env, thread, time::Duration};
 
-use lifx_core::udp::Manager;
+use lifx_core::udp::{Circadian, Manager};
 
-#[allow(unreachable_code)]
+/// Keeps every bulb found at startup on daylight-following whites.
+///
+/// Usage: better_day_dusk [latitude longitude]
 fn main() -> anyhow::Result<()> {
-	let mut mgr = Manager::new()?;
+	let args: Vec<f64> = env::args().skip(1).map(|arg| arg.parse()).collect::<Result<_, _>>()?;
+	let (latitude, longitude) = match args[..] {
+		[latitude, longitude] => (latitude, longitude),
+		_ => (51.48, 0.0),
+	};
 
-	loop {
-		if Instant::now() - mgr.last_discovery > Duration::from_secs(300) {
-			mgr.discover()?;
-		}
-		mgr.refresh();
+	let mgr = Manager::new()?;
+	thread::sleep(Duration::from_secs(5));
+	mgr.refresh()?;
+
+	let bulbs = mgr.snapshot();
+	for bulb in &bulbs {
+		println!("{:?} {}", bulb.target, bulb.label.as_deref().unwrap_or("??"));
+	}
+	let circadian = Circadian::sun(latitude, longitude).targets(bulbs.iter().map(|b| b.target));
+	let control = circadian.control();
+	mgr.animate(circadian);
 
-		println!("\n");
-		if let Ok(bulbs) = mgr.bulbs.lock() {
-			for bulb in bulbs.values() {
-				println!("{:?}", bulb);
-				// bulb.set_color(
-				// 	HSBK::color(175, 1.0, 0.4),
-				// 	Duration::from_millis(5000)
-				// )?;
+	loop {
+		thread::sleep(Duration::from_secs(60));
+		mgr.refresh()?;
+		for bulb in &bulbs {
+			if let Some(hold) = control.hold_on(bulb.target) {
+				println!("{:?}: {:?}", bulb.target, hold);
 			}
 		}
-
-		thread::sleep(Duration::from_secs(5));
 	}
-
-	Ok(())
 }
@@ -9,7 +9,7 @@ fn main() {
 	let sock = UdpSocket::bind("0.0.0.0:56700").unwrap();
 	sock.set_broadcast(true).unwrap();
 
-	// 0000562B29D573D0 - 10.10.1.131:56700
+	// d0:73:d5:29:2b:56 - 10.10.1.131:56700
 
 	let target: SocketAddr = "10.10.1.131:56700".parse().unwrap();
 
@@ -27,7 +27,7 @@ fn main() {
 	};
 
 	let opts = BuildOptions {
-		target: Some(0x0000562B29D573D0),
+		target: Some("d0:73:d5:29:2b:56".parse().unwrap()),
 		source: 12345678,
 		..Default::default()
 	};
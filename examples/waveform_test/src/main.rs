@@ -8,12 +8,12 @@ fn main() {
 	let sock = UdpSocket::bind("0.0.0.0:56700").unwrap();
 	sock.set_broadcast(true).unwrap();
 
-	//Office/My Home (0000619602D573D0 - 10.10.1.132:56700) - Original 1000
+	//Office/My Home (d0:73:d5:02:96:61 - 10.10.1.132:56700) - Original 1000
 
 	let target: SocketAddr = "10.10.1.132:56700".parse().unwrap();
 
 	let opts = BuildOptions {
-		target: Some(0x0000619602D573D0),
+		target: Some("d0:73:d5:02:96:61".parse().unwrap()),
 		ack_required: false,
 		res_required: false,
 		sequence: 0,
@@ -0,0 +1,93 @@
+//! Benchmarks for packing messages, as done for every packet a sender puts on the wire.
+//!
+//! Run with `cargo bench -p lifx-core`.
+
+#![feature(test)]
+
+extern crate test;
+
+use lifx_core::{
+	ApplicationRequest, BuildOptions, Kelvin, Message, RawMessage, HSBK, MAX_MESSAGE_SIZE,
+};
+use test::{black_box, Bencher};
+
+fn small() -> Message {
+	Message::LightSetColor {
+		reserved: 0,
+		color: HSBK::white(Kelvin::Neutral, 1.0),
+		duration: 1024,
+	}
+}
+
+fn large() -> Message {
+	Message::SetExtendedColorZones {
+		duration: 0,
+		apply: ApplicationRequest::Apply,
+		zone_index: 0,
+		colors_count: 82,
+		colors: [HSBK::white(Kelvin::Neutral, 1.0); 82],
+	}
+}
+
+fn raw(msg: Message) -> RawMessage {
+	RawMessage::build(&BuildOptions::default(), msg).unwrap()
+}
+
+/// Packs each header into its own `Vec` and concatenates them, for comparison.
+fn pack_concat(raw: &RawMessage) -> Vec<u8> {
+	let mut v = Vec::with_capacity(raw.packed_size());
+	v.extend(raw.frame.pack().unwrap());
+	v.extend(raw.frame_addr.pack().unwrap());
+	v.extend(raw.protocol_header.pack().unwrap());
+	v.extend(&raw.payload);
+	v
+}
+
+#[bench]
+fn pack_small(b: &mut Bencher) {
+	let raw = raw(small());
+	b.iter(|| black_box(&raw).pack().unwrap());
+}
+
+#[bench]
+fn pack_small_concat(b: &mut Bencher) {
+	let raw = raw(small());
+	b.iter(|| pack_concat(black_box(&raw)));
+}
+
+#[bench]
+fn pack_into_small(b: &mut Bencher) {
+	let raw = raw(small());
+	let mut buf = [0; MAX_MESSAGE_SIZE];
+	b.iter(|| black_box(&raw).pack_into(&mut buf).unwrap());
+}
+
+#[bench]
+fn pack_large(b: &mut Bencher) {
+	let raw = raw(large());
+	b.iter(|| black_box(&raw).pack().unwrap());
+}
+
+#[bench]
+fn pack_large_concat(b: &mut Bencher) {
+	let raw = raw(large());
+	b.iter(|| pack_concat(black_box(&raw)));
+}
+
+#[bench]
+fn pack_into_large(b: &mut Bencher) {
+	let raw = raw(large());
+	let mut buf = [0; MAX_MESSAGE_SIZE];
+	b.iter(|| black_box(&raw).pack_into(&mut buf).unwrap());
+}
+
+#[bench]
+fn build_and_pack_small(b: &mut Bencher) {
+	let options = BuildOptions::default();
+	b.iter(|| {
+		RawMessage::build(&options, black_box(small()))
+			.unwrap()
+			.pack()
+			.unwrap()
+	});
+}
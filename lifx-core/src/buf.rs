@@ -0,0 +1,39 @@
+//! Packing and unpacking with the [bytes] crate's [Buf] and [BufMut] traits.
+//!
+//! Requires the `bytes` feature.
+
+use bytes::{Buf, BufMut};
+
+use crate::{Error, RawMessage};
+
+impl RawMessage {
+	/// Like [RawMessage::unpack], but consumes a [Buf] holding exactly one message.
+	///
+	/// A contiguous buffer (like `Bytes` or `BytesMut`) is parsed in place, and is only advanced
+	/// if it holds a valid message.  A buffer made of several chunks has to be gathered up first,
+	/// so it is consumed either way.
+	pub fn unpack_buf<B: Buf>(mut buf: B) -> Result<RawMessage, Error> {
+		let msg = if buf.chunk().len() == buf.remaining() {
+			RawMessage::unpack(buf.chunk())?
+		} else {
+			RawMessage::unpack(&buf.copy_to_bytes(buf.remaining()))?
+		};
+		buf.advance(buf.remaining());
+		Ok(msg)
+	}
+
+	/// Like [RawMessage::pack], but writes into a [BufMut] instead of allocating.
+	///
+	/// Nothing is written if `buf` doesn't have room for [RawMessage::packed_size] bytes.
+	pub fn pack_buf<B: BufMut>(&self, buf: &mut B) -> Result<(), Error> {
+		let size = self.checked_size()?;
+		if buf.remaining_mut() < size {
+			return Err(Error::BufferTooSmall {
+				needed: size,
+				actual: buf.remaining_mut(),
+			});
+		}
+
+		self.write_to(buf.writer())
+	}
+}
@@ -0,0 +1,208 @@
+use std::{
+	convert::TryFrom,
+	io::{self, Read, Write},
+};
+
+use crate::{
+	error::Error,
+	misc::LifxIdent,
+	read_write::{invalid_data, Codec},
+};
+
+/// The gesture that triggers a [ButtonAction].
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ButtonGesture {
+	/// An unused action slot
+	#[default]
+	None = 0,
+	Press = 1,
+	Hold = 2,
+	PressPress = 3,
+	PressHold = 4,
+	HoldHold = 5,
+}
+
+impl Codec for ButtonGesture {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u16).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<ButtonGesture, io::Error> {
+		ButtonGesture::try_from(u16::decode(r)?).map_err(invalid_data)
+	}
+}
+
+impl TryFrom<u16> for ButtonGesture {
+	type Error = Error;
+	fn try_from(val: u16) -> Result<ButtonGesture, Error> {
+		match val {
+			0 => Ok(ButtonGesture::None),
+			1 => Ok(ButtonGesture::Press),
+			2 => Ok(ButtonGesture::Hold),
+			3 => Ok(ButtonGesture::PressPress),
+			4 => Ok(ButtonGesture::PressHold),
+			5 => Ok(ButtonGesture::HoldHold),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "gesture",
+				value: x as u32,
+			}),
+		}
+	}
+}
+
+/// What a [ButtonAction] operates on.
+///
+/// On the wire this is a `target_type` followed by a 16 byte union; the variant determines both.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ButtonTarget {
+	/// A reserved (or unknown) target type, with its raw union bytes
+	Reserved { target_type: u16, data: [u8; 16] },
+	/// Relays on the switch itself
+	Relays { relays_count: u8, relays: [u8; 15] },
+	/// Another device, by serial number (MAC address)
+	Device { serial: [u8; 6], reserved: [u8; 10] },
+	Location(LifxIdent),
+	Group(LifxIdent),
+	Scene(LifxIdent),
+	/// Relays on another switch, by serial number
+	DeviceRelays {
+		serial: [u8; 6],
+		relays_count: u8,
+		relays: [u8; 9],
+	},
+}
+
+impl Default for ButtonTarget {
+	fn default() -> ButtonTarget {
+		ButtonTarget::Reserved {
+			target_type: 0,
+			data: [0; 16],
+		}
+	}
+}
+
+impl Codec for ButtonTarget {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		match self {
+			ButtonTarget::Reserved { target_type, data } => {
+				target_type.encode(w)?;
+				data.encode(w)?;
+			}
+			ButtonTarget::Relays {
+				relays_count,
+				relays,
+			} => {
+				2u16.encode(w)?;
+				relays_count.encode(w)?;
+				relays.encode(w)?;
+			}
+			ButtonTarget::Device { serial, reserved } => {
+				3u16.encode(w)?;
+				serial.encode(w)?;
+				reserved.encode(w)?;
+			}
+			ButtonTarget::Location(id) => {
+				4u16.encode(w)?;
+				id.encode(w)?;
+			}
+			ButtonTarget::Group(id) => {
+				5u16.encode(w)?;
+				id.encode(w)?;
+			}
+			ButtonTarget::Scene(id) => {
+				6u16.encode(w)?;
+				id.encode(w)?;
+			}
+			ButtonTarget::DeviceRelays {
+				serial,
+				relays_count,
+				relays,
+			} => {
+				7u16.encode(w)?;
+				serial.encode(w)?;
+				relays_count.encode(w)?;
+				relays.encode(w)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<ButtonTarget, io::Error> {
+		let target_type = u16::decode(r)?;
+		Ok(match target_type {
+			2 => ButtonTarget::Relays {
+				relays_count: Codec::decode(r)?,
+				relays: Codec::decode(r)?,
+			},
+			3 => ButtonTarget::Device {
+				serial: Codec::decode(r)?,
+				reserved: Codec::decode(r)?,
+			},
+			4 => ButtonTarget::Location(Codec::decode(r)?),
+			5 => ButtonTarget::Group(Codec::decode(r)?),
+			6 => ButtonTarget::Scene(Codec::decode(r)?),
+			7 => ButtonTarget::DeviceRelays {
+				serial: Codec::decode(r)?,
+				relays_count: Codec::decode(r)?,
+				relays: Codec::decode(r)?,
+			},
+			_ => ButtonTarget::Reserved {
+				target_type,
+				data: Codec::decode(r)?,
+			},
+		})
+	}
+}
+
+/// A single gesture-to-target binding on a button.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ButtonAction {
+	pub gesture: ButtonGesture,
+	pub target: ButtonTarget,
+}
+
+impl Codec for ButtonAction {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.gesture.encode(w)?;
+		self.target.encode(w)?;
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<ButtonAction, io::Error> {
+		Ok(ButtonAction {
+			gesture: Codec::decode(r)?,
+			target: Codec::decode(r)?,
+		})
+	}
+}
+
+/// The configuration of a single physical button on a LIFX Switch.
+///
+/// See also [Message::StateButton].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Button {
+	/// The number of entries in `actions` that are used
+	pub actions_count: u8,
+	pub actions: [ButtonAction; 5],
+}
+
+impl Codec for Button {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.actions_count.encode(w)?;
+		self.actions.encode(w)?;
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Button, io::Error> {
+		Ok(Button {
+			actions_count: Codec::decode(r)?,
+			actions: Codec::decode(r)?,
+		})
+	}
+}
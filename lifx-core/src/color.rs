@@ -1,16 +1,21 @@
-use std::{convert::TryFrom, io};
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::{
+	convert::TryFrom,
+	io::{self, Read, Write},
+	ops::RangeInclusive,
+};
 
 use crate::{
 	error::Error,
-	read_write::{LittleEndianReader, LittleEndianWriter},
+	product::ProductInfo,
+	read_write::{invalid_data, Codec},
 };
 
 /// Controls how/when multizone devices apply color changes
 ///
 /// See also [Message::SetColorZones].
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ApplicationRequest {
 	/// Don't apply the requested changes until a message with Apply or ApplyOnly is sent
 	NoApply = 0,
@@ -20,12 +25,13 @@ pub enum ApplicationRequest {
 	ApplyOnly = 2,
 }
 
-impl<T> LittleEndianWriter<ApplicationRequest> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: ApplicationRequest) -> Result<(), io::Error> {
-		self.write_u8(v as u8)
+impl Codec for ApplicationRequest {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<ApplicationRequest, io::Error> {
+		ApplicationRequest::try_from(u8::decode(r)?).map_err(invalid_data)
 	}
 }
 
@@ -36,16 +42,18 @@ impl TryFrom<u8> for ApplicationRequest {
 			0 => Ok(ApplicationRequest::NoApply),
 			1 => Ok(ApplicationRequest::Apply),
 			2 => Ok(ApplicationRequest::ApplyOnly),
-			x => Err(Error::ProtocolError(format!(
-				"Unknown application request {}",
-				x
-			))),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "apply",
+				value: x as u32,
+			}),
 		}
 	}
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Waveform {
 	Saw = 0,
 	Sine = 1,
@@ -54,12 +62,13 @@ pub enum Waveform {
 	Pulse = 4,
 }
 
-impl<T> LittleEndianWriter<Waveform> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: Waveform) -> Result<(), io::Error> {
-		self.write_u8(v as u8)
+impl Codec for Waveform {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Waveform, io::Error> {
+		Waveform::try_from(u8::decode(r)?).map_err(invalid_data)
 	}
 }
 
@@ -72,10 +81,11 @@ impl TryFrom<u8> for Waveform {
 			2 => Ok(Waveform::HalfSign),
 			3 => Ok(Waveform::Triangle),
 			4 => Ok(Waveform::Pulse),
-			x => Err(Error::ProtocolError(format!(
-				"Unknown waveform value {}",
-				x
-			))),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "waveform",
+				value: x as u32,
+			}),
 		}
 	}
 }
@@ -94,7 +104,8 @@ impl TryFrom<u8> for Waveform {
 /// When a light is displaying colors, kelvin is ignored.
 ///
 /// To display "pure" colors, set saturation to full (65535).
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HSBK {
 	pub hue: u16,
 	pub saturation: u16,
@@ -102,7 +113,13 @@ pub struct HSBK {
 	pub kelvin: u16,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// The named color temperatures from the LIFX mobile app.
+///
+/// Converts into a `u16`, so it can be passed to [HSBK::white] directly.  Use [Kelvin::nearest] to
+/// go the other way for an arbitrary temperature.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Kelvin  {
 	Candlelight    = 1500,
 	Sunset         = 2000,
@@ -122,6 +139,58 @@ pub enum Kelvin  {
 	BlueIce        = 9000,
 }
 
+impl Kelvin {
+	const ALL: [Kelvin; 16] = [
+		Kelvin::Candlelight,
+		Kelvin::Sunset,
+		Kelvin::UltraWarm,
+		Kelvin::Incandescent,
+		Kelvin::Warm,
+		Kelvin::Neutral,
+		Kelvin::Cool,
+		Kelvin::CoolDaylight,
+		Kelvin::SoftDaylight,
+		Kelvin::Daylight,
+		Kelvin::NoonDaylight,
+		Kelvin::BrightDaylight,
+		Kelvin::CloudyDaylight,
+		Kelvin::BlueDaylight,
+		Kelvin::BlueOvercast,
+		Kelvin::BlueIce,
+	];
+
+	/// The named temperature closest to `kelvin`.  Ties go to the warmer one.
+	pub fn nearest(kelvin: u16) -> Kelvin {
+		*Kelvin::ALL
+			.iter()
+			.min_by_key(|k| (**k as u16).abs_diff(kelvin))
+			.unwrap()
+	}
+}
+
+impl From<Kelvin> for u16 {
+	fn from(k: Kelvin) -> u16 {
+		k as u16
+	}
+}
+
+impl TryFrom<u16> for Kelvin {
+	type Error = Error;
+
+	/// Fails unless `val` is exactly one of the named temperatures; see [Kelvin::nearest].
+	fn try_from(val: u16) -> Result<Kelvin, Error> {
+		Kelvin::ALL
+			.iter()
+			.find(|k| **k as u16 == val)
+			.copied()
+			.ok_or(Error::InvalidEnumValue {
+				typ: None,
+				field: "kelvin",
+				value: val as u32,
+			})
+	}
+}
+
 impl HSBK {
 	pub fn describe(&self, short: bool) -> String {
 		match short {
@@ -145,11 +214,13 @@ impl HSBK {
 		}
 	}
 
-	pub fn white(kelvin: u16, brightness: f32) -> HSBK {
+	/// A white at the given color temperature, which can be a [Kelvin] or a `u16` (integer literals
+	/// need a suffix, as in `3500u16`).  `brightness` ranges from 0.0 to 1.0.
+	pub fn white(kelvin: impl Into<u16>, brightness: f32) -> HSBK {
 		HSBK {
 			hue: 0,
 			saturation: 0,
-			kelvin,
+			kelvin: kelvin.into(),
 			brightness: (brightness * u16::MAX as f32) as u16,
 		}
 	}
@@ -162,6 +233,254 @@ impl HSBK {
 			kelvin: 0,
 		}
 	}
+
+	/// Interpolates between this color (at `t = 0.0`) and `other` (at `t = 1.0`).
+	///
+	/// Hue goes the short way around the color wheel, so going from red to magenta doesn't pass
+	/// through green and blue.  Saturation, brightness and kelvin are interpolated linearly.  `t`
+	/// is clamped to `0.0..=1.0`.
+	pub fn lerp(&self, other: HSBK, t: f32) -> HSBK {
+		let t = t.clamp(0.0, 1.0);
+		let lerp = |a: u16, b: u16| (a as f32 + (b as f32 - a as f32) * t).round() as u16;
+
+		// the hue wraps around at 65536, so the difference as an i16 is the shortest path
+		let hue_delta = other.hue.wrapping_sub(self.hue) as i16;
+		HSBK {
+			hue: self.hue.wrapping_add((hue_delta as f32 * t).round() as i16 as u16),
+			saturation: lerp(self.saturation, other.saturation),
+			brightness: lerp(self.brightness, other.brightness),
+			kelvin: lerp(self.kelvin, other.kelvin),
+		}
+	}
+
+	/// Returns this color with its brightness set to look `brightness` (0.0 to 1.0) as bright as
+	/// full brightness does.
+	///
+	/// The `brightness` field is linear in light output, but the eye isn't: 50% of the output looks
+	/// more like 75%.  This uses the CIE 1976 lightness curve to correct for that, so stepping
+	/// `brightness` evenly makes even steps in how bright the light looks.
+	pub fn with_perceived_brightness(self, brightness: f32) -> HSBK {
+		let l = brightness.clamp(0.0, 1.0);
+		let y = if l > 0.08 {
+			((l + 0.16) / 1.16).powi(3)
+		} else {
+			l / 9.033
+		};
+		HSBK {
+			brightness: (y * u16::MAX as f32).round() as u16,
+			..self
+		}
+	}
+
+	/// How bright this color looks (0.0 to 1.0), the inverse of [HSBK::with_perceived_brightness].
+	///
+	/// Useful for displaying a brightness to a user, e.g. as a percentage.
+	pub fn perceived_brightness(&self) -> f32 {
+		let y = self.brightness as f32 / u16::MAX as f32;
+		if y > 0.008856 {
+			1.16 * y.cbrt() - 0.16
+		} else {
+			y * 9.033
+		}
+	}
+
+	/// The CIE 1931 xy chromaticity of this color (ignoring brightness).
+	///
+	/// Whites (zero saturation) are placed on the black-body curve at their color temperature.
+	/// Colors are treated as sRGB, with a D65 white point, so this is an approximation of what a
+	/// bulb actually shows.
+	pub fn to_xy(&self) -> (f32, f32) {
+		if self.saturation == 0 {
+			return planckian_xy(self.kelvin);
+		}
+
+		let (r, g, b) = hsv_to_rgb(
+			self.hue as f32 / 65536.0 * 6.0,
+			self.saturation as f32 / u16::MAX as f32,
+		);
+		let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+		let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+		let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+		let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+		let sum = x + y + z;
+		(x / sum, y / sum)
+	}
+
+	/// The color closest to the CIE 1931 chromaticity `(x, y)`, at `brightness` (0.0 to 1.0).
+	///
+	/// This is the inverse of [HSBK::to_xy] for colors: chromaticities outside of the sRGB gamut are
+	/// clamped to its edge.  The kelvin is set to the nearest color temperature (within 1500K to
+	/// 9000K), which is what the bulb uses if the saturation is zero.
+	pub fn from_xy(x: f32, y: f32, brightness: f32) -> HSBK {
+		let y = y.max(f32::EPSILON);
+		let (cx, cy, cz) = (x / y, 1.0, (1.0 - x - y) / y);
+		let r = 3.2406 * cx - 1.5372 * cy - 0.4986 * cz;
+		let g = -0.9689 * cx + 1.8758 * cy + 0.0415 * cz;
+		let b = 0.0557 * cx - 0.2040 * cy + 1.0570 * cz;
+
+		let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+		let peak = r.max(g).max(b).max(f32::EPSILON);
+		let (r, g, b) = (
+			linear_to_srgb(r / peak),
+			linear_to_srgb(g / peak),
+			linear_to_srgb(b / peak),
+		);
+		let max = r.max(g).max(b);
+		let delta = max - r.min(g).min(b);
+		let hue = if delta <= 0.0 {
+			0.0
+		} else if max == r {
+			((g - b) / delta).rem_euclid(6.0)
+		} else if max == g {
+			(b - r) / delta + 2.0
+		} else {
+			(r - g) / delta + 4.0
+		};
+
+		// McCamy's approximation of the correlated color temperature
+		let n = (x - 0.3320) / (0.1858 - y);
+		let cct = 449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33;
+
+		HSBK {
+			hue: (hue / 6.0 * 65536.0).round() as u32 as u16,
+			saturation: (delta / max * u16::MAX as f32).round() as u16,
+			brightness: (brightness.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16,
+			kelvin: cct.clamp(1500.0, 9000.0).round() as u16,
+		}
+	}
+
+	/// Rotates the hue by `degrees` (which can be negative), wrapping around the color wheel.
+	///
+	/// Like the other adjustment methods, this returns the new color, so they can be chained:
+	/// `color.rotate_hue(30.0).scale_brightness(0.5)`.
+	pub fn rotate_hue(self, degrees: f32) -> HSBK {
+		let delta = (degrees.rem_euclid(360.0) / 360.0 * 65536.0).round() as u32 as u16;
+		HSBK {
+			hue: self.hue.wrapping_add(delta),
+			..self
+		}
+	}
+
+	/// Multiplies the brightness by `factor`, clamping at full brightness.
+	pub fn scale_brightness(self, factor: f32) -> HSBK {
+		HSBK {
+			brightness: scale(self.brightness, factor),
+			..self
+		}
+	}
+
+	/// Multiplies the saturation by `factor`, clamping at full saturation.
+	///
+	/// A factor below 1.0 fades the color towards white, and a factor of 0.0 makes it white.
+	pub fn saturate(self, factor: f32) -> HSBK {
+		HSBK {
+			saturation: scale(self.saturation, factor),
+			..self
+		}
+	}
+
+	/// Adds `delta` (which can be negative) to the kelvin, keeping it within `range`.
+	///
+	/// For example, `shift_kelvin(-500, 2500..=9000)` makes a white warmer, but no warmer than
+	/// 2500K.
+	pub fn shift_kelvin(self, delta: i32, range: RangeInclusive<u16>) -> HSBK {
+		let kelvin = (self.kelvin as i32 + delta).clamp(*range.start() as i32, *range.end() as i32);
+		HSBK {
+			kelvin: kelvin as u16,
+			..self
+		}
+	}
+
+	/// Adapts this color to what `product` can display: the kelvin is clamped to its
+	/// [temperature range](ProductInfo::temperature_range), and the saturation is zeroed if it
+	/// isn't a color device.
+	///
+	/// Devices ignore a kelvin outside their range, so asking a bulb that bottoms out at 2500K for
+	/// 2000K would otherwise leave it unchanged.
+	pub fn clamped_for(self, product: &ProductInfo) -> HSBK {
+		HSBK {
+			saturation: if product.color { self.saturation } else { 0 },
+			kelvin: product.clamp_kelvin(self.kelvin),
+			..self
+		}
+	}
+}
+
+/// Multiplies a u16 field by a non-negative factor, saturating at the top of its range.
+fn scale(v: u16, factor: f32) -> u16 {
+	(v as f32 * factor.max(0.0)).round().min(u16::MAX as f32) as u16
+}
+
+/// A gradient between a list of colors, for painting multizone strips (see
+/// [Message::SetColorZones] and [Message::SetExtendedColorZones]).
+///
+/// Each stop has a position from 0.0 (the first zone) to 1.0 (the last zone).  Colors in between
+/// are interpolated with [HSBK::lerp], so hues blend the short way around the color wheel.  A white
+/// stop takes on the hue of its neighbor, so fading from a color to white doesn't sweep through
+/// other hues.
+///
+/// [Message::SetColorZones]: crate::Message::SetColorZones
+/// [Message::SetExtendedColorZones]: crate::Message::SetExtendedColorZones
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+	stops: Vec<(f32, HSBK)>,
+}
+
+impl Gradient {
+	/// An empty gradient, which renders as black until stops are added.
+	pub fn new() -> Gradient {
+		Gradient::default()
+	}
+
+	/// Adds a stop at `position`, keeping the stops sorted.
+	///
+	/// A stop added at the same position as an existing one goes after it, which makes a hard edge
+	/// between the two colors.
+	pub fn with_stop(mut self, position: f32, color: HSBK) -> Gradient {
+		let idx = self.stops.partition_point(|(p, _)| *p <= position);
+		self.stops.insert(idx, (position, color));
+		self
+	}
+
+	/// The stops, in order of position.
+	pub fn stops(&self) -> &[(f32, HSBK)] {
+		&self.stops
+	}
+
+	/// The color at `position`.  Positions outside of the stops get the nearest stop's color.
+	pub fn color_at(&self, position: f32) -> HSBK {
+		let idx = self.stops.partition_point(|(p, _)| *p <= position);
+		match (self.stops.get(idx.wrapping_sub(1)), self.stops.get(idx)) {
+			(Some(&(p0, mut c0)), Some(&(p1, mut c1))) => {
+				if c0.saturation == 0 {
+					c0.hue = c1.hue;
+				} else if c1.saturation == 0 {
+					c1.hue = c0.hue;
+				}
+				c0.lerp(c1, (position - p0) / (p1 - p0))
+			}
+			(Some(&(_, c)), None) | (None, Some(&(_, c))) => c,
+			(None, None) => HSBK::default(),
+		}
+	}
+
+	/// Renders the gradient across `zones` zones, with the first and last zones at positions 0.0
+	/// and 1.0.
+	pub fn render(&self, zones: usize) -> Vec<HSBK> {
+		let mut colors = vec![HSBK::default(); zones];
+		self.render_into(&mut colors);
+		colors
+	}
+
+	/// Like [Gradient::render], but fills an existing slice, such as the `colors` of a
+	/// [Message::SetExtendedColorZones](crate::Message::SetExtendedColorZones).
+	pub fn render_into(&self, zones: &mut [HSBK]) {
+		let last = zones.len().saturating_sub(1).max(1) as f32;
+		for (idx, zone) in zones.iter_mut().enumerate() {
+			*zone = self.color_at(idx as f32 / last);
+		}
+	}
 }
 
 /// Describe (in english words) the color temperature as given in kelvin.
@@ -188,14 +507,80 @@ pub fn describe_kelvin(k: u16) -> &'static str {
 	}
 }
 
+/// Converts a color temperature from kelvin to mireds (micro reciprocal degrees), as used by
+/// Philips Hue and HomeKit.
+///
+/// Rounds to the nearest mired, and saturates at `u16::MAX` for very low (or zero) temperatures.
+pub fn kelvin_to_mired(kelvin: u16) -> u16 {
+	(1_000_000.0 / kelvin as f32).round().min(u16::MAX as f32) as u16
+}
+
+/// Converts a color temperature from mireds to kelvin; see [kelvin_to_mired].
+pub fn mired_to_kelvin(mired: u16) -> u16 {
+	kelvin_to_mired(mired)
+}
+
+/// The chromaticity of a black body at `kelvin`, using the approximation from Kim et al.
+///
+/// Only valid from 1667K to 25000K, so temperatures outside of that are clamped.
+fn planckian_xy(kelvin: u16) -> (f32, f32) {
+	let t = (kelvin as f64).clamp(1667.0, 25000.0);
+	let x = if t <= 4000.0 {
+		-0.2661239e9 / t.powi(3) - 0.2343589e6 / t.powi(2) + 0.8776956e3 / t + 0.179910
+	} else {
+		-3.0258469e9 / t.powi(3) + 2.1070379e6 / t.powi(2) + 0.2226347e3 / t + 0.240390
+	};
+	let y = if t <= 2222.0 {
+		-1.1063814 * x.powi(3) - 1.34811020 * x.powi(2) + 2.18555832 * x - 0.20219683
+	} else if t <= 4000.0 {
+		-0.9549476 * x.powi(3) - 1.37418593 * x.powi(2) + 2.09137015 * x - 0.16748867
+	} else {
+		3.0817580 * x.powi(3) - 5.87338670 * x.powi(2) + 3.75112997 * x - 0.37001483
+	};
+	(x as f32, y as f32)
+}
+
+/// Converts a hue (in sixths of a turn) and saturation to sRGB, at full value.
+fn hsv_to_rgb(h: f32, s: f32) -> (f32, f32, f32) {
+	let f = |n: f32| {
+		let k = (n + h) % 6.0;
+		1.0 - s * k.min(4.0 - k).clamp(0.0, 1.0)
+	};
+	(f(5.0), f(3.0), f(1.0))
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+	if c <= 0.0031308 {
+		c * 12.92
+	} else {
+		1.055 * c.powf(1.0 / 2.4) - 0.055
+	}
+}
+
 impl HSBK {}
 
-impl<R: ReadBytesExt> LittleEndianReader<HSBK> for R {
-	fn read_val(&mut self) -> Result<HSBK, io::Error> {
-		let hue = self.read_val()?;
-		let sat = self.read_val()?;
-		let bri = self.read_val()?;
-		let kel = self.read_val()?;
+impl Codec for HSBK {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.hue.encode(w)?;
+		self.saturation.encode(w)?;
+		self.brightness.encode(w)?;
+		self.kelvin.encode(w)?;
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<HSBK, io::Error> {
+		let hue = u16::decode(r)?;
+		let sat = u16::decode(r)?;
+		let bri = u16::decode(r)?;
+		let kel = u16::decode(r)?;
 		Ok(HSBK {
 			hue,
 			saturation: sat,
@@ -204,16 +589,3 @@ impl<R: ReadBytesExt> LittleEndianReader<HSBK> for R {
 		})
 	}
 }
-
-impl<T> LittleEndianWriter<HSBK> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: HSBK) -> Result<(), io::Error> {
-		self.write_val(v.hue)?;
-		self.write_val(v.saturation)?;
-		self.write_val(v.brightness)?;
-		self.write_val(v.kelvin)?;
-		Ok(())
-	}
-}
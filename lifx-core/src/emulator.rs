@@ -0,0 +1,330 @@
+//! A virtual bulb that answers LIFX messages over UDP, for testing and demos without hardware.
+//!
+//! An [EmulatedBulb] binds its own socket and answers from a background thread, so a
+//! [Manager](crate::udp::Manager) (or any other client) can discover and control it like a real
+//! bulb.  It understands the common device and light messages, and answers zone queries with
+//! [Message::StateUnhandled] (as a single-zone bulb does); anything else is ignored.
+//!
+//! ```no_run
+//! use lifx_core::{emulator::EmulatedBulb, MacAddress};
+//!
+//! let bulb = EmulatedBulb::bind("127.0.0.1:0".parse().unwrap(), MacAddress([1; 6])).unwrap();
+//! println!("listening on {}", bulb.addr());
+//! ```
+
+use std::{
+	io,
+	net::{SocketAddr, UdpSocket},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	thread::{self, JoinHandle},
+	time::{Duration, Instant},
+};
+
+use log::debug;
+
+use crate::{
+	time,
+	BuildOptions,
+	HSBK,
+	LifxIdent,
+	LifxString,
+	MacAddress,
+	Message,
+	PowerLevel,
+	RawMessage,
+	Service,
+};
+
+/// How often the answering thread checks whether it should stop.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What an [EmulatedBulb] reports about itself, and changes when it's sent commands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmulatedState {
+	pub label: String,
+	pub location: LifxIdent,
+	pub location_label: String,
+	pub group: LifxIdent,
+	pub group_label: String,
+	pub power: PowerLevel,
+	pub color: HSBK,
+	/// Reported in [Message::StateVersion]; the default is a LIFX A19.
+	pub vendor: u32,
+	pub product: u32,
+	/// Reported in [Message::StateHostFirmware], as `major << 16 | minor`.
+	pub firmware_version: u32,
+}
+
+impl Default for EmulatedState {
+	fn default() -> EmulatedState {
+		EmulatedState {
+			label: "Emulated Bulb".to_owned(),
+			location: LifxIdent([1; 16]),
+			location_label: "Emulated Home".to_owned(),
+			group: LifxIdent([2; 16]),
+			group_label: "Emulated Room".to_owned(),
+			power: PowerLevel::Standby,
+			color: HSBK {
+				hue: 0,
+				saturation: 0,
+				brightness: 65535,
+				kelvin: 3500,
+			},
+			vendor: 1,
+			product: 27,
+			firmware_version: 3 << 16 | 70,
+		}
+	}
+}
+
+/// A virtual bulb listening on its own UDP socket.
+///
+/// It stops answering when dropped.
+pub struct EmulatedBulb {
+	target: MacAddress,
+	addr: SocketAddr,
+	state: Arc<Mutex<EmulatedState>>,
+	shutdown: Arc<AtomicBool>,
+	thread: Option<JoinHandle<()>>,
+}
+
+impl EmulatedBulb {
+	/// Starts a bulb with the default [EmulatedState], answering as `target` on `addr`.
+	///
+	/// Bind port 0 to pick a free port, and find it with [EmulatedBulb::addr].
+	pub fn bind(addr: SocketAddr, target: MacAddress) -> io::Result<EmulatedBulb> {
+		EmulatedBulb::bind_with_state(addr, target, EmulatedState::default())
+	}
+
+	/// Like [EmulatedBulb::bind], but starting from `state`.
+	pub fn bind_with_state(
+		addr: SocketAddr,
+		target: MacAddress,
+		state: EmulatedState,
+	) -> io::Result<EmulatedBulb> {
+		let sock = UdpSocket::bind(addr)?;
+		sock.set_read_timeout(Some(SHUTDOWN_POLL_INTERVAL))?;
+		let addr = sock.local_addr()?;
+
+		let state = Arc::new(Mutex::new(state));
+		let shutdown = Arc::new(AtomicBool::new(false));
+		let answerer = Answerer {
+			sock,
+			target,
+			started: Instant::now(),
+			state: state.clone(),
+			shutdown: shutdown.clone(),
+		};
+		let thread = thread::spawn(move || answerer.run());
+
+		Ok(EmulatedBulb {
+			target,
+			addr,
+			state,
+			shutdown,
+			thread: Some(thread),
+		})
+	}
+
+	/// The address the bulb is listening on.
+	pub fn addr(&self) -> SocketAddr {
+		self.addr
+	}
+
+	pub fn target(&self) -> MacAddress {
+		self.target
+	}
+
+	/// A copy of the bulb's current state.
+	pub fn state(&self) -> EmulatedState {
+		self.state.lock().unwrap().clone()
+	}
+
+	/// Replaces the bulb's state, as if it had been changed from another app or a wall switch.
+	pub fn set_state(&self, state: EmulatedState) {
+		*self.state.lock().unwrap() = state;
+	}
+}
+
+impl Drop for EmulatedBulb {
+	fn drop(&mut self) {
+		self.shutdown.store(true, Ordering::Relaxed);
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// The background side of an [EmulatedBulb].
+struct Answerer {
+	sock: UdpSocket,
+	target: MacAddress,
+	started: Instant,
+	state: Arc<Mutex<EmulatedState>>,
+	shutdown: Arc<AtomicBool>,
+}
+
+impl Answerer {
+	fn run(self) {
+		let mut buf = [0; 1024];
+		while !self.shutdown.load(Ordering::Relaxed) {
+			match self.sock.recv_from(&mut buf) {
+				Ok((nbytes, addr)) => {
+					if let Err(e) = self.answer(&buf[..nbytes], addr) {
+						debug!("Emulated bulb couldn't answer {}: {}", addr, e);
+					}
+				}
+				// the read timeout, so we can check for shutdown
+				Err(e)
+					if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+				Err(e) => {
+					debug!("Emulated bulb stopped receiving: {}", e);
+					return;
+				}
+			}
+		}
+	}
+
+	/// Handles one datagram, sending an acknowledgement and a reply if they're called for.
+	fn answer(&self, bytes: &[u8], addr: SocketAddr) -> anyhow::Result<()> {
+		let raw = RawMessage::unpack(bytes)?;
+		let frame_addr = &raw.frame_addr;
+		if frame_addr.target != MacAddress([0; 6]) && frame_addr.target != self.target {
+			return Ok(());
+		}
+
+		// like a real bulb, nothing that can't be decoded is acknowledged
+		let message = Message::from_raw(&raw)?;
+		let options = BuildOptions {
+			target: Some(self.target),
+			sequence: frame_addr.sequence,
+			source: raw.frame.source,
+			..Default::default()
+		};
+		if frame_addr.ack_required {
+			self.send(&options, Message::Acknowledgement, addr)?;
+		}
+
+		let reply = self.apply(message);
+		if let Some((reply, query)) = reply {
+			if query || frame_addr.res_required {
+				self.send(&options, reply, addr)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn send(
+		&self,
+		options: &BuildOptions,
+		message: Message,
+		addr: SocketAddr,
+	) -> anyhow::Result<()> {
+		let bytes = RawMessage::build(options, message)?.pack()?;
+		self.sock.send_to(&bytes, addr)?;
+		Ok(())
+	}
+
+	/// Applies `message` to the bulb's state, returning its reply and whether that's sent even
+	/// without `res_required` (as it is for queries).
+	///
+	/// As on real bulbs, the reply to a `Set` message is the state from before the change.
+	fn apply(&self, message: Message) -> Option<(Message, bool)> {
+		let mut state = self.state.lock().unwrap();
+		let power = |level: u16| match level {
+			0 => PowerLevel::Standby,
+			_ => PowerLevel::Enabled,
+		};
+
+		let (query, reply) = match message {
+			Message::GetService => (true, Message::StateService {
+				port: self.sock.local_addr().ok()?.port() as u32,
+				service: Service::UDP,
+			}),
+			Message::GetHostFirmware => (true, Message::StateHostFirmware {
+				build: 0,
+				reserved: 0,
+				version: state.firmware_version,
+			}),
+			Message::GetVersion => (true, Message::StateVersion {
+				vendor: state.vendor,
+				product: state.product,
+				version: 0,
+			}),
+			Message::GetInfo => (true, Message::StateInfo {
+				time: time::now(),
+				uptime: time::to_nanos(self.started.elapsed()),
+				downtime: 0,
+			}),
+			Message::EchoRequest { payload } => (true, Message::EchoResponse { payload }),
+			Message::GetLabel => (true, Message::StateLabel {
+				label: LifxString::new(&state.label),
+			}),
+			Message::SetLabel { label } => {
+				let old = LifxString::new(&state.label);
+				state.label = label.to_string();
+				(false, Message::StateLabel { label: old })
+			}
+			Message::GetLocation => (true, location(&state)),
+			Message::GetGroup => (true, group(&state)),
+			Message::GetPower => (true, Message::StatePower { level: state.power }),
+			Message::SetPower { level } => {
+				let old = std::mem::replace(&mut state.power, level);
+				(false, Message::StatePower { level: old })
+			}
+			Message::LightGetPower => (true, Message::LightStatePower {
+				level: state.power as u16,
+			}),
+			Message::LightSetPower { level, .. } => {
+				let old = std::mem::replace(&mut state.power, power(level));
+				(false, Message::LightStatePower { level: old as u16 })
+			}
+			Message::LightGet => (true, light_state(&state)),
+			Message::LightSetColor { color, .. } => {
+				let old = light_state(&state);
+				state.color = color;
+				(false, old)
+			}
+			// it has no zones, and says so like newer firmware does
+			Message::GetColorZones { .. } | Message::GetExtendedColorZones => {
+				(true, Message::StateUnhandled {
+					unhandled_type: message.get_num(),
+				})
+			}
+			other => {
+				debug!("Emulated bulb ignored {:?}", other);
+				return None;
+			}
+		};
+		Some((reply, query))
+	}
+}
+
+fn location(state: &EmulatedState) -> Message {
+	Message::StateLocation {
+		location: state.location.clone(),
+		label: LifxString::new(&state.location_label),
+		updated_at: 0,
+	}
+}
+
+fn group(state: &EmulatedState) -> Message {
+	Message::StateGroup {
+		group: state.group.clone(),
+		label: LifxString::new(&state.group_label),
+		updated_at: 0,
+	}
+}
+
+fn light_state(state: &EmulatedState) -> Message {
+	Message::LightState {
+		color: state.color,
+		reserved: 0,
+		power: state.power,
+		label: LifxString::new(&state.label),
+		reserved2: 0,
+	}
+}
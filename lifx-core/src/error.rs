@@ -3,6 +3,7 @@ use thiserror::Error;
 
 /// Various message encoding/decoding errors
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
 	/// This error means we were unable to parse a raw message because its type is unknown.
 	///
@@ -10,14 +11,113 @@ pub enum Error {
 	/// type does not necessarily represent a bug.
 	#[error("unknown message type: `{0}`")]
 	UnknownMessageType(u16),
-	/// This error means one of the message fields contains an invalid or unsupported value.
-	#[error("protocol error: `{0}`")]
-	ProtocolError(String),
+
+	/// The buffer is too short to even hold the message headers.
+	#[error("message too short: expected at least {expected} bytes, got {actual}")]
+	MessageTooShort { expected: usize, actual: usize },
+
+	/// The size claimed by [Frame::size](crate::Frame::size) doesn't match the actual size of the
+	/// message.
+	#[error("size mismatch: frame claims {claimed} bytes, but the message is {actual}")]
+	SizeMismatch { claimed: usize, actual: usize },
+
+	/// The payload of a message ended before all of its fields could be read.
+	///
+	/// `offset` is the position within the payload of the field that could not be read.
+	#[error("payload of message type {typ} too short: {actual} bytes, field `{field}` starts at offset {offset}")]
+	PayloadTooShort {
+		typ: u16,
+		field: &'static str,
+		offset: usize,
+		actual: usize,
+	},
+
+	/// The payload isn't the size expected for its message type.
+	#[error("payload of message type {typ} is {actual} bytes, expected {expected}")]
+	PayloadSizeMismatch {
+		typ: u16,
+		expected: usize,
+		actual: usize,
+	},
+
+	/// A reserved field is non-zero (only reported when decoding strictly).
+	///
+	/// `typ` is the message type, if it was known when the field was checked.
+	#[error("reserved field `{field}` is non-zero")]
+	ReservedFieldNonZero {
+		typ: Option<u16>,
+		field: &'static str,
+	},
+
+	/// One of the message fields contains a value that doesn't map to a known enum variant.
+	///
+	/// `typ` is the message type, if the value was found while decoding a message payload.  Only
+	/// strict decoding reports these for payloads (see [crate::Message::Undecoded]).
+	#[error("invalid value {value} for `{field}`")]
+	InvalidEnumValue {
+		typ: Option<u16>,
+		field: &'static str,
+		value: u32,
+	},
+
+	/// One of the header fields contains an invalid or unsupported value.
+	#[error("invalid value {value} for header field `{field}`")]
+	InvalidHeaderField { field: &'static str, value: u32 },
+
+	/// A string couldn't be parsed as a [MacAddress](crate::MacAddress).
+	#[error("invalid MAC address: `{0}`")]
+	InvalidMacAddress(String),
+
+	/// The buffer passed to a `pack_into` method is too small to hold the packed data.
+	#[error("buffer too small: need {needed} bytes, got {actual}")]
+	BufferTooSmall { needed: usize, actual: usize },
+
+	/// A string is too long to fit in a [LifxString](crate::LifxString).
+	#[error("string is {len} bytes, but at most 32 are allowed")]
+	StringTooLong { len: usize },
 
 	#[error("i/o error")]
 	Io(#[from] io::Error),
 }
 
+impl Error {
+	/// Attaches the message type and field name to an error raised while decoding a field.
+	pub(crate) fn in_field(self, typ: u16, field: &'static str) -> Error {
+		match self {
+			Error::InvalidEnumValue { value, .. } => Error::InvalidEnumValue {
+				typ: Some(typ),
+				field,
+				value,
+			},
+			e => e,
+		}
+	}
+
+	/// Converts an error raised while reading a payload field at `offset`.
+	pub(crate) fn from_read(
+		e: io::Error,
+		typ: u16,
+		field: &'static str,
+		offset: usize,
+		actual: usize,
+	) -> Error {
+		if e.kind() == io::ErrorKind::UnexpectedEof {
+			return Error::PayloadTooShort {
+				typ,
+				field,
+				offset,
+				actual,
+			};
+		}
+		// a field type that rejected its value (see `read_write::invalid_data`)
+		if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+			let inner = e.into_inner().unwrap().downcast::<Error>().unwrap();
+			return inner.in_field(typ, field);
+		}
+		Error::Io(e)
+	}
+}
+
 impl From<std::convert::Infallible> for Error {
 	fn from(_: std::convert::Infallible) -> Self {
 		unreachable!()
@@ -30,20 +30,46 @@ mod error;
 mod string;
 mod read_write;
 mod msg;
+mod msg_type;
 mod protocol;
 mod color;
 mod misc;
 mod product;
+mod vendor;
+mod tile;
+mod button;
+mod source;
+#[cfg(feature = "serde")]
+mod serde_array;
+#[cfg(feature = "bytes")]
+mod buf;
+pub mod emulator;
+pub mod time;
 pub mod udp;
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
 
 pub use error::Error;
 pub use string::LifxString;
-pub use read_write::{LittleEndianReader, LittleEndianWriter};
-pub use msg::{BuildOptions, Message, RawMessage};
+pub use read_write::Codec;
+pub use msg::{
+	BuildOptions, DecodeOptions, Message, Payload, RawMessage, RawMessageRef, ResponseMeta,
+	MAX_MESSAGE_SIZE,
+};
+pub use msg_type::MessageType;
 pub use protocol::{Frame, FrameAddress, ProtocolHeader};
-pub use color::{ApplicationRequest, Waveform, HSBK, Kelvin};
-pub use misc::{EchoPayload, LifxIdent, PowerLevel, Service};
+pub use color::{
+	kelvin_to_mired, mired_to_kelvin, ApplicationRequest, Gradient, Waveform, HSBK, Kelvin,
+};
+pub use misc::{
+	EchoPayload, LastHevCycleResult, LifxIdent, MacAddress, MultiZoneEffectType, PowerLevel,
+	Service, TileEffectType,
+};
 pub use product::{get_product_info, ProductInfo};
+pub use vendor::{register_product, Vendor};
+pub use tile::Tile;
+pub use button::{Button, ButtonAction, ButtonGesture, ButtonTarget};
+pub use source::random_source;
 
 //trace_macros!(true);
 //message_types! {
@@ -66,6 +92,95 @@ pub use product::{get_product_info, ProductInfo};
 mod tests {
 	use super::*;
 
+	/// A bulb with no model yet, and the queue its messages go to.
+	fn test_bulb() -> (udp::SendQueue, udp::Bulb) {
+		let queue = udp::SendQueue::new();
+		let addr = "127.0.0.1:56700".parse().unwrap();
+		let bulb = udp::Bulb::new(0, MacAddress([1; 6]), queue.clone(), addr);
+		(queue, bulb)
+	}
+
+	/// Every message waiting in `queue`, in the order they would be sent.
+	fn drain(queue: &udp::SendQueue) -> Vec<Message> {
+		let unpack = |outgoing: udp::Outgoing| {
+			Message::from_raw(&RawMessage::unpack(&outgoing.bytes).unwrap()).unwrap()
+		};
+		std::iter::from_fn(|| queue.try_pop()).map(unpack).collect()
+	}
+
+	/// A threaded Manager on localhost that has found an [emulator::EmulatedBulb].
+	fn emulated_manager() -> (emulator::EmulatedBulb, udp::Manager) {
+		let localhost = "127.0.0.1:0".parse().unwrap();
+		let emulated = emulator::EmulatedBulb::bind(localhost, MacAddress([1; 6])).unwrap();
+		let mgr = localhost_manager();
+		mgr.probe(emulated.addr()).unwrap();
+		wait_for_bulb(&mgr);
+		(emulated, mgr)
+	}
+
+	/// A threaded Manager on localhost that has found a bulb played by the returned socket, for
+	/// tests that answer it by hand.  The bulb's address is `MacAddress([1; 6])`.
+	fn fake_bulb_manager() -> (std::net::UdpSocket, udp::Manager) {
+		let sock = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+		sock.set_read_timeout(Some(std::time::Duration::from_secs(1))).unwrap();
+		let mgr = localhost_manager();
+		mgr.probe(sock.local_addr().unwrap()).unwrap();
+		let (get_service, addr) = recv_raw(&sock);
+		let state_service = Message::StateService {
+			port: sock.local_addr().unwrap().port() as u32,
+			service: Service::UDP,
+		};
+		reply(&sock, addr, &get_service, MacAddress([1; 6]), state_service);
+		wait_for_bulb(&mgr);
+		(sock, mgr)
+	}
+
+	/// A threaded Manager on localhost that only sends what it's asked to, and only once.
+	fn localhost_manager() -> udp::Manager {
+		udp::Manager::builder()
+			.addr("127.0.0.1:0".parse().unwrap())
+			.interfaces(udp::InterfaceFilter::all().with_name("(none)"))
+			.retry_policy(udp::RetryPolicy::NONE)
+			.auto_discovery(None)
+			.build()
+			.unwrap()
+	}
+
+	fn wait_for_bulb(mgr: &udp::Manager) {
+		for _ in 0..100 {
+			if mgr.get(MacAddress([1; 6])).is_some() {
+				return;
+			}
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		panic!("the bulb wasn't found");
+	}
+
+	/// The next message that arrives on `sock`, and where it came from.
+	fn recv_raw(sock: &std::net::UdpSocket) -> (RawMessage, std::net::SocketAddr) {
+		let mut buf = [0; 1024];
+		let (nbytes, addr) = sock.recv_from(&mut buf).unwrap();
+		(RawMessage::unpack(&buf[..nbytes]).unwrap(), addr)
+	}
+
+	/// Sends `message` to `addr` as the bulb `target`'s reply to `request`.
+	fn reply(
+		sock: &std::net::UdpSocket,
+		addr: std::net::SocketAddr,
+		request: &RawMessage,
+		target: MacAddress,
+		message: Message,
+	) {
+		let options = BuildOptions {
+			target: Some(target),
+			sequence: request.frame_addr.sequence,
+			source: request.frame.source,
+			..Default::default()
+		};
+		let bytes = RawMessage::build(&options, message).unwrap().pack().unwrap();
+		sock.send_to(&bytes, addr).unwrap();
+	}
+
 	#[test]
 	fn test_frame() {
 		let frame = Frame {
@@ -135,7 +250,7 @@ mod tests {
 	#[test]
 	fn test_frame_address() {
 		let frame = FrameAddress {
-			target: 0x11224488,
+			target: MacAddress::from(0x11224488),
 			reserved: [0; 6],
 			reserved2: 0,
 			ack_required: true,
@@ -152,6 +267,362 @@ mod tests {
 		assert_eq!(frame, unpacked);
 	}
 
+	#[test]
+	fn test_codec() {
+		// the layout of a button target depends on its type
+		let target = ButtonTarget::DeviceRelays {
+			serial: [1, 2, 3, 4, 5, 6],
+			relays_count: 2,
+			relays: [0, 1, 0, 0, 0, 0, 0, 0, 0],
+		};
+		let mut buf = Vec::new();
+		target.encode(&mut buf).unwrap();
+		assert_eq!(buf.len(), 18);
+		assert_eq!(&buf[..2], &[7, 0]);
+		assert_eq!(ButtonTarget::decode(&mut buf.as_slice()).unwrap(), target);
+
+		let mut buf = Vec::new();
+		[HSBK::white(Kelvin::Neutral, 1.0); 3].encode(&mut buf).unwrap();
+		assert_eq!(buf.len(), 24);
+		assert_eq!(<[HSBK; 3]>::decode(&mut buf.as_slice()).unwrap()[2].kelvin, 3500);
+
+		// invalid values are reported as the error their conversion gave
+		let e = ApplicationRequest::decode(&mut [3u8].as_slice()).unwrap_err();
+		assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+		assert!(matches!(
+			Error::from_read(e, 501, "apply", 8, 15),
+			Error::InvalidEnumValue {
+				typ: Some(501),
+				field: "apply",
+				value: 3,
+			}
+		));
+	}
+
+	#[test]
+	fn test_lifx_string() {
+		use std::convert::TryFrom;
+
+		let s = LifxString::new("Kitchen");
+		assert_eq!(s, *"Kitchen");
+		assert_eq!(&s.as_bytes()[..8], b"Kitchen\0");
+
+		// 31 bytes of ASCII followed by a two-byte character, which doesn't fit
+		let long = format!("{}é", "a".repeat(31));
+		let s = LifxString::new(&long);
+		assert_eq!(s.to_str(), "a".repeat(31));
+		assert_eq!(s.as_bytes()[31], 0);
+
+		assert_eq!(LifxString::try_from(&long[..31]).unwrap().to_str(), &long[..31]);
+		assert!(matches!(
+			LifxString::try_from(long.as_str()),
+			Err(Error::StringTooLong { len: 33 })
+		));
+
+		let s = LifxString::new("Küche");
+		let mut buf = Vec::new();
+		s.encode(&mut buf).unwrap();
+		assert_eq!(buf.len(), 32);
+		let decoded = LifxString::decode(&mut buf.as_slice()).unwrap();
+		assert_eq!(decoded, s);
+		assert_eq!(decoded.to_string(), "Küche");
+
+		let mut bytes = [0; 32];
+		bytes[..3].copy_from_slice(&[b'a', 0xff, b'b']);
+		let s = LifxString::from_bytes(bytes);
+		assert_eq!(s.to_str(), "a\u{fffd}b");
+		assert_eq!(s.as_bytes(), &bytes);
+	}
+
+	#[test]
+	fn test_mac_address() {
+		let mac: MacAddress = "d0:73:d5:02:97:de".parse().unwrap();
+		assert_eq!(mac, MacAddress::from_bytes([0xd0, 0x73, 0xd5, 0x02, 0x97, 0xde]));
+		assert_eq!(mac.to_string(), "d0:73:d5:02:97:de");
+		assert_eq!(u64::from(mac), 0x0000_de97_02d5_73d0);
+		assert_eq!(MacAddress::from(0x0000_de97_02d5_73d0), mac);
+		assert_eq!("D0-73-D5-02-97-DE".parse::<MacAddress>().unwrap(), mac);
+
+		assert!("d0:73:d5:02:97".parse::<MacAddress>().is_err());
+		assert!("d0:73:d5:02:97:de:00".parse::<MacAddress>().is_err());
+		assert!("d0:73:d5:02:97:zz".parse::<MacAddress>().is_err());
+		assert!("d0:73:d5:02:97:d".parse::<MacAddress>().is_err());
+	}
+
+	#[test]
+	fn test_kelvin() {
+		use std::convert::TryFrom;
+
+		assert_eq!(u16::from(Kelvin::Neutral), 3500);
+		assert_eq!(HSBK::white(Kelvin::Neutral, 0.8), HSBK::white(3500u16, 0.8));
+		assert_eq!(Kelvin::try_from(2700).unwrap(), Kelvin::Incandescent);
+		assert!(matches!(
+			Kelvin::try_from(2701),
+			Err(Error::InvalidEnumValue { value: 2701, .. })
+		));
+
+		assert_eq!(Kelvin::nearest(0), Kelvin::Candlelight);
+		assert_eq!(Kelvin::nearest(3300), Kelvin::Neutral);
+		assert_eq!(Kelvin::nearest(3250), Kelvin::Warm);
+		assert_eq!(Kelvin::nearest(u16::MAX), Kelvin::BlueIce);
+	}
+
+	#[test]
+	fn test_perceived_brightness() {
+		let white = HSBK::white(Kelvin::Neutral, 1.0);
+		assert_eq!(white.with_perceived_brightness(0.0).brightness, 0);
+		assert_eq!(white.with_perceived_brightness(1.0).brightness, 65535);
+		assert_eq!(white.with_perceived_brightness(2.0).brightness, 65535);
+
+		// looking half as bright takes much less than half the output
+		let half = white.with_perceived_brightness(0.5);
+		assert_eq!(half.kelvin, 3500);
+		assert!((half.brightness as f32 / 65535.0 - 0.184).abs() < 0.001);
+
+		for step in 0..=20 {
+			let p = step as f32 / 20.0;
+			let back = white.with_perceived_brightness(p).perceived_brightness();
+			assert!((back - p).abs() < 0.001, "{} != {}", back, p);
+		}
+	}
+
+	#[test]
+	fn test_xy_and_mired() {
+		let close = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| {
+			(x1 - x2).abs() < 0.002 && (y1 - y2).abs() < 0.002
+		};
+
+		// the sRGB primaries, and points on the black-body curve
+		assert!(close(HSBK::color(0, 1.0, 1.0).to_xy(), (0.64, 0.33)));
+		assert!(close(HSBK::color(120, 1.0, 1.0).to_xy(), (0.30, 0.60)));
+		assert!(close(HSBK::color(240, 1.0, 1.0).to_xy(), (0.15, 0.06)));
+		assert!(close(HSBK::white(6500u16, 1.0).to_xy(), (0.3135, 0.3237)));
+		assert!(close(HSBK::white(Kelvin::Incandescent, 1.0).to_xy(), (0.4599, 0.4106)));
+
+		let red = HSBK::from_xy(0.64, 0.33, 0.5);
+		assert!(red.hue < 100 || red.hue > 65436);
+		assert!(red.saturation > 65400);
+		assert_eq!(red.brightness, 32768);
+
+		for hue in (0..360).step_by(30) {
+			let color = HSBK::color(hue, 0.6, 1.0);
+			let (x, y) = color.to_xy();
+			assert!(close(HSBK::from_xy(x, y, 1.0).to_xy(), (x, y)), "hue {}", hue);
+		}
+
+		let white = HSBK::from_xy(0.4599, 0.4106, 1.0);
+		assert!((white.kelvin as i32 - 2700).abs() < 20);
+
+		assert_eq!(kelvin_to_mired(6500), 154);
+		assert_eq!(kelvin_to_mired(2000), 500);
+		assert_eq!(kelvin_to_mired(0), u16::MAX);
+		assert_eq!(mired_to_kelvin(153), 6536);
+		assert_eq!(mired_to_kelvin(500), 2000);
+	}
+
+	#[test]
+	fn test_hsbk_adjustments() {
+		let color = HSBK::color(0, 0.5, 0.5);
+
+		assert_eq!(color.rotate_hue(90.0).hue, 16384);
+		assert_eq!(color.rotate_hue(-90.0).hue, 49152);
+		assert_eq!(color.rotate_hue(360.0).hue, color.hue);
+		assert_eq!(color.rotate_hue(450.0).hue, 16384);
+
+		assert_eq!(color.scale_brightness(0.5).brightness, 16384);
+		assert_eq!(color.scale_brightness(4.0).brightness, 65535);
+		assert_eq!(color.scale_brightness(-1.0).brightness, 0);
+		assert_eq!(color.saturate(2.0).saturation, 65534);
+		assert_eq!(color.saturate(0.0).saturation, 0);
+
+		let white = HSBK::white(Kelvin::Warm, 1.0);
+		assert_eq!(white.shift_kelvin(-250, 2500..=9000).kelvin, 2750);
+		assert_eq!(white.shift_kelvin(-1000, 2500..=9000).kelvin, 2500);
+		assert_eq!(white.shift_kelvin(10000, 2500..=9000).kelvin, 9000);
+
+		// everything else is left alone, so the adjustments can be chained
+		let adjusted = color.rotate_hue(120.0).scale_brightness(2.0).saturate(0.5);
+		assert_eq!(adjusted.hue, 21845);
+		assert_eq!(adjusted.brightness, 65534);
+		assert_eq!(adjusted.saturation, 16384);
+		assert_eq!(adjusted.kelvin, color.kelvin);
+
+		let warm = HSBK::white(2000u16, 1.0);
+		let neon = get_product_info(1, 141).unwrap();
+		assert_eq!(warm.clamped_for(neon), warm);
+		let bulb = get_product_info(1, 1).unwrap();
+		assert_eq!(warm.clamped_for(bulb).kelvin, 2500);
+		let red = HSBK {
+			hue: 0,
+			saturation: u16::MAX,
+			brightness: u16::MAX,
+			kelvin: 2000,
+		};
+		let white_bulb = get_product_info(1, 10).unwrap();
+		assert_eq!(
+			red.clamped_for(white_bulb),
+			HSBK {
+				saturation: 0,
+				kelvin: 2700,
+				..red
+			}
+		);
+	}
+
+	#[test]
+	fn test_gradient() {
+		assert_eq!(Gradient::new().render(2), vec![HSBK::default(); 2]);
+
+		let red = HSBK::color(0, 1.0, 1.0);
+		let blue = HSBK::color(240, 1.0, 1.0);
+		let white = HSBK::white(Kelvin::Neutral, 1.0);
+
+		// stops can be added in any order
+		let gradient = Gradient::new()
+			.with_stop(1.0, white)
+			.with_stop(0.0, blue)
+			.with_stop(0.5, red);
+		assert_eq!(gradient.stops()[1], (0.5, red));
+
+		let zones = gradient.render(5);
+		assert_eq!(zones[0], blue);
+		assert_eq!(zones[2], red);
+		assert_eq!(zones[4], white);
+
+		// blue to red goes through magenta, not green
+		assert_eq!(zones[1], blue.lerp(red, 0.5));
+		assert!(zones[1].hue > blue.hue);
+
+		// fading to white keeps the hue, rather than sweeping back towards the white's hue of 0
+		let gradient = Gradient::new().with_stop(0.0, blue).with_stop(1.0, white);
+		let mid = gradient.color_at(0.5);
+		assert_eq!(mid.hue, blue.hue);
+		assert_eq!(mid.saturation, 32768);
+
+		// a single zone takes the start of the gradient
+		assert_eq!(gradient.render(1), vec![blue]);
+		let mut colors = [HSBK::default(); 82];
+		gradient.render_into(&mut colors[..3]);
+		assert_eq!(colors[1], mid);
+		assert_eq!(colors[3], HSBK::default());
+	}
+
+	#[test]
+	fn test_hsbk_lerp() {
+		let a = HSBK {
+			hue: 60000,
+			saturation: 0,
+			brightness: 65535,
+			kelvin: 2500,
+		};
+		let b = HSBK {
+			hue: 5000,
+			saturation: 65535,
+			brightness: 0,
+			kelvin: 9000,
+		};
+		assert_eq!(a.lerp(b, 0.0), a);
+		assert_eq!(a.lerp(b, 1.0), b);
+		assert_eq!(a.lerp(b, 2.0), b);
+
+		// the hue wraps around zero, rather than going back through the middle of the range
+		let mid = a.lerp(b, 0.5);
+		assert_eq!(mid.hue, 65268);
+		assert_eq!(mid.saturation, 32768);
+		assert_eq!(mid.brightness, 32768);
+		assert_eq!(mid.kelvin, 5750);
+		assert_eq!(b.lerp(a, 0.5).hue, 65268);
+		assert_eq!(a.lerp(b, 0.25).hue, 62634);
+	}
+
+	#[test]
+	fn test_product_info() {
+		let neon = get_product_info(1, 141).unwrap();
+		assert_eq!(neon.name, "LIFX Neon US");
+		assert!(neon.multizone);
+
+		let candle = get_product_info(1, 137).unwrap();
+		assert!(candle.matrix);
+		assert!(!get_product_info(1, 115).unwrap().color);
+		assert!(get_product_info(1, 9999).is_none());
+
+		assert_eq!(neon.temperature_range, Some((1500, 9000)));
+		assert_eq!(neon.clamp_kelvin(1000), 1500);
+		assert_eq!(neon.clamp_kelvin(3500), 3500);
+		let white = get_product_info(1, 127).unwrap();
+		assert_eq!(white.clamp_kelvin(6500), 2700);
+		assert_eq!(get_product_info(1, 115).unwrap().clamp_kelvin(6500), 6500);
+
+		let z = get_product_info(1, 32).unwrap();
+		assert!(z.extended_multizone);
+		assert_eq!(z.min_ext_mz_firmware_components, Some((2, 77)));
+		assert!(!z.supports_extended_multizone(2 << 16 | 76));
+		assert!(z.supports_extended_multizone(2 << 16 | 77));
+		assert!(z.supports_extended_multizone(3 << 16));
+		assert!(neon.supports_extended_multizone(0));
+		assert!(!get_product_info(1, 31).unwrap().supports_extended_multizone(3 << 16));
+		assert!(!candle.supports_extended_multizone(3 << 16));
+
+		let clean = get_product_info(1, 90).unwrap();
+		assert!(clean.hev && !clean.relays && !clean.buttons);
+		let switch = get_product_info(1, 70).unwrap();
+		assert!(switch.relays && switch.buttons && !switch.hev);
+		assert!(!neon.hev && !neon.relays && !neon.buttons);
+	}
+
+	#[test]
+	fn test_vendor() {
+		assert_eq!(Vendor::from(1), Vendor::Lifx);
+		assert_eq!(u32::from(Vendor::Other(42)), 42);
+
+		static STRIP: ProductInfo = ProductInfo {
+			name: "Third-party strip",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((2700, 6500)),
+		};
+		assert!(get_product_info(42, 7).is_none());
+		assert!(register_product(Vendor::Other(42), 7, &STRIP).is_none());
+		assert_eq!(get_product_info(42, 7).unwrap().name, "Third-party strip");
+		assert!(register_product(Vendor::Other(42), 7, &STRIP).is_some());
+
+		// the built-in table wins
+		register_product(Vendor::Lifx, 1, &STRIP);
+		assert_eq!(get_product_info(1, 1).unwrap().name, "LIFX Original 1000");
+	}
+
+	#[test]
+	fn test_time_conversion() {
+		use std::time::Duration;
+
+		assert_eq!(time::to_millis(Duration::from_millis(1500)), 1500);
+		assert_eq!(time::to_millis(Duration::from_secs(u64::MAX)), u32::MAX);
+		assert_eq!(time::from_millis(250), Duration::from_millis(250));
+		assert_eq!(time::to_nanos(Duration::from_secs(u64::MAX)), u64::MAX);
+
+		let t = std::time::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+		assert_eq!(time::from_system_time(t), 1_600_000_000_000_000_000);
+		assert_eq!(time::to_system_time(1_600_000_000_000_000_000), t);
+		assert_eq!(
+			time::from_system_time(std::time::UNIX_EPOCH - Duration::from_secs(1)),
+			0
+		);
+
+		match Message::light_set_color(HSBK::white(Kelvin::Neutral, 1.0), Duration::from_secs(2)) {
+			Message::LightSetColor { duration, .. } => assert_eq!(duration, 2000),
+			msg => panic!("unexpected message {:?}", msg),
+		}
+	}
+
 	#[test]
 	fn test_decode_frame_address() {
 		//   1  2  3  4  5  6  7  8  9  10 11 12 13 14 15 16
@@ -227,43 +698,2597 @@ mod tests {
 	}
 
 	#[test]
-	fn test_build_a_packet() {
-		// packet taken from https://lan.developer.lifx.com/docs/building-a-lifx-packet
+	fn test_vectors() {
+		for vector in test_vectors::all() {
+			let raw = RawMessage::unpack_with(vector.bytes, &DecodeOptions::default()).unwrap();
+			assert_eq!(raw.frame_addr.target, vector.target, "{}", vector.name);
+			assert_eq!(raw.frame.source, vector.source, "{}", vector.name);
+			assert_eq!(raw.frame_addr.sequence, vector.sequence, "{}", vector.name);
 
-		let msg = Message::LightSetColor {
-			reserved: 0,
-			color: HSBK {
-				hue: 21845,
-				saturation: 0xffff,
-				brightness: 0xffff,
-				kelvin: 3500,
+			let msg = Message::from_raw(&raw).unwrap();
+			assert_eq!(msg, vector.message, "{}", vector.name);
+		}
+	}
+
+	#[test]
+	fn test_unpack_invalid_frame() {
+		let raw = RawMessage::build(&BuildOptions::default(), Message::GetService).unwrap();
+		let mut bytes = raw.pack().unwrap();
+		// clear the `addressable` bit
+		bytes[3] &= !0x10;
+
+		assert!(RawMessage::unpack(&bytes).is_err());
+
+		let frame = Frame::unpack(&bytes).unwrap();
+		assert!(frame.try_validate().is_err());
+	}
+
+	#[test]
+	fn test_unpack_truncated() {
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
 			},
-			duration: 1024,
-		};
+		)
+		.unwrap();
+		let bytes = raw.pack().unwrap();
+
+		for len in 0..bytes.len() {
+			assert!(RawMessage::unpack(&bytes[..len]).is_err());
+		}
+
+		// a frame claiming to be smaller than its own headers
+		let mut bytes = bytes;
+		bytes[0] = 10;
+		bytes[1] = 0;
+		assert!(RawMessage::unpack(&bytes).is_err());
+	}
 
+	#[test]
+	fn test_unpack_partial() {
 		let raw = RawMessage::build(
-			&BuildOptions {
-				target: None,
-				ack_required: false,
-				res_required: false,
-				sequence: 0,
-				source: 0,
+			&BuildOptions::default(),
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
 			},
-			msg,
 		)
 		.unwrap();
+		let mut bytes = raw.pack().unwrap();
+		let len = bytes.len();
+		bytes.extend(&[0xde, 0xad, 0xbe, 0xef]);
 
-		let bytes = raw.pack().unwrap();
-		println!("{:?}", bytes);
-		assert_eq!(bytes.len(), 49);
+		let (unpacked, consumed) = RawMessage::unpack_partial(&bytes).unwrap();
+		assert_eq!(consumed, len);
+		assert_eq!(unpacked, raw);
+
+		assert!(RawMessage::unpack_partial(&bytes[..len - 1]).is_err());
+		assert!(matches!(
+			RawMessage::unpack(&bytes),
+			Err(Error::SizeMismatch {
+				claimed: 68,
+				actual: 72
+			})
+		));
+	}
+
+	#[test]
+	fn test_raw_message_ref() {
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
+			},
+		)
+		.unwrap();
+		let mut bytes = raw.pack().unwrap();
+
+		let borrowed = RawMessageRef::unpack(&bytes).unwrap();
+		assert_eq!(borrowed.payload, &raw.payload[..]);
+		assert_eq!(RawMessage::from(borrowed), raw);
 		assert_eq!(
-			bytes,
-			vec![
-				0x31, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-				0x00, 0x00, 0x00, 0x00, 0x66, 0x00, 0x00, 0x00, 0x00, 0x55, 0x55, 0xFF, 0xFF, 0xFF,
-				0xFF, 0xAC, 0x0D, 0x00, 0x04, 0x00, 0x00
-			]
+			Message::from_raw_ref(&borrowed).unwrap(),
+			Message::from_raw(&raw).unwrap()
 		);
+
+		bytes.extend_from_slice(&[0xff; 4]);
+		assert!(RawMessageRef::unpack(&bytes).is_err());
+		let (borrowed, size) = RawMessageRef::unpack_partial(&bytes).unwrap();
+		assert_eq!(size, raw.packed_size());
+		assert_eq!(borrowed.payload.len(), 32);
+	}
+
+	#[test]
+	fn test_pack_size_mismatch() {
+		let mut raw = RawMessage::build(&BuildOptions::default(), Message::GetService).unwrap();
+		raw.payload.push(0);
+		assert!(matches!(raw.pack(), Err(Error::SizeMismatch { .. })));
+
+		raw.update_size().unwrap();
+		assert_eq!(raw.pack().unwrap().len(), 37);
+	}
+
+	#[test]
+	fn test_send_queue_reuses_buffers() {
+		use udp::{Outgoing, Priority, SendQueue};
+
+		let queue = SendQueue::new();
+		let raw = RawMessage::build(&BuildOptions::default(), Message::GetLabel).unwrap();
+		let outgoing = |bytes| Outgoing {
+			target: MacAddress::ALL,
+			addr: "127.0.0.1:56700".parse().unwrap(),
+			typ: raw.protocol_header.typ,
+			bytes,
+		};
+
+		let bytes = queue.pack(&raw).unwrap();
+		assert_eq!(bytes, raw.pack().unwrap());
+		let ptr = bytes.as_ptr();
+		queue.push(Priority::Background, outgoing(bytes));
+		let sent = queue.pop().unwrap();
+		queue.recycle(sent.bytes);
+
+		// the next message is packed into the buffer of the one that was sent
+		let bytes = queue.pack(&raw).unwrap();
+		assert_eq!(bytes.as_ptr(), ptr);
+
+		// as is the next one after that, once this one is superseded in the queue
+		queue.push(Priority::Background, outgoing(bytes));
+		let newer = queue.pack(&raw).unwrap();
+		queue.push(Priority::Background, outgoing(newer));
+		let bytes = queue.pack(&raw).unwrap();
+		assert_eq!(bytes.as_ptr(), ptr);
+	}
+
+	#[test]
+	fn test_send_queue_close() {
+		use udp::{Outgoing, Priority, SendQueue};
+
+		let queue = SendQueue::new();
+		let outgoing = Outgoing {
+			target: MacAddress::ALL,
+			addr: "127.0.0.1:56700".parse().unwrap(),
+			typ: 23,
+			bytes: vec![],
+		};
+		queue.push(Priority::Background, outgoing.clone());
+
+		let waiting = {
+			let queue = queue.clone();
+			std::thread::spawn(move || (queue.pop().is_some(), queue.pop().is_some()))
+		};
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		queue.close();
+		assert_eq!(waiting.join().unwrap(), (true, false));
+
+		queue.push(Priority::Interactive, outgoing);
+		assert!(queue.is_empty());
+		assert!(queue.try_pop().is_none());
+	}
+
+	#[test]
+	fn test_bulb_helpers() {
+		use udp::Color;
+
+		let (queue, mut bulb) = test_bulb();
+
+		// needs a cached color first
+		assert!(bulb.set_brightness(0.5, Default::default()).is_err());
+		bulb.set_model(1, 1);
+		let red = HSBK::color(0, 1.0, 1.0);
+		if let Color::Single(ref mut color) = bulb.color {
+			color.update(red);
+		}
+		bulb.power_level.update(PowerLevel::Standby);
+
+		bulb.set_brightness(0.5, Default::default()).unwrap();
+		match &drain(&queue)[..] {
+			[Message::LightSetColor { color, .. }] => {
+				assert_eq!(*color, HSBK { brightness: 32768, ..red })
+			}
+			msgs => panic!("unexpected {:?}", msgs),
+		}
+
+		// dimming a bulb that's off to a non-zero level turns it on too
+		bulb.adjust_brightness(-0.25, Default::default()).unwrap();
+		assert_eq!(drain(&queue), [
+			Message::light_set_color(HSBK { brightness: 49151, ..red }, Default::default()),
+			Message::SetPower {
+				level: PowerLevel::Enabled
+			},
+		]);
+		bulb.dim_to(0.0, Default::default()).unwrap();
+		assert_eq!(drain(&queue), [Message::light_set_color(
+			HSBK { brightness: 0, ..red },
+			Default::default()
+		)]);
+
+		bulb.to_white(2700, Default::default()).unwrap();
+		assert_eq!(drain(&queue), [Message::light_set_color(
+			HSBK {
+				saturation: 0,
+				kelvin: 2700,
+				..red
+			},
+			Default::default()
+		)]);
+		bulb.to_color(120, 2.0, Default::default()).unwrap();
+		let green = HSBK::color(120, 1.0, 1.0);
+		assert_eq!(drain(&queue), [Message::light_set_color(
+			HSBK { kelvin: red.kelvin, ..green },
+			Default::default()
+		)]);
+
+		let snapshot = bulb.snapshot();
+		assert_eq!(snapshot.color, Some(red));
+		assert_eq!(snapshot.power, Some(PowerLevel::Standby));
+		assert_eq!(snapshot.product.map(|info| info.name), Some("LIFX Original 1000"));
+		assert_eq!(snapshot.label, None);
+		bulb.name.update("Lamp".to_owned());
+		assert_eq!(bulb.label(), Some("Lamp"));
+		assert_eq!(bulb.zones(), None);
+
+		bulb.set_label("Kitchen").unwrap();
+		assert_eq!(drain(&queue), [Message::SetLabel {
+			label: LifxString::new("Kitchen")
+		}]);
+		assert!(bulb.set_label(&"x".repeat(33)).is_err());
+		assert!(queue.is_empty());
+	}
+
+	#[test]
+	fn test_bulb_zones() {
+		let (queue, mut bulb) = test_bulb();
+		let zones = |start_index, end_index, color, apply| Message::SetColorZones {
+			start_index,
+			end_index,
+			color,
+			duration: 0,
+			apply,
+		};
+
+		let red = HSBK::color(0, 1.0, 1.0);
+		let blue = HSBK::color(43690, 1.0, 1.0);
+		bulb.set_model(1, 31);
+		bulb.set_zones(2..=5, red, Default::default()).unwrap();
+		assert_eq!(drain(&queue), [zones(2, 5, red, ApplicationRequest::Apply)]);
+
+		bulb.set_zone_colors(&[red, red, blue, red], Default::default()).unwrap();
+		assert_eq!(drain(&queue), [
+			zones(0, 1, red, ApplicationRequest::NoApply),
+			zones(2, 2, blue, ApplicationRequest::NoApply),
+			zones(3, 3, red, ApplicationRequest::Apply),
+		]);
+
+		assert!(bulb.set_zone_colors(&[red; 257], Default::default()).is_err());
+		bulb.set_model(1, 1);
+		assert!(bulb.set_zones(0..=0, red, Default::default()).is_err());
+		assert!(queue.is_empty());
+	}
+
+	#[test]
+	fn test_bulb_extended_zones() {
+		use std::time::Duration;
+
+		let (queue, mut bulb) = test_bulb();
+
+		let red = HSBK::color(0, 1.0, 1.0);
+		bulb.set_model(1, 32);
+		bulb.host_firmware.update(2 << 16 | 76);
+		assert!(!bulb.supports_extended_multizone());
+		bulb.set_zone_colors(&[red; 2], Default::default()).unwrap();
+		let sent: Vec<MessageType> = drain(&queue).iter().map(Message::message_type).collect();
+		assert_eq!(sent, [MessageType::SetColorZones]);
+
+		bulb.host_firmware.update(2 << 16 | 77);
+		assert!(bulb.supports_extended_multizone());
+		bulb.set_zone_colors(&[red; 100], Default::default()).unwrap();
+		match &drain(&queue)[..] {
+			[
+				Message::SetExtendedColorZones {
+					apply: ApplicationRequest::NoApply,
+					zone_index: 0,
+					colors_count: 82,
+					colors: first,
+					..
+				},
+				Message::SetExtendedColorZones {
+					apply: ApplicationRequest::Apply,
+					zone_index: 82,
+					colors_count: 18,
+					colors: second,
+					..
+				},
+			] => {
+				assert!(first.iter().all(|c| *c == red));
+				assert!(second[..18].iter().all(|c| *c == red));
+			}
+			msgs => panic!("unexpected {:?}", msgs),
+		}
+
+		bulb.query_for_missing_info(Duration::from_secs(1)).unwrap();
+		let queries: Vec<MessageType> = drain(&queue).iter().map(Message::message_type).collect();
+		assert!(queries.contains(&MessageType::GetExtendedColorZones));
+		assert!(!queries.contains(&MessageType::GetColorZones));
+	}
+
+	#[test]
+	fn test_bulb_tiles() {
+		use std::time::Duration;
+		use udp::Color;
+
+		let (queue, mut bulb) = test_bulb();
+		let queries = |bulb: &mut udp::Bulb| {
+			bulb.query_for_missing_info(Duration::from_secs(1)).unwrap();
+			drain(&queue)
+		};
+
+		bulb.set_model(1, 55);
+		let sent_queries = queries(&mut bulb);
+		assert!(sent_queries.contains(&Message::GetDeviceChain));
+		assert!(!sent_queries.iter().any(|q| q.message_type() == MessageType::Get64));
+
+		let tile = Tile {
+			width: 8,
+			height: 8,
+			..Default::default()
+		};
+		let red = HSBK::color(0, 1.0, 1.0);
+		match bulb.color {
+			Color::Tile(ref mut tiles) => {
+				assert!(tiles.update_chain(0, &[tile; 2]));
+				assert!(!tiles.update_chain(0, &[tile; 2]));
+			}
+			ref color => panic!("unexpected {:?}", color),
+		}
+		assert!(queries(&mut bulb).contains(&Message::Get64 {
+			tile_index: 0,
+			length: 2,
+			reserved: 0,
+			x: 0,
+			y: 0,
+			width: 8,
+		}));
+		if let Color::Tile(ref mut tiles) = bulb.color {
+			assert!(tiles.update_pixels(1, 0, 0, 8, &[red; 64]));
+			assert!(!tiles.update_pixels(1, 0, 0, 8, &[red; 64]));
+			let pixels = tiles.pixels.as_ref().unwrap();
+			assert_eq!(pixels.len(), 2);
+			assert_eq!(pixels[0], None);
+			assert_eq!(pixels[1], Some([red; 64]));
+		}
+
+		bulb.set_tiles(&[[red; 64]; 2], Default::default()).unwrap();
+		let set64 = |tile_index| Message::Set64 {
+			tile_index,
+			length: 1,
+			reserved: 0,
+			x: 0,
+			y: 0,
+			width: 8,
+			duration: 0,
+			colors: [red; 64],
+		};
+		assert_eq!(drain(&queue), [set64(0), set64(1)]);
+
+		bulb.set_model(1, 1);
+		assert!(bulb.set_tile(0, &[red; 64], Default::default()).is_err());
+	}
+
+	#[test]
+	fn test_bulb_infrared() {
+		use std::time::Duration;
+
+		let (queue, mut bulb) = test_bulb();
+		let queries = |bulb: &mut udp::Bulb| {
+			bulb.query_for_missing_info(Duration::from_secs(1)).unwrap();
+			drain(&queue).iter().map(Message::message_type).collect::<Vec<_>>()
+		};
+
+		bulb.set_model(1, 1);
+		assert!(!queries(&mut bulb).contains(&MessageType::LightGetInfrared));
+		assert!(bulb.set_infrared(100).is_err());
+
+		let mut bulb = udp::Bulb::new(0, MacAddress([2; 6]), queue.clone(), bulb.addr);
+		bulb.set_model(1, 29);
+		assert!(queries(&mut bulb).contains(&MessageType::LightGetInfrared));
+		bulb.set_infrared(100).unwrap();
+		assert_eq!(drain(&queue), [Message::LightSetInfrared { brightness: 100 }]);
+	}
+
+	#[test]
+	fn test_bulb_clean_cycle() {
+		use std::time::Duration;
+
+		let (queue, mut bulb) = test_bulb();
+
+		bulb.set_model(1, 1);
+		assert!(bulb.start_clean_cycle(Duration::from_secs(60)).is_err());
+
+		bulb.set_model(1, 90);
+		bulb.start_clean_cycle(Duration::from_millis(7_200_400)).unwrap();
+		bulb.stop_clean_cycle().unwrap();
+		assert_eq!(drain(&queue), [
+			Message::SetHevCycle {
+				enable: true,
+				duration: 7200,
+			},
+			Message::SetHevCycle {
+				enable: false,
+				duration: 0,
+			},
+		]);
+
+		bulb.query_for_missing_info(Duration::from_secs(1)).unwrap();
+		let queries: Vec<MessageType> = drain(&queue).iter().map(Message::message_type).collect();
+		assert!(queries.contains(&MessageType::GetHevCycle));
+		assert!(queries.contains(&MessageType::GetLastHevCycleResult));
+	}
+
+	#[test]
+	fn test_wifi_signal() {
+		use udp::{SignalQuality, WifiSignal};
+
+		let quality = |signal: f32| WifiSignal::from_signal(signal).quality;
+		// RSSI, from newer firmware
+		assert_eq!(WifiSignal::from_signal(1e-5).rssi, -50);
+		assert_eq!(quality(1e-5), SignalQuality::Good);
+		assert_eq!(quality(1e-7), SignalQuality::SomewhatBad);
+		assert_eq!(quality(1e-9), SignalQuality::VeryBad);
+		assert_eq!(quality(1e20), SignalQuality::NoSignal);
+		// signal-to-noise ratio, from older firmware
+		assert_eq!(quality(10.0), SignalQuality::SomewhatBad);
+		assert_eq!(quality(20.0), SignalQuality::Alright);
+		assert_eq!(quality(100.0), SignalQuality::Good);
+		assert_eq!(quality(1.0), SignalQuality::NoSignal);
+	}
+
+	#[test]
+	fn test_runtime_info() {
+		use std::time::Duration;
+		use udp::RuntimeInfo;
+
+		let info = |uptime, last_downtime| RuntimeInfo {
+			uptime: Duration::from_secs(uptime),
+			last_downtime: Duration::from_secs(last_downtime),
+		};
+		assert!(!info(120, 5).restarted_since(&info(60, 5)));
+		assert!(info(10, 5).restarted_since(&info(60, 5)));
+		assert!(info(120, 30).restarted_since(&info(60, 5)));
+
+		let (_, mut bulb) = test_bulb();
+		assert_eq!(bulb.uptime(), None);
+		bulb.runtime.update(info(120, 30));
+		assert_eq!(bulb.uptime(), Some(Duration::from_secs(120)));
+		assert_eq!(bulb.last_downtime(), Some(Duration::from_secs(30)));
+	}
+
+	#[test]
+	fn test_refresh_policies() {
+		use std::time::Duration;
+		use udp::{RefreshPolicies, RefreshPolicy};
+
+		let (queue, mut bulb) = test_bulb();
+		let queries = |bulb: &mut udp::Bulb| {
+			bulb.query_for_missing_info(Duration::ZERO).unwrap();
+			drain(&queue).iter().map(Message::message_type).collect::<Vec<_>>()
+		};
+
+		bulb.set_refresh_policies(RefreshPolicies {
+			name: RefreshPolicy::Never,
+			power: RefreshPolicy::OnDemand,
+			color: RefreshPolicy::OnDemand,
+			..Default::default()
+		});
+		let sent = queries(&mut bulb);
+		assert!(sent.contains(&MessageType::GetLabel));
+		assert!(!sent.contains(&MessageType::GetPower));
+
+		// policies carry over to the color once the model is known
+		bulb.set_model(1, 1);
+		assert_eq!(bulb.name.policy(), RefreshPolicy::Never);
+		if let udp::Color::Single(ref color) = bulb.color {
+			assert_eq!(color.policy(), RefreshPolicy::OnDemand);
+		}
+		bulb.name.update("Lamp".to_owned());
+		let sent = queries(&mut bulb);
+		assert!(!sent.contains(&MessageType::GetLabel));
+		assert!(!sent.contains(&MessageType::LightGet));
+
+		bulb.power_level.mark_stale();
+		assert!(queries(&mut bulb).contains(&MessageType::GetPower));
+	}
+
+	#[test]
+	fn test_bulb_sequences() {
+		use std::time::Duration;
+
+		let (queue, mut bulb) = test_bulb();
+		let other = udp::Bulb::new(0, MacAddress([2; 6]), queue.clone(), bulb.addr);
+		let sequences = || {
+			let mut sequences = Vec::new();
+			while let Some(outgoing) = queue.try_pop() {
+				let raw = RawMessage::unpack(&outgoing.bytes).unwrap();
+				sequences.push((raw.frame_addr.target, raw.frame_addr.sequence));
+			}
+			sequences
+		};
+
+		bulb.set_power(true, Default::default()).unwrap();
+		other.set_power(true, Default::default()).unwrap();
+		bulb.set_label("Lamp").unwrap();
+		assert_eq!(sequences(), [
+			(MacAddress([1; 6]), 0),
+			(MacAddress([2; 6]), 0),
+			(MacAddress([1; 6]), 1),
+		]);
+
+		// every refresh query gets its own number too
+		bulb.query_for_missing_info(Duration::from_secs(1)).unwrap();
+		let numbers: Vec<u8> = sequences().into_iter().map(|(_, sequence)| sequence).collect();
+		assert!(numbers.len() > 1);
+		assert_eq!(numbers, (2..2 + numbers.len() as u8).collect::<Vec<_>>());
+		assert_eq!(bulb.next_sequence(), 2 + numbers.len() as u8);
+	}
+
+	#[test]
+	fn test_random_source() {
+		let sources: Vec<u32> = (0..8).map(|_| random_source()).collect();
+		assert!(sources.iter().all(|&source| source != 0));
+		assert!(sources.iter().any(|&source| source != sources[0]));
+	}
+
+	#[test]
+	fn test_waveform_spec() {
+		use std::time::Duration;
+		use udp::WaveformSpec;
+
+		let red = HSBK::color(0, 1.0, 1.0);
+		let pulse = WaveformSpec::new(Waveform::Pulse, red)
+			.period(Duration::from_millis(250))
+			.cycles(4.0)
+			.skew(-2.0)
+			.transient(true);
+		assert_eq!(pulse.to_message(), Message::SetWaveform {
+			reserved: 0,
+			transient: true,
+			color: red,
+			period: 250,
+			cycles: 4.0,
+			skew_ratio: -i16::MAX,
+			waveform: Waveform::Pulse,
+		});
+
+		let breathe = WaveformSpec::new(Waveform::Sine, red).skew(0.5).keep_hue().keep_kelvin();
+		assert_eq!(breathe.to_message(), Message::SetWaveformOptional {
+			reserved: 0,
+			transient: false,
+			color: red,
+			period: 1000,
+			cycles: 1.0,
+			skew_ratio: 16384,
+			waveform: Waveform::Sine,
+			set_hue: false,
+			set_saturation: true,
+			set_brightness: true,
+			set_kelvin: false,
+		});
+	}
+
+	#[test]
+	fn test_effects() {
+		use std::time::Duration;
+		use udp::{Breathe, Effect, Pulse};
+
+		let (queue, mut bulb) = test_bulb();
+		bulb.set_model(1, 27);
+
+		// a breathe peaking late, from a given color, that stays on its color
+		let red = HSBK {
+			kelvin: 3500,
+			..HSBK::color(0, 1.0, 1.0)
+		};
+		let blue = HSBK {
+			kelvin: 3500,
+			..HSBK::color(240, 1.0, 1.0)
+		};
+		let breathe = Breathe {
+			from_color: Some(blue),
+			period: Duration::from_secs(2),
+			cycles: 3.0,
+			persist: true,
+			peak: 0.75,
+			..Breathe::new(red)
+		};
+		Effect::from(breathe).start(&bulb).unwrap();
+		assert_eq!(drain(&queue), [
+			Message::light_set_color(blue, Duration::from_secs(0)),
+			Message::SetPower {
+				level: PowerLevel::Enabled,
+			},
+			Message::SetWaveform {
+				reserved: 0,
+				transient: false,
+				color: red,
+				period: 2000,
+				cycles: 3.0,
+				skew_ratio: 16384,
+				waveform: Waveform::Sine,
+			},
+		]);
+
+		// a pulse from wherever the bulb is, leaving its power alone
+		let pulse = Pulse {
+			power_on: false,
+			..Pulse::new(red)
+		};
+		Effect::from(pulse).start(&bulb).unwrap();
+		assert_eq!(drain(&queue), [Message::SetWaveform {
+			reserved: 0,
+			transient: true,
+			color: red,
+			period: 1000,
+			cycles: 1.0,
+			skew_ratio: 0,
+			waveform: Waveform::Pulse,
+		}]);
+	}
+
+	#[test]
+	fn test_flicker() {
+		use std::{collections::HashMap, time::{Duration, Instant}};
+		use udp::{Animation, Flicker, InterfaceFilter, Manager};
+
+		let (queue, mut bulb) = test_bulb();
+		bulb.set_model(1, 27);
+		let mut bulbs = HashMap::new();
+		bulbs.insert(bulb.target, bulb);
+
+		// every change stays close to the color, and never brighter
+		let base = HSBK {
+			hue: 30000,
+			saturation: 30000,
+			brightness: 50000,
+			kelvin: 2500,
+		};
+		let tempo = Duration::from_millis(100);
+		let mut flicker = Flicker::new(MacAddress([1; 6])).color(base).intensity(1.0).tempo(tempo);
+		for _ in 0..50 {
+			let wait = flicker.frame(&bulbs, Instant::now()).unwrap().unwrap();
+			assert!(wait >= tempo / 2 && wait <= tempo * 3 / 2);
+			match &drain(&queue)[..] {
+				[Message::LightSetColor { color, duration, .. }] => {
+					assert_eq!(*duration as u128, wait.as_millis());
+					assert!((color.hue as i32 - base.hue as i32).abs() <= 1457);
+					assert!(color.brightness <= base.brightness);
+					assert!(color.brightness >= base.brightness / 5 * 2);
+					assert_eq!((color.saturation, color.kelvin), (base.saturation, base.kelvin));
+				}
+				msgs => panic!("unexpected {:?}", msgs),
+			}
+		}
+
+		// or just some zones, and nothing at all for unknown bulbs
+		bulbs.get_mut(&MacAddress([1; 6])).unwrap().set_model(1, 31);
+		let mut zones = Flicker::new(MacAddress([1; 6])).zones(2..=5);
+		zones.frame(&bulbs, Instant::now()).unwrap();
+		let zoned = drain(&queue);
+		assert!(matches!(zoned[..], [Message::SetColorZones { start_index: 2, end_index: 5, .. }]));
+		let mut missing = Flicker::new(MacAddress([2; 6]));
+		assert!(missing.frame(&bulbs, Instant::now()).unwrap().is_some());
+		assert!(queue.is_empty());
+
+		// the Manager runs it until it's cancelled
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mgr = Manager::bind_polled("127.0.0.1:0".parse().unwrap(), filter).unwrap();
+		let handle = mgr.animate(Flicker::new(MacAddress([1; 6])));
+		mgr.poll().unwrap();
+		assert!(!handle.is_finished());
+		handle.cancel();
+		mgr.poll().unwrap();
+		assert!(handle.is_finished());
+	}
+
+	#[test]
+	fn test_circadian() {
+		use std::{
+			collections::HashMap,
+			time::{Duration, Instant, UNIX_EPOCH},
+		};
+		use udp::{Animation, Circadian, DaySchedule, Hold};
+
+		// the sun is up at noon in a London summer, and down at midnight in winter, with dawn in
+		// between
+		let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+		let london = DaySchedule::Sun {
+			latitude: 51.48,
+			longitude: 0.0,
+		};
+		assert_eq!(london.daylight_at(at(1718971200)), 1.0);
+		assert_eq!(london.daylight_at(at(1734739200)), 0.0);
+		let dawn = london.daylight_at(at(1710914400));
+		assert!(dawn > 0.0 && dawn < 1.0);
+
+		// fixed schedules are in local time, and wrap around midnight
+		let hours = |h: u64| Duration::from_secs(h * 60 * 60);
+		let points = vec![(hours(6), 0.0), (hours(8), 1.0), (hours(20), 1.0), (hours(22), 0.0)];
+		let fixed = DaySchedule::Fixed {
+			points,
+			utc_offset: 60 * 60,
+		};
+		assert_eq!(fixed.daylight_at(at(6 * 60 * 60)), 0.5);
+		assert_eq!(fixed.daylight_at(at(12 * 60 * 60)), 1.0);
+		assert_eq!(fixed.daylight_at(at(0)), 0.0);
+
+		let (queue, mut bulb) = test_bulb();
+		bulb.set_model(1, 27);
+		let mut bulbs = HashMap::new();
+		bulbs.insert(bulb.target, bulb);
+
+		// always daytime, and only sent again when the color changes
+		let day = HSBK {
+			hue: 0,
+			saturation: 0,
+			brightness: 50000,
+			kelvin: 5000,
+		};
+		let mut circadian = Circadian::fixed(vec![(hours(0), 1.0)], 0)
+			.target(MacAddress([1; 6]))
+			.day(day);
+		let control = circadian.control();
+		let earlier = Instant::now() - Duration::from_secs(10);
+		assert!(circadian.frame(&bulbs, earlier).unwrap().is_some());
+		let expected = Message::LightSetColor { reserved: 0, color: day, duration: 1000 };
+		assert_eq!(drain(&queue), std::slice::from_ref(&expected));
+		circadian.frame(&bulbs, earlier).unwrap();
+		assert!(queue.is_empty());
+
+		// a bulb changed by hand is left alone until it's released
+		let dimmed = HSBK {
+			brightness: 10000,
+			..day
+		};
+		let bulb = bulbs.get_mut(&MacAddress([1; 6])).unwrap();
+		if let udp::Color::Single(ref mut color) = bulb.color {
+			color.update(dimmed);
+		}
+		circadian.frame(&bulbs, Instant::now()).unwrap();
+		assert!(queue.is_empty());
+		assert!(matches!(control.hold_on(MacAddress([1; 6])), Some(Hold::Manual(Some(_)))));
+		control.release(MacAddress([1; 6]));
+		circadian.frame(&bulbs, Instant::now()).unwrap();
+		assert_eq!(drain(&queue), [expected]);
+
+		// or held at another color
+		let red = HSBK {
+			kelvin: 3500,
+			..HSBK::color(0, 1.0, 1.0)
+		};
+		control.hold(MacAddress([1; 6]), red, None);
+		circadian.frame(&bulbs, Instant::now()).unwrap();
+		let expected = Message::LightSetColor { reserved: 0, color: red, duration: 60000 };
+		assert_eq!(drain(&queue), [expected]);
+	}
+
+	#[test]
+	fn test_color_cycle() {
+		use std::{collections::HashMap, time::{Duration, Instant}};
+		use udp::{Animation, ColorCycle, Easing};
+
+		let (queue, mut bulb) = test_bulb();
+		let mut other = udp::Bulb::new(0, MacAddress([2; 6]), queue.clone(), bulb.addr);
+		bulb.set_model(1, 27);
+		other.set_model(1, 27);
+		let mut bulbs = HashMap::new();
+		bulbs.insert(bulb.target, bulb);
+		bulbs.insert(other.target, other);
+
+		let palette: Vec<HSBK> = [0, 90, 180, 270]
+			.iter()
+			.map(|&hue| HSBK {
+				kelvin: 3500,
+				..HSBK::color(hue, 1.0, 1.0)
+			})
+			.collect();
+		let mut cycle = ColorCycle::new(palette.clone())
+			.targets([MacAddress([1; 6]), MacAddress([2; 6])])
+			.period(Duration::from_secs(4))
+			.step(Duration::from_secs(1))
+			.easing(Easing::Linear);
+		assert_eq!(cycle.color_at(0.125), Some(palette[0].lerp(palette[1], 0.5)));
+		assert_eq!(cycle.color_at(1.0), Some(palette[0]));
+
+		// each step fades every bulb to where it should be at the end of it, half a cycle apart
+		for (step, &(first, second)) in [(1, 3), (2, 0), (3, 1), (0, 2)].iter().enumerate() {
+			let wait = cycle.frame(&bulbs, Instant::now()).unwrap();
+			assert_eq!(wait, Some(Duration::from_secs(1)), "step {}", step);
+			let fade = |index: usize| Message::LightSetColor {
+				reserved: 0,
+				color: palette[index],
+				duration: 1000,
+			};
+			assert_eq!(drain(&queue), [fade(first), fade(second)]);
+		}
+
+		// with no colors there's nothing to do
+		let mut empty = ColorCycle::new(Vec::new()).target(MacAddress([1; 6]));
+		assert_eq!(empty.frame(&bulbs, Instant::now()).unwrap(), None);
+	}
+
+	#[test]
+	fn test_identify() {
+		use std::time::Duration;
+
+		let (queue, mut bulb) = test_bulb();
+		bulb.power_level.update(PowerLevel::Standby);
+		let flash = |message: &Message| {
+			matches!(message, Message::SetWaveform {
+				transient: true,
+				waveform: Waveform::Pulse,
+				..
+			})
+		};
+
+		// an off bulb is switched on to flash, and faded back off over the same time
+		bulb.identify(Duration::from_secs(2)).unwrap();
+		let messages = drain(&queue);
+		assert_eq!(messages.len(), 3, "{:?}", messages);
+		assert_eq!(messages[0], Message::SetPower {
+			level: PowerLevel::Enabled,
+		});
+		assert!(flash(&messages[1]), "{:?}", messages);
+		assert_eq!(
+			messages[2],
+			Message::light_set_power(PowerLevel::Standby, Duration::from_secs(2))
+		);
+
+		// but a bulb that was on is left on
+		bulb.power_level.update(PowerLevel::Enabled);
+		bulb.identify(Duration::from_secs(2)).unwrap();
+		let messages = drain(&queue);
+		assert_eq!(messages.len(), 1, "{:?}", messages);
+		assert!(flash(&messages[0]), "{:?}", messages);
+	}
+
+	#[test]
+	fn test_transition() {
+		use std::{collections::HashMap, time::{Duration, Instant}};
+		use udp::{Animation, Easing, InterfaceFilter, Manager, Transition};
+
+		assert_eq!(Easing::Linear.apply(0.25), 0.25);
+		assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+		assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+		assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+		assert_eq!(Easing::EaseInOut.apply(2.0), 1.0);
+
+		let (queue, mut bulb) = test_bulb();
+		bulb.set_model(1, 27);
+		let mut bulbs = HashMap::new();
+		bulbs.insert(bulb.target, bulb);
+
+		let from = HSBK {
+			hue: 0,
+			saturation: 0,
+			brightness: 0,
+			kelvin: 3000,
+		};
+		let to = HSBK {
+			brightness: 60000,
+			kelvin: 6000,
+			..from
+		};
+		let mut transition = Transition::new(from, to, Duration::from_secs(60))
+			.target(MacAddress([1; 6]))
+			.step(Duration::from_secs(20))
+			.easing(Easing::Linear);
+		assert_eq!(transition.color_at(Duration::from_secs(30)).brightness, 30000);
+
+		// it starts by turning the bulb on at `from`
+		let wait = transition.frame(&bulbs, Instant::now()).unwrap();
+		assert_eq!(wait, Some(Duration::from_millis(200)));
+		assert_eq!(drain(&queue), [
+			Message::LightSetColor {
+				reserved: 0,
+				color: from,
+				duration: 0,
+			},
+			Message::SetPower {
+				level: PowerLevel::Enabled,
+			},
+		]);
+
+		// then fades a chunk at a time, carrying on where it left off, until it's done
+		let wait = transition.frame(&bulbs, Instant::now()).unwrap();
+		assert_eq!(wait, Some(Duration::from_secs(20)));
+		let first = HSBK {
+			brightness: 20000,
+			kelvin: 4000,
+			..from
+		};
+		let expected = Message::LightSetColor { reserved: 0, color: first, duration: 20000 };
+		assert_eq!(drain(&queue), [expected]);
+		assert_eq!(transition.elapsed(), Duration::from_secs(20));
+		assert!(transition.frame(&bulbs, Instant::now()).unwrap().is_some());
+		assert_eq!(drain(&queue).len(), 1);
+		assert!(transition.frame(&bulbs, Instant::now()).unwrap().is_some());
+		let expected = Message::LightSetColor { reserved: 0, color: to, duration: 20000 };
+		assert_eq!(drain(&queue), [expected]);
+		assert_eq!(transition.frame(&bulbs, Instant::now()).unwrap(), None);
+		assert!(queue.is_empty());
+
+		// the Manager can pause, resume and cancel it
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mgr = Manager::bind_polled("127.0.0.1:0".parse().unwrap(), filter).unwrap();
+		let handle = mgr.animate(Transition::sunrise(Duration::from_secs(1800)));
+		mgr.poll().unwrap();
+		handle.pause();
+		assert!(handle.is_paused());
+		mgr.poll().unwrap();
+		handle.resume();
+		assert!(!handle.is_paused() && !handle.is_finished());
+		handle.cancel();
+		mgr.poll().unwrap();
+		assert!(handle.is_finished());
+	}
+
+	#[test]
+	fn test_interface_filter() {
+		use std::net::Ipv4Addr;
+		use udp::InterfaceFilter;
+
+		let lan = Ipv4Addr::new(192, 168, 1, 20);
+		let docker = Ipv4Addr::new(172, 17, 0, 1);
+		assert!(InterfaceFilter::all().allows("docker0", docker));
+
+		let filter: InterfaceFilter = "wlan0, 192.168.0.0/16".parse().unwrap();
+		assert_eq!(
+			filter,
+			InterfaceFilter::all()
+				.with_name("wlan0")
+				.with_network(Ipv4Addr::new(192, 168, 0, 0), 16)
+		);
+		assert!(filter.allows("eth0", lan));
+		assert!(filter.allows("wlan0", docker));
+		assert!(!filter.allows("docker0", docker));
+
+		assert!(InterfaceFilter::all().with_network(lan, 32).allows("eth0", lan));
+		assert!(InterfaceFilter::all().with_network(lan, 0).allows("docker0", docker));
+		assert!("10.0.0.0/33".parse::<InterfaceFilter>().is_err());
+		assert!("10.0.0/8".parse::<InterfaceFilter>().is_err());
+	}
+
+	#[test]
+	fn test_bulb_offline() {
+		let (_, mut bulb) = test_bulb();
+		let addr = bulb.addr;
+		bulb.power_level.update(PowerLevel::Enabled);
+		assert!(bulb.is_online());
+		assert_eq!(bulb.power(), Some(PowerLevel::Enabled));
+
+		// stale state isn't served while the bulb is offline
+		bulb.mark_offline();
+		assert_eq!(bulb.power(), None);
+		assert!(bulb.dim_to(0.5, Default::default()).is_err());
+
+		bulb.update(addr);
+		assert!(bulb.is_online());
+		assert_eq!(bulb.power(), Some(PowerLevel::Enabled));
+	}
+
+	#[test]
+	fn test_locations() {
+		let addr = "127.0.0.1:56700".parse().unwrap();
+		let queue = udp::SendQueue::new();
+		let (home, office) = (LifxIdent([1; 16]), LifxIdent([2; 16]));
+		let (kitchen, desk) = (LifxIdent([3; 16]), LifxIdent([4; 16]));
+
+		let bulb = |n,
+		            location: &LifxIdent,
+		            location_label: &str,
+		            group: Option<(&LifxIdent, &str)>| {
+			let mut bulb = udp::Bulb::new(0, MacAddress([n; 6]), queue.clone(), addr);
+			assert!(bulb.set_location(location.clone(), location_label.to_owned()));
+			if let Some((group, group_label)) = group {
+				assert!(bulb.set_group(group.clone(), group_label.to_owned()));
+			}
+			bulb
+		};
+		let mut bulbs = vec![
+			bulb(3, &office, "Office", Some((&desk, "Desk"))),
+			bulb(2, &home, "Home", Some((&kitchen, "Kitchen"))),
+			bulb(1, &home, "Home", Some((&kitchen, "Kitchen"))),
+			// no group yet
+			bulb(4, &home, "Home", None),
+		];
+		assert!(!bulbs[0].set_location(office.clone(), "Office".to_owned()));
+
+		let locations = udp::Location::collect(&bulbs);
+		assert_eq!(locations.len(), 2);
+		assert_eq!((&locations[0].id, locations[0].label.as_str()), (&home, "Home"));
+		assert_eq!(locations[1].label, "Office");
+		let kitchen_bulbs = &locations[0].group("Kitchen").unwrap().bulbs;
+		assert_eq!(kitchen_bulbs, &[MacAddress([1; 6]), MacAddress([2; 6])]);
+		assert_eq!(locations[1].bulbs().collect::<Vec<_>>(), [MacAddress([3; 6])]);
+
+		// moving a bulb moves it in the hierarchy
+		assert!(bulbs[0].set_location(home.clone(), "Home".to_owned()));
+		let locations = udp::Location::collect(&bulbs);
+		assert_eq!(locations.len(), 1);
+		assert_eq!(locations[0].groups.len(), 2);
+		assert_eq!(locations[0].bulbs().count(), 3);
+	}
+
+	#[test]
+	fn test_discovery_schedule() {
+		use std::time::{Duration, Instant};
+		use udp::{discovery::DiscoverySchedule, AutoDiscovery};
+
+		let config = AutoDiscovery {
+			interval: Duration::from_secs(60),
+			max_interval: Duration::from_secs(200),
+		};
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+		let mut schedule = DiscoverySchedule::new(start);
+
+		// due after the interval, which then doubles when nothing new was found
+		assert!(!schedule.due(&config, at(59), 0));
+		assert!(schedule.due(&config, at(60), 0));
+		assert_eq!(schedule.last, at(60));
+		assert!(!schedule.due(&config, at(179), 0));
+		assert!(schedule.due(&config, at(180), 0));
+
+		// up to the maximum
+		assert!(!schedule.due(&config, at(379), 0));
+		assert!(schedule.due(&config, at(380), 0));
+		assert!(schedule.due(&config, at(580), 0));
+
+		// finding a bulb starts it over
+		assert!(schedule.due(&config, at(780), 1));
+		assert!(schedule.due(&config, at(840), 1));
+		assert!(!schedule.due(&config, at(900), 1));
+
+		// and so does asking for discovery
+		schedule.discovered(at(910), 1);
+		assert!(!schedule.due(&config, at(969), 1));
+		assert!(schedule.due(&config, at(970), 1));
+	}
+
+	#[test]
+	fn test_manager_builder() {
+		use udp::{InterfaceFilter, Keepalive, Manager, ManagerConfig, RateLimits, RetryPolicy};
+
+		let builder = Manager::builder()
+			.addr("127.0.0.1:56700".parse().unwrap())
+			.port(0)
+			.interfaces(InterfaceFilter::all().with_name("(none)"))
+			.source(0x1234)
+			.retry_policy(RetryPolicy::NONE)
+			.keepalive(Keepalive::default())
+			.auto_discovery(None)
+			.rate_limits(RateLimits::NONE);
+		assert_eq!(builder.config().addr, "127.0.0.1:0".parse().unwrap());
+		assert_eq!(builder.config().refresh_policies, ManagerConfig::default().refresh_policies);
+
+		let mgr = builder.build_polled().unwrap();
+		assert_eq!(mgr.source, 0x1234);
+		assert_eq!(mgr.retry_policy, RetryPolicy::NONE);
+		assert_eq!(mgr.keepalive, Some(Keepalive::default()));
+		assert_eq!(mgr.auto_discovery, None);
+		assert_eq!(mgr.rate_limits(), RateLimits::NONE);
+		assert!(mgr.sock.local_addr().unwrap().ip().is_loopback());
+
+		// sources are random unless given
+		let mgr = Manager::builder().port(0).auto_discovery(None).build_polled().unwrap();
+		assert_ne!(mgr.source, 0);
+	}
+
+	#[test]
+	fn test_manager_poll() {
+		use std::net::UdpSocket;
+		use udp::{BulbField, InterfaceFilter, Manager, ManagerEvent};
+
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mut mgr = Manager::bind_polled("127.0.0.1:0".parse().unwrap(), filter).unwrap();
+		mgr.retry_policy = udp::RetryPolicy::NONE;
+		assert_eq!(mgr.poll().unwrap(), []);
+		assert!(mgr.request(MacAddress([1; 6]), Message::GetLabel).is_err());
+
+		// a bulb says hello
+		let bulb = UdpSocket::bind("127.0.0.1:0").unwrap();
+		bulb.set_read_timeout(Some(std::time::Duration::from_secs(1))).unwrap();
+		let options = BuildOptions {
+			target: Some(MacAddress([1; 6])),
+			..Default::default()
+		};
+		let label = Message::StateLabel {
+			label: LifxString::new("Lamp"),
+		};
+		let bytes = RawMessage::build(&options, label).unwrap().pack().unwrap();
+		bulb.send_to(&bytes, mgr.sock.local_addr().unwrap()).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(50));
+
+		let events = mgr.poll().unwrap();
+		assert_eq!(events, [
+			ManagerEvent::DeviceDiscovered(MacAddress([1; 6])),
+			ManagerEvent::DeviceUpdated(MacAddress([1; 6]), BulbField::Name),
+		]);
+		let bulbs = mgr.snapshot();
+		assert_eq!(bulbs.len(), 1);
+		assert_eq!(bulbs[0].target, MacAddress([1; 6]));
+		assert_eq!(bulbs[0].label.as_deref(), Some("Lamp"));
+		assert_eq!(mgr.get(MacAddress([1; 6])).as_ref(), bulbs.first());
+		assert_eq!(mgr.get(MacAddress([2; 6])), None);
+		assert_eq!(mgr.find_by_addr(bulb.local_addr().unwrap()).as_ref(), bulbs.first());
+		assert_eq!(mgr.find_by_label("Lamp"), bulbs);
+		assert_eq!(mgr.find_by_label("Desk"), []);
+		assert_eq!(mgr.find_by_group("Kitchen"), []);
+		assert_eq!(mgr.iter().count(), 1);
+
+		// and is asked for everything else that's unknown
+		let mut buf = [0; 1024];
+		let (nbytes, _) = bulb.recv_from(&mut buf).unwrap();
+		let query = RawMessage::unpack(&buf[..nbytes]).unwrap();
+		assert_eq!(query.frame_addr.target, MacAddress([1; 6]));
+		assert_eq!(Message::from_raw(&query).unwrap(), Message::GetGroup);
+
+		// garbage is reported, not just logged
+		let errors = mgr.errors();
+		bulb.send_to(&[0xff; 12], mgr.sock.local_addr().unwrap()).unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		assert_eq!(mgr.poll().unwrap(), []);
+		let error = errors.try_recv().unwrap();
+		let from = bulb.local_addr().unwrap();
+		assert!(matches!(*error, udp::ManagerError::Unpack { addr, .. } if addr == from));
+	}
+
+	#[test]
+	fn test_manager_refresh() {
+		let (_emulated, mgr) = emulated_manager();
+		let label = || mgr.get(MacAddress([1; 6])).unwrap().label;
+		assert_eq!(label(), None);
+
+		mgr.refresh().unwrap();
+		for _ in 0..100 {
+			if label().is_some() {
+				break;
+			}
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		assert_eq!(label().as_deref(), Some("Emulated Bulb"));
+	}
+
+	#[test]
+	fn test_manager_rediscover_known() {
+		let (sock, mgr) = fake_bulb_manager();
+
+		// broadcasts only go out on the allowed interfaces, and there are none
+		mgr.set_all_power(true, Default::default()).unwrap();
+		mgr.set_all_color(HSBK::color(0, 1.0, 1.0), Default::default()).unwrap();
+
+		// known bulbs are probed directly, wherever they are
+		mgr.rediscover_known().unwrap();
+		let (raw, _) = recv_raw(&sock);
+		assert_eq!(Message::from_raw(&raw).unwrap(), Message::GetService);
+	}
+
+	#[test]
+	fn test_manager_zones_out_of_range() {
+		use std::{net::UdpSocket, thread, time::Duration};
+		use udp::{InterfaceFilter, Manager};
+
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mgr = Manager::bind_polled("127.0.0.1:0".parse().unwrap(), filter).unwrap();
+		let bulb = UdpSocket::bind("127.0.0.1:0").unwrap();
+		let send = |message| {
+			let options = BuildOptions {
+				target: Some(MacAddress([1; 6])),
+				..Default::default()
+			};
+			let bytes = RawMessage::build(&options, message).unwrap().pack().unwrap();
+			bulb.send_to(&bytes, mgr.sock.local_addr().unwrap()).unwrap();
+			thread::sleep(Duration::from_millis(20));
+			mgr.poll().unwrap();
+		};
+		let red = HSBK::color(0, 1.0, 1.0);
+		let multi_zone = |index| Message::StateMultiZone {
+			count: 8,
+			index,
+			color0: red,
+			color1: red,
+			color2: red,
+			color3: red,
+			color4: red,
+			color5: red,
+			color6: red,
+			color7: red,
+		};
+
+		// a LIFX Z with 8 zones, which claims to have zones it doesn't
+		send(Message::StateVersion {
+			vendor: 1,
+			product: 31,
+			version: 0,
+		});
+		send(Message::StateZone {
+			count: 8,
+			index: 200,
+			color: red,
+		});
+		send(multi_zone(250));
+		assert_eq!(mgr.get(MacAddress([1; 6])).unwrap().zones, Some(vec![None; 8]));
+
+		// the zones that do exist are still cached
+		send(multi_zone(4));
+		let zones = mgr.get(MacAddress([1; 6])).unwrap().zones.unwrap();
+		assert_eq!(zones, [None, None, None, None, Some(red), Some(red), Some(red), Some(red)]);
+		send(Message::StateZone {
+			count: 8,
+			index: 0,
+			color: red,
+		});
+		assert_eq!(mgr.get(MacAddress([1; 6])).unwrap().zones.unwrap()[0], Some(red));
+	}
+
+	#[cfg(feature = "tokio")]
+	#[tokio::test]
+	async fn test_async_manager() {
+		use std::{net::UdpSocket, time::Duration};
+		use udp::{InterfaceFilter, Manager, RetryPolicy};
+
+		let localhost = "127.0.0.1:0".parse().unwrap();
+		let emulated = emulator::EmulatedBulb::bind(localhost, MacAddress([1; 6])).unwrap();
+		let mut mgr = Manager::builder()
+			.addr(localhost)
+			.interfaces(InterfaceFilter::all().with_name("(none)"))
+			.retry_policy(RetryPolicy::NONE)
+			.auto_discovery(None)
+			.build_async()
+			.await
+			.unwrap();
+		let second = Duration::from_secs(1);
+
+		// a probed bulb is discovered once its reply is received
+		mgr.probe(emulated.addr()).await.unwrap();
+		let target = tokio::time::timeout(second, mgr.recv()).await.unwrap().unwrap();
+		assert_eq!(target, MacAddress([1; 6]));
+		assert_eq!(mgr.get(target).unwrap().addr, emulated.addr());
+
+		// requests return the bulb's reply, which updates the cache too
+		let label = mgr.request(target, Message::GetLabel).await.unwrap();
+		assert_eq!(label, Message::StateLabel {
+			label: LifxString::new("Emulated Bulb"),
+		});
+		assert_eq!(mgr.get(target).unwrap().label.as_deref(), Some("Emulated Bulb"));
+		let power_on = Message::SetPower {
+			level: PowerLevel::Enabled,
+		};
+		mgr.send_with_ack(target, power_on.clone()).await.unwrap();
+		assert_eq!(emulated.state().power, PowerLevel::Enabled);
+
+		// the emulator ignores messages it doesn't understand, so this one is never answered
+		let ms = Duration::from_millis;
+		let err = mgr.request_with(target, Message::GetWifiInfo, &RetryPolicy::NONE, ms(50)).await;
+		assert!(err.unwrap_err().to_string().contains("didn't reply"));
+
+		// and a reply that can't be decoded is an error, rather than being waited past
+		let fake = UdpSocket::bind(localhost).unwrap();
+		fake.set_read_timeout(Some(second)).unwrap();
+		let fake_addr = fake.local_addr().unwrap();
+		let fake_bulb = std::thread::spawn(move || {
+			let (get_service, addr) = recv_raw(&fake);
+			let state_service = Message::StateService {
+				port: fake_addr.port() as u32,
+				service: Service::UDP,
+			};
+			reply(&fake, addr, &get_service, MacAddress([2; 6]), state_service);
+			let (get_power, addr) = recv_raw(&fake);
+			let options = BuildOptions {
+				target: Some(MacAddress([2; 6])),
+				sequence: get_power.frame_addr.sequence,
+				source: get_power.frame.source,
+				..Default::default()
+			};
+			let mut truncated = RawMessage::build(&options, power_on).unwrap();
+			truncated.payload.truncate(1);
+			truncated.frame.size -= 1;
+			fake.send_to(&truncated.pack().unwrap(), addr).unwrap();
+		});
+		mgr.probe(fake_addr).await.unwrap();
+		let found = tokio::time::timeout(second, mgr.recv()).await.unwrap().unwrap();
+		assert_eq!(found, MacAddress([2; 6]));
+		let err = mgr.request(MacAddress([2; 6]), Message::GetPower).await.unwrap_err();
+		assert!(err.downcast_ref::<Error>().is_some(), "unexpected error {}", err);
+		fake_bulb.join().unwrap();
+	}
+
+	#[test]
+	fn test_manager_send_with_ack() {
+		use std::time::Duration;
+		use udp::RetryPolicy;
+
+		let (bulb, mgr) = fake_bulb_manager();
+		let target = MacAddress([1; 6]);
+		let policy = RetryPolicy {
+			attempts: 3,
+			backoff: Duration::from_millis(10),
+			jitter: Duration::from_millis(0),
+		};
+		let timeout = Duration::from_millis(50);
+		let power_on = Message::SetPower {
+			level: PowerLevel::Enabled,
+		};
+
+		std::thread::scope(|scope| {
+			let sent = scope.spawn(|| {
+				mgr.send_with_ack_with(target, power_on.clone(), &policy, timeout)
+			});
+
+			// unacknowledged, it's sent again just as it was
+			let (first, addr) = recv_raw(&bulb);
+			assert!(first.frame_addr.ack_required && !first.frame_addr.res_required);
+			let (second, _) = recv_raw(&bulb);
+			assert_eq!(second, first);
+
+			// an acknowledgement of some other message doesn't count
+			let mut other = first.clone();
+			other.frame_addr.sequence = first.frame_addr.sequence.wrapping_add(1);
+			reply(&bulb, addr, &other, target, Message::Acknowledgement);
+			let (third, _) = recv_raw(&bulb);
+			assert_eq!(third, first);
+
+			// but one with the same source, sequence and target does
+			reply(&bulb, addr, &third, target, Message::Acknowledgement);
+			sent.join().unwrap().unwrap();
+		});
+
+		// once every attempt goes unanswered, it's an error
+		let err = mgr.send_with_ack_with(target, power_on, &policy, timeout).unwrap_err();
+		assert!(err.to_string().contains("didn't acknowledge"));
+		bulb.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+		let mut buf = [0; 1024];
+		assert_eq!(std::iter::from_fn(|| bulb.recv_from(&mut buf).ok()).count(), 3);
+	}
+
+	#[test]
+	fn test_manager_request() {
+		use std::time::Duration;
+		use udp::RetryPolicy;
+
+		let (bulb, mgr) = fake_bulb_manager();
+		let target = MacAddress([1; 6]);
+		let ms = Duration::from_millis;
+		let request = || mgr.request_with(target, Message::GetPower, &RetryPolicy::NONE, ms(500));
+		let enabled = Message::StatePower {
+			level: PowerLevel::Enabled,
+		};
+		let standby = Message::StatePower {
+			level: PowerLevel::Standby,
+		};
+
+		// replies from other bulbs, or to other requests, are passed over
+		std::thread::scope(|scope| {
+			let requested = scope.spawn(request);
+			let (get_power, addr) = recv_raw(&bulb);
+			assert!(get_power.frame_addr.res_required);
+			let mut other_sequence = get_power.clone();
+			other_sequence.frame_addr.sequence = get_power.frame_addr.sequence.wrapping_add(1);
+			reply(&bulb, addr, &other_sequence, target, standby.clone());
+			reply(&bulb, addr, &get_power, MacAddress([2; 6]), standby.clone());
+			reply(&bulb, addr, &get_power, target, enabled.clone());
+			assert_eq!(requested.join().unwrap().unwrap(), enabled);
+		});
+
+		// a reply that can't be decoded is an error right away, rather than a timeout
+		std::thread::scope(|scope| {
+			let requested = scope.spawn(request);
+			let (get_power, addr) = recv_raw(&bulb);
+			let options = BuildOptions {
+				target: Some(target),
+				sequence: get_power.frame_addr.sequence,
+				source: get_power.frame.source,
+				..Default::default()
+			};
+			let mut truncated = RawMessage::build(&options, enabled.clone()).unwrap();
+			truncated.payload.truncate(1);
+			truncated.frame.size -= 1;
+			bulb.send_to(&truncated.pack().unwrap(), addr).unwrap();
+			let err = requested.join().unwrap().unwrap_err();
+			assert!(err.downcast_ref::<Error>().is_some(), "unexpected error {}", err);
+		});
+
+		// and no reply at all times out
+		let err = mgr.request_with(target, Message::GetPower, &RetryPolicy::NONE, ms(50));
+		assert!(err.unwrap_err().to_string().contains("didn't reply"));
+	}
+
+	#[test]
+	fn test_manager_transport() {
+		use std::{
+			collections::VecDeque,
+			io,
+			net::SocketAddr,
+			sync::{Arc, Mutex},
+			time::Duration,
+		};
+		use udp::{BulbField, InterfaceFilter, Manager, ManagerEvent, Transport};
+
+		type Datagrams = Arc<Mutex<VecDeque<(Vec<u8>, SocketAddr)>>>;
+
+		/// A network that only exists in memory, shared by every clone.
+		#[derive(Clone, Default)]
+		struct Loopback {
+			incoming: Datagrams,
+			sent: Datagrams,
+		}
+
+		impl Transport for Loopback {
+			fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+				self.sent.lock().unwrap().push_back((buf.to_vec(), addr));
+				Ok(buf.len())
+			}
+
+			fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+				match self.incoming.lock().unwrap().pop_front() {
+					Some((bytes, addr)) => {
+						buf[..bytes.len()].copy_from_slice(&bytes);
+						Ok((bytes.len(), addr))
+					}
+					None => Err(io::ErrorKind::WouldBlock.into()),
+				}
+			}
+
+			fn try_clone(&self) -> io::Result<Loopback> {
+				Ok(self.clone())
+			}
+
+			fn local_addr(&self) -> io::Result<SocketAddr> {
+				Ok("[::]:56700".parse().unwrap())
+			}
+
+			fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+				Ok(())
+			}
+
+			fn set_nonblocking(&self, _: bool) -> io::Result<()> {
+				Ok(())
+			}
+		}
+
+		let net = Loopback::default();
+		let sent = || {
+			let datagram = net.sent.lock().unwrap().pop_front()?;
+			let raw = RawMessage::unpack(&datagram.0).unwrap();
+			assert_eq!(raw.frame.source, 0x1234);
+			Some((Message::from_raw(&raw).unwrap(), datagram.1))
+		};
+
+		// discovery goes out through the transport, to every IPv6 node on the link
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mgr = Manager::with_transport_polled(net.clone(), filter, 0x1234).unwrap();
+		let all_nodes: SocketAddr = "[ff02::1]:56700".parse().unwrap();
+		assert_eq!(sent(), Some((Message::GetService, all_nodes)));
+		assert_eq!(sent(), None);
+
+		// and replies come back through it too
+		let bulb: SocketAddr = "[fe80::2]:56700".parse().unwrap();
+		let options = BuildOptions {
+			target: Some(MacAddress([1; 6])),
+			..Default::default()
+		};
+		let label = Message::StateLabel {
+			label: LifxString::new("Lamp"),
+		};
+		let bytes = RawMessage::build(&options, label).unwrap().pack().unwrap();
+		net.incoming.lock().unwrap().push_back((bytes, bulb));
+
+		assert_eq!(mgr.poll().unwrap(), [
+			ManagerEvent::DeviceDiscovered(MacAddress([1; 6])),
+			ManagerEvent::DeviceUpdated(MacAddress([1; 6]), BulbField::Name),
+		]);
+		assert_eq!(mgr.get(MacAddress([1; 6])).unwrap().addr, bulb);
+		assert_eq!(sent(), Some((Message::GetGroup, bulb)));
+	}
+
+	#[test]
+	fn test_conformance() {
+		use std::{net::UdpSocket, time::Duration};
+		use emulator::{EmulatedBulb, EmulatedState};
+		use udp::conformance::{self, Outcome};
+
+		let localhost = "127.0.0.1:0".parse().unwrap();
+		let emulated = EmulatedBulb::bind(localhost, MacAddress([1; 6])).unwrap();
+		let sock = UdpSocket::bind(localhost).unwrap();
+		let timeout = Duration::from_millis(50);
+		let report = conformance::run(&sock, emulated.addr(), MacAddress([1; 6]), timeout).unwrap();
+		let outcome = |name| {
+			let check = report.checks.iter().find(|check| check.name == name).unwrap();
+			check.outcome.clone()
+		};
+
+		for name in &["discovery", "echo", "get label", "label round-trip", "get color"] {
+			assert_eq!(outcome(*name), Outcome::Passed, "{}", name);
+		}
+		assert_eq!(outcome("color set/verify"), Outcome::Passed);
+		assert_eq!(outcome("power"), Outcome::Passed);
+		// it's a single-zone bulb, and the emulator doesn't know about wifi
+		assert_eq!(outcome("zone query"), Outcome::Unsupported);
+		assert_eq!(outcome("wifi info"), Outcome::NoResponse);
+
+		assert!(report.passed());
+		assert!(report.unknown_messages.is_empty());
+		assert!(report.supported().contains(&Message::LightGet.get_num()));
+		assert!(!report.supported().contains(&Message::GetWifiInfo.get_num()));
+		// and everything it set was written back as it was
+		assert_eq!(emulated.state(), EmulatedState::default());
+	}
+
+	#[test]
+	fn test_emulated_bulb() {
+		use std::{net::UdpSocket, time::Duration};
+		use emulator::{EmulatedBulb, EmulatedState};
+		use udp::{InterfaceFilter, Manager, ManagerEvent};
+
+		let addr = "127.0.0.1:0".parse().unwrap();
+		let emulated = EmulatedBulb::bind(addr, MacAddress([1; 6])).unwrap();
+		let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+		client.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+		let mut buf = [0; 1024];
+		let mut exchange = |message, res_required| {
+			let options = BuildOptions {
+				target: Some(MacAddress([1; 6])),
+				res_required,
+				sequence: 7,
+				source: 0x1234,
+				..Default::default()
+			};
+			let bytes = RawMessage::build(&options, message).unwrap().pack().unwrap();
+			client.send_to(&bytes, emulated.addr()).unwrap();
+			let nbytes = client.recv_from(&mut buf).ok()?.0;
+			let raw = RawMessage::unpack(&buf[..nbytes]).unwrap();
+			assert_eq!(raw.frame_addr.target, MacAddress([1; 6]));
+			assert_eq!((raw.frame.source, raw.frame_addr.sequence), (0x1234, 7));
+			Some(Message::from_raw(&raw).unwrap())
+		};
+
+		// queries are answered from the bulb's state
+		let label = Message::StateLabel {
+			label: LifxString::new("Emulated Bulb"),
+		};
+		assert_eq!(exchange(Message::GetLabel, false), Some(label));
+
+		// and commands change it, replying (with the state from before) only when asked to
+		let color = HSBK {
+			hue: 120,
+			saturation: 65535,
+			brightness: 32768,
+			kelvin: 3500,
+		};
+		let set_color = Message::LightSetColor {
+			reserved: 0,
+			color,
+			duration: 0,
+		};
+		match exchange(set_color, true) {
+			Some(Message::LightState { color: reply, .. }) => {
+				assert_eq!(reply, EmulatedState::default().color);
+			}
+			other => panic!("unexpected reply {:?}", other),
+		}
+		let power_on = Message::SetPower {
+			level: PowerLevel::Enabled,
+		};
+		client.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+		assert_eq!(exchange(power_on, false), None);
+		assert_eq!(emulated.state(), EmulatedState {
+			color,
+			power: PowerLevel::Enabled,
+			..Default::default()
+		});
+
+		// a Manager finds it like any other bulb
+		let filter = InterfaceFilter::all().with_name("(none)");
+		let mgr = Manager::bind_polled("127.0.0.1:0".parse().unwrap(), filter).unwrap();
+		mgr.probe(emulated.addr()).unwrap();
+		let mut events = Vec::new();
+		for _ in 0..20 {
+			events.extend(mgr.poll().unwrap());
+			if mgr.get(MacAddress([1; 6])).is_some_and(|bulb| bulb.color.is_some()) {
+				break;
+			}
+			std::thread::sleep(Duration::from_millis(25));
+		}
+		assert!(events.contains(&ManagerEvent::DeviceDiscovered(MacAddress([1; 6]))));
+		let bulb = mgr.get(MacAddress([1; 6])).unwrap();
+		assert_eq!(bulb.label.as_deref(), Some("Emulated Bulb"));
+		assert_eq!(bulb.color, Some(color));
+	}
+
+	#[test]
+	fn test_echo_health() {
+		use std::time::{Duration, Instant};
+		use udp::{EchoTracker, HealthStatus, Keepalive};
+
+		let keepalive = Keepalive::default();
+		let mut echo = EchoTracker::default();
+		let start = Instant::now();
+		let at = |secs: f32| start + Duration::from_secs_f32(secs);
+		assert_eq!(echo.health(&keepalive).status, HealthStatus::Unknown);
+
+		let first = echo.start(&keepalive, at(0.0)).unwrap();
+		assert_eq!(echo.start(&keepalive, at(1.0)), None);
+		assert_eq!(echo.finish(&first, at(0.1)), Some(Duration::from_secs_f32(0.1)));
+		assert_eq!(echo.finish(&first, at(0.2)), None);
+		let health = echo.health(&keepalive);
+		assert_eq!(health.status, HealthStatus::Healthy);
+		assert_eq!(health.last_rtt, Some(Duration::from_secs_f32(0.1)));
+
+		// a slow reply
+		let second = echo.start(&keepalive, at(10.0)).unwrap();
+		echo.finish(&second, at(11.0));
+		assert_eq!(echo.health(&keepalive).status, HealthStatus::Degraded);
+
+		// three lost in a row
+		for secs in &[20.0, 30.0, 40.0] {
+			echo.start(&keepalive, at(*secs)).unwrap();
+		}
+		echo.start(&keepalive, at(50.0)).unwrap();
+		let health = echo.health(&keepalive);
+		assert_eq!(health.status, HealthStatus::Unreachable);
+		assert!((health.loss - 0.6).abs() < 1e-6);
+		assert_eq!(health.last_rtt, Some(Duration::from_secs(1)));
+
+		// a window shorter than the run of losses still lets a bulb become unreachable
+		let keepalive = Keepalive {
+			window: 2,
+			..Keepalive::default()
+		};
+		let mut echo = EchoTracker::default();
+		for secs in &[0.0, 10.0, 20.0] {
+			echo.start(&keepalive, at(*secs)).unwrap();
+		}
+		echo.start(&keepalive, at(30.0)).unwrap();
+		let health = echo.health(&keepalive);
+		assert_eq!(health.status, HealthStatus::Unreachable);
+		assert_eq!(health.loss, 1.0);
+	}
+
+	#[test]
+	fn test_refreshable_data_update_changed() {
+		let mut data = udp::RefreshableData::empty(Default::default(), Message::GetPower);
+		assert_eq!(data.last_changed(), None);
+		assert!(data.update(PowerLevel::Standby));
+		let changed = data.last_changed().unwrap();
+		assert!(!data.update(PowerLevel::Standby));
+		assert_eq!(data.last_changed(), Some(changed));
+		assert!(data.last_updated() >= changed);
+		assert!(data.update(PowerLevel::Enabled));
+		assert!(data.last_changed().unwrap() >= changed);
+		assert_eq!(data.as_ref(), Some(&PowerLevel::Enabled));
+	}
+
+	#[test]
+	fn test_retry_policy() {
+		use std::time::Duration;
+		use udp::RetryPolicy;
+
+		let ms = Duration::from_millis;
+		let policy = RetryPolicy {
+			attempts: 4,
+			backoff: ms(100),
+			jitter: Duration::ZERO,
+		};
+		assert_eq!(policy.delay(0), None);
+		assert_eq!(policy.delay(1), Some(ms(100)));
+		assert_eq!(policy.delay(3), Some(ms(400)));
+		assert_eq!(policy.delay(4), None);
+		assert_eq!(policy.delays().collect::<Vec<_>>(), [ms(100), ms(200), ms(400)]);
+
+		// jitter only ever adds, and by less than its bound
+		let jittery = RetryPolicy {
+			jitter: ms(10),
+			..policy
+		};
+		for _ in 0..20 {
+			let delays: Vec<Duration> = jittery.delays().collect();
+			assert_eq!(delays.len(), 3);
+			for (delay, base) in delays.into_iter().zip(policy.delays()) {
+				assert!(delay >= base && delay < base + ms(10));
+			}
+		}
+
+		// long backoffs saturate rather than overflow, and zero attempts still sends once
+		let patient = RetryPolicy {
+			attempts: 40,
+			backoff: Duration::MAX / 2,
+			jitter: Duration::ZERO,
+		};
+		assert_eq!(patient.delay(39), Some(Duration::MAX));
+		assert_eq!(RetryPolicy::NONE.delays().count(), 0);
+		assert_eq!(RetryPolicy { attempts: 0, ..policy }.delays().count(), 0);
+
+		// and each call can bring its own policy
+		let (_emulated, mgr) = emulated_manager();
+		let twice = RetryPolicy {
+			attempts: 2,
+			..RetryPolicy::NONE
+		};
+		let ignored = mgr.request_with(MacAddress([1; 6]), Message::GetWifiInfo, &twice, ms(20));
+		assert!(ignored.unwrap_err().to_string().ends_with("after 2 attempts"));
+		let label = mgr.request_with(MacAddress([1; 6]), Message::GetLabel, &twice, ms(500));
+		assert_eq!(label.unwrap(), Message::StateLabel {
+			label: LifxString::new("Emulated Bulb")
+		});
+	}
+
+	#[test]
+	fn test_rate_limiter() {
+		use std::time::{Duration, Instant};
+		use udp::{RateLimiter, RateLimits};
+
+		let mut limiter = RateLimiter::new(RateLimits {
+			per_device: 10.0,
+			global: 15.0,
+			burst: 2,
+		});
+		let (a, b) = (MacAddress([1; 6]), MacAddress([2; 6]));
+		let now = Instant::now();
+
+		// a burst goes out at once, then the device has to wait for its bucket to refill
+		assert!(limiter.try_acquire(a, now).is_ok());
+		assert!(limiter.try_acquire(a, now).is_ok());
+		let wait = limiter.try_acquire(a, now).unwrap_err();
+		assert!((wait.as_secs_f32() - 0.1).abs() < 1e-4);
+
+		// other devices are held back by the global limit
+		let wait = limiter.try_acquire(b, now).unwrap_err();
+		assert!((wait.as_secs_f32() - 1.0 / 15.0).abs() < 1e-4);
+		assert!(limiter.try_acquire(b, now + Duration::from_millis(70)).is_ok());
+		assert!(limiter.try_acquire(a, now + Duration::from_millis(100)).is_err());
+		assert!(limiter.try_acquire(a, now + Duration::from_millis(140)).is_ok());
+
+		limiter.set_limits(RateLimits::NONE);
+		for _ in 0..100 {
+			assert!(limiter.try_acquire(a, now).is_ok());
+		}
+
+		// rates that aren't positive don't stop messages altogether
+		limiter.set_limits(RateLimits {
+			per_device: 0.0,
+			global: f32::NAN,
+			burst: 1,
+		});
+		assert_eq!(limiter.limits().per_device, f32::INFINITY);
+		assert_eq!(limiter.limits().global, f32::INFINITY);
+		for _ in 0..100 {
+			assert!(limiter.try_acquire(a, now).is_ok());
+		}
+	}
+
+	#[test]
+	fn test_polled_auto_discovery() {
+		use std::time::Duration;
+		use udp::{AutoDiscovery, InterfaceFilter, Manager};
+
+		let mgr = Manager::builder()
+			.addr("127.0.0.1:0".parse().unwrap())
+			.interfaces(InterfaceFilter::all().with_name("(none)"))
+			.auto_discovery(Some(AutoDiscovery {
+				interval: Duration::from_millis(200),
+				max_interval: Duration::from_millis(200),
+			}))
+			.build_polled()
+			.unwrap();
+		let started = mgr.last_discovery();
+
+		mgr.poll().unwrap();
+		assert_eq!(mgr.last_discovery(), started);
+		std::thread::sleep(Duration::from_millis(250));
+		mgr.poll().unwrap();
+		assert!(mgr.last_discovery() > started);
+	}
+
+	#[test]
+	fn test_manager_shutdown_while_rate_limited() {
+		use std::time::{Duration, Instant};
+
+		let (_emulated, mut mgr) = emulated_manager();
+		mgr.set_rate_limits(udp::RateLimits {
+			per_device: 0.01,
+			global: 0.01,
+			burst: 1,
+		});
+		// queues several queries, and the second one has 100 seconds to wait
+		mgr.refresh().unwrap();
+		std::thread::sleep(Duration::from_millis(50));
+
+		let start = Instant::now();
+		mgr.shutdown();
+		assert!(start.elapsed() < Duration::from_secs(2));
+	}
+
+	#[test]
+	fn test_pack_into() {
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::light_set_color(HSBK::white(Kelvin::Neutral, 1.0), Default::default()),
+		)
+		.unwrap();
+		let packed = raw.pack().unwrap();
+
+		let mut buf = [0xff; 64];
+		assert_eq!(raw.pack_into(&mut buf).unwrap(), packed.len());
+		assert_eq!(&buf[..packed.len()], &packed[..]);
+		assert!(buf[packed.len()..].iter().all(|&b| b == 0xff));
+
+		assert!(matches!(
+			raw.pack_into(&mut buf[..40]),
+			Err(Error::BufferTooSmall {
+				needed: 49,
+				actual: 40
+			})
+		));
+
+		let mut buf = [0; 8];
+		assert_eq!(raw.frame.pack_into(&mut buf).unwrap(), Frame::packed_size());
+		assert_eq!(&buf[..], &packed[..8]);
+	}
+
+	#[test]
+	fn test_decode_truncated_payload() {
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::StateLabel {
+				label: LifxString::new("Kitchen"),
+			},
+		)
+		.unwrap();
+		let mut unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+		unpacked.payload.truncate(10);
+		match Message::from_raw(&unpacked) {
+			Err(Error::PayloadSizeMismatch {
+				typ,
+				expected,
+				actual,
+			}) => {
+				assert_eq!(typ, 25);
+				assert_eq!(expected, 32);
+				assert_eq!(actual, 10);
+			}
+			res => panic!("unexpected result {:?}", res),
+		}
+	}
+
+	#[test]
+	fn test_decode_invalid_enum_value() {
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::StatePower {
+				level: PowerLevel::Enabled,
+			},
+		)
+		.unwrap();
+		let mut unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+		unpacked.payload = Payload::from_slice(&[0x34, 0x12]);
+		match Message::from_raw_with(&unpacked, &DecodeOptions::strict()) {
+			Err(Error::InvalidEnumValue { typ, field, value }) => {
+				assert_eq!(typ, Some(22));
+				assert_eq!(field, "level");
+				assert_eq!(value, 0x1234);
+			}
+			res => panic!("unexpected result {:?}", res),
+		}
+
+		// decoding leniently keeps the message as it is, and it packs back into the same bytes
+		let undecoded = Message::from_raw_with(&unpacked, &DecodeOptions::lenient()).unwrap();
+		assert_eq!(undecoded, Message::Undecoded {
+			typ: MessageType::StatePower,
+			payload: vec![0x34, 0x12],
+		});
+		assert_eq!(Message::from_raw(&unpacked).unwrap(), undecoded);
+		assert_eq!(undecoded.get_num(), 22);
+		let options = BuildOptions::default();
+		assert_eq!(RawMessage::build(&options, undecoded).unwrap(), unpacked);
+
+		// an undocumented service too, which some bulbs advertise alongside UDP
+		let mut service = RawMessage::build(&options, Message::StateService {
+			port: 56700,
+			service: Service::UDP,
+		})
+		.unwrap();
+		service.payload[0] = 5;
+		assert!(Message::from_raw_with(&service, &DecodeOptions::strict()).is_err());
+		assert!(matches!(
+			Message::from_raw(&service),
+			Ok(Message::Undecoded {
+				typ: MessageType::StateService,
+				..
+			})
+		));
+
+		// and a button gesture from newer switch firmware
+		let mut button = RawMessage::build(&options, Message::StateButton {
+			count: 1,
+			index: 0,
+			buttons_count: 1,
+			buttons: Default::default(),
+		})
+		.unwrap();
+		button.payload[4] = 9;
+		match Message::from_raw_with(&button, &DecodeOptions::strict()) {
+			Err(Error::InvalidEnumValue { typ, field, value }) => {
+				assert_eq!(typ, Some(907));
+				assert_eq!(field, "buttons");
+				assert_eq!(value, 9);
+			}
+			res => panic!("unexpected result {:?}", res),
+		}
+		assert!(matches!(
+			Message::from_raw(&button),
+			Ok(Message::Undecoded {
+				typ: MessageType::StateButton,
+				..
+			})
+		));
+	}
+
+	#[test]
+	fn test_build_a_packet() {
+		// packet taken from https://lan.developer.lifx.com/docs/building-a-lifx-packet
+
+		let msg = Message::LightSetColor {
+			reserved: 0,
+			color: HSBK {
+				hue: 21845,
+				saturation: 0xffff,
+				brightness: 0xffff,
+				kelvin: 3500,
+			},
+			duration: 1024,
+		};
+
+		let raw = RawMessage::build(
+			&BuildOptions {
+				target: None,
+				ack_required: false,
+				res_required: false,
+				sequence: 0,
+				source: 0,
+			},
+			msg,
+		)
+		.unwrap();
+
+		let bytes = raw.pack().unwrap();
+		println!("{:?}", bytes);
+		assert_eq!(bytes.len(), 49);
+		assert_eq!(
+			bytes,
+			vec![
+				0x31, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x66, 0x00, 0x00, 0x00, 0x00, 0x55, 0x55, 0xFF, 0xFF, 0xFF,
+				0xFF, 0xAC, 0x0D, 0x00, 0x04, 0x00, 0x00
+			]
+		);
+	}
+
+	#[test]
+	fn test_message_type() {
+		use std::convert::TryFrom;
+
+		assert_eq!(MessageType::try_from(102).unwrap(), MessageType::LightSetColor);
+		assert_eq!(MessageType::LightSetColor.name(), "LightSetColor");
+		assert_eq!(u16::from(MessageType::StateButton), 907);
+		assert!(matches!(
+			MessageType::try_from(4),
+			Err(Error::UnknownMessageType(4))
+		));
+
+		let mut largest = 0;
+		for typ in 0..1024 {
+			if let Ok(t) = MessageType::try_from(typ) {
+				assert_eq!(t as u16, typ);
+				assert_eq!(Message::expected_payload_size(typ), Some(t.payload_size()));
+				largest = largest.max(t.payload_size());
+			} else {
+				assert_eq!(Message::expected_payload_size(typ), None);
+			}
+		}
+		assert_eq!(Message::GetService.message_type(), MessageType::GetService);
+		assert_eq!(
+			MAX_MESSAGE_SIZE,
+			Frame::PACKED_SIZE + FrameAddress::PACKED_SIZE + ProtocolHeader::PACKED_SIZE + largest
+		);
+	}
+
+	#[test]
+	fn test_response_meta() {
+		let target = MacAddress::from_bytes([0xd0, 0x73, 0xd5, 0x02, 0x97, 0xde]);
+		let opts = BuildOptions {
+			target: Some(target),
+			sequence: 42,
+			source: 0x1234_5678,
+			..Default::default()
+		};
+		let raw = RawMessage::build(&opts, Message::Acknowledgement).unwrap();
+		assert!(raw.payload.is_empty());
+
+		let unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+		let (msg, meta) = Message::from_raw_with_meta(&unpacked).unwrap();
+		assert_eq!(msg, Message::Acknowledgement);
+		assert_eq!(
+			meta,
+			ResponseMeta {
+				source: 0x1234_5678,
+				sequence: 42,
+				target,
+			}
+		);
+	}
+
+	#[test]
+	fn test_expected_response() {
+		assert_eq!(Message::GetService.expected_response(), &[MessageType::StateService]);
+		assert_eq!(Message::LightGet.expected_response(), &[MessageType::LightState]);
+		assert_eq!(
+			Message::light_set_color(HSBK::white(Kelvin::Neutral, 1.0), Default::default())
+				.expected_response(),
+			&[MessageType::LightState]
+		);
+		assert_eq!(
+			Message::GetColorZones {
+				start_index: 0,
+				end_index: 255
+			}
+			.expected_response(),
+			&[MessageType::StateZone, MessageType::StateMultiZone]
+		);
+		assert!(Message::Acknowledgement.expected_response().is_empty());
+		assert!(Message::StatePower {
+			level: PowerLevel::Enabled
+		}
+		.expected_response()
+		.is_empty());
+	}
+
+	#[test]
+	fn test_payload_size() {
+		let messages = vec![
+			Message::GetService,
+			Message::SetPower {
+				level: PowerLevel::Enabled,
+			},
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
+			},
+			Message::SetGroup {
+				group: LifxIdent([0; 16]),
+				label: LifxString::new("Downstairs"),
+				updated_at: 0,
+			},
+			Message::LightSetColor {
+				reserved: 0,
+				color: HSBK::white(Kelvin::Neutral, 1.0),
+				duration: 1024,
+			},
+			Message::SetWaveformOptional {
+				reserved: 0,
+				transient: true,
+				color: HSBK::white(Kelvin::Neutral, 1.0),
+				period: 1000,
+				cycles: 1.0,
+				skew_ratio: 0,
+				waveform: Waveform::Pulse,
+				set_hue: false,
+				set_saturation: false,
+				set_brightness: true,
+				set_kelvin: false,
+			},
+			Message::LightSetPower {
+				level: 65535,
+				duration: 0,
+			},
+			Message::SetColorZones {
+				start_index: 0,
+				end_index: 7,
+				color: HSBK::white(Kelvin::Neutral, 1.0),
+				duration: 0,
+				apply: ApplicationRequest::Apply,
+			},
+			Message::SetExtendedColorZones {
+				duration: 0,
+				apply: ApplicationRequest::Apply,
+				zone_index: 0,
+				colors_count: 1,
+				colors: [HSBK::white(Kelvin::Neutral, 1.0); 82],
+			},
+		];
+
+		for msg in messages {
+			let expected = msg.payload_size();
+			let payload = msg.to_payload().unwrap();
+			assert_eq!(payload.len(), expected);
+
+			// small payloads stay inline; large ones are allocated once, at the right size
+			let raw = RawMessage::build(&BuildOptions::default(), msg).unwrap();
+			assert_eq!(raw.payload[..], payload[..]);
+			assert_eq!(raw.payload.spilled(), expected > raw.payload.inline_size());
+			assert_eq!(raw.payload.capacity(), expected.max(raw.payload.inline_size()));
+		}
+	}
+
+	#[test]
+	fn test_state_service_layout() {
+		// the service comes first on the wire, then the port
+		let message = Message::StateService {
+			port: 56700,
+			service: Service::UDP,
+		};
+		let raw = RawMessage::build(&BuildOptions::default(), message.clone()).unwrap();
+		assert_eq!(&raw.payload[..], &[1, 0x7c, 0xdd, 0, 0]);
+		assert_eq!(Message::from_raw(&raw).unwrap(), message);
+	}
+
+	#[test]
+	fn test_set_messages_round_trip() {
+		let messages = vec![
+			Message::SetPower {
+				level: PowerLevel::Enabled,
+			},
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
+			},
+			Message::SetLocation {
+				location: LifxIdent([7; 16]),
+				label: LifxString::new("Home"),
+				updated_at: 1_600_000_000_000_000_000,
+			},
+			Message::SetGroup {
+				group: LifxIdent([3; 16]),
+				label: LifxString::new("Downstairs"),
+				updated_at: 1_600_000_000_000_000_000,
+			},
+			Message::SetWaveform {
+				reserved: 0,
+				transient: true,
+				color: HSBK::white(Kelvin::Neutral, 1.0),
+				period: 1000,
+				cycles: 2.5,
+				skew_ratio: -1200,
+				waveform: Waveform::Sine,
+			},
+			Message::SetWaveformOptional {
+				reserved: 0,
+				transient: false,
+				color: HSBK::white(Kelvin::Incandescent, 0.5),
+				period: 500,
+				cycles: 1.0,
+				skew_ratio: 0,
+				waveform: Waveform::Pulse,
+				set_hue: false,
+				set_saturation: false,
+				set_brightness: true,
+				set_kelvin: true,
+			},
+			Message::LightSetInfrared { brightness: 32768 },
+		];
+
+		for msg in messages {
+			let raw = RawMessage::build(&BuildOptions::default(), msg.clone()).unwrap();
+			let unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+			assert_eq!(Message::from_raw(&unpacked).unwrap(), msg);
+		}
+	}
+
+	#[test]
+	fn test_newer_messages_round_trip() {
+		let red = HSBK::color(0, 1.0, 1.0);
+		let blue = HSBK::color(240, 1.0, 0.5);
+		let mut zones = [red; 82];
+		zones[81] = blue;
+		let mut pixels = [blue; 64];
+		pixels[0] = red;
+		let mut palette = [red; 16];
+		palette[1] = blue;
+		let mut tiles = [Tile::default(); 16];
+		tiles[1] = Tile {
+			user_x: 1.5,
+			user_y: -0.5,
+			width: 8,
+			height: 8,
+			device_version_vendor: 1,
+			device_version_product: 55,
+			firmware_version_major: 3,
+			firmware_version_minor: 70,
+			..Tile::default()
+		};
+		let mut buttons: [Button; 8] = Default::default();
+		buttons[0].actions_count = 2;
+		buttons[0].actions[0] = ButtonAction {
+			gesture: ButtonGesture::Press,
+			target: ButtonTarget::Group(LifxIdent([4; 16])),
+		};
+		buttons[0].actions[1] = ButtonAction {
+			gesture: ButtonGesture::PressHold,
+			target: ButtonTarget::Device {
+				serial: [0xd0, 0x73, 0xd5, 1, 2, 3],
+				reserved: [0; 10],
+			},
+		};
+		let parameters = [1, 2, 3, 4, 5, 6, 7, 0xffff_ffff];
+
+		let messages = vec![
+			Message::GetHevCycle,
+			Message::SetHevCycle {
+				enable: true,
+				duration: 7200,
+			},
+			Message::StateHevCycle {
+				duration: 7200,
+				remaining: 3600,
+				last_power: true,
+			},
+			Message::GetHevCycleConfiguration,
+			Message::SetHevCycleConfiguration {
+				indication: true,
+				duration: 3600,
+			},
+			Message::StateHevCycleConfiguration {
+				indication: false,
+				duration: 1800,
+			},
+			Message::GetLastHevCycleResult,
+			Message::StateLastHevCycleResult {
+				result: LastHevCycleResult::InterruptedByLan,
+			},
+			Message::StateUnhandled { unhandled_type: 502 },
+			Message::SetReboot,
+			Message::GetExtendedColorZones,
+			Message::SetExtendedColorZones {
+				duration: 1000,
+				apply: ApplicationRequest::ApplyOnly,
+				zone_index: 8,
+				colors_count: 82,
+				colors: zones,
+			},
+			Message::StateExtendedColorZones {
+				zones_count: 120,
+				zone_index: 82,
+				colors_count: 38,
+				colors: zones,
+			},
+			Message::GetMultiZoneEffect,
+			Message::SetMultiZoneEffect {
+				instance_id: 0x1234_5678,
+				typ: MultiZoneEffectType::Move,
+				reserved: 0,
+				speed: 3000,
+				duration: 60_000_000_000,
+				reserved2: 0,
+				reserved3: 0,
+				parameters,
+			},
+			Message::StateMultiZoneEffect {
+				instance_id: 0x1234_5678,
+				typ: MultiZoneEffectType::Off,
+				reserved: 0,
+				speed: 0,
+				duration: 0,
+				reserved2: 0,
+				reserved3: 0,
+				parameters,
+			},
+			Message::GetDeviceChain,
+			Message::StateDeviceChain {
+				start_index: 0,
+				tile_devices: tiles,
+				tile_devices_count: 2,
+			},
+			Message::SetUserPosition {
+				tile_index: 1,
+				reserved: 0,
+				user_x: 1.5,
+				user_y: -0.5,
+			},
+			Message::Get64 {
+				tile_index: 1,
+				length: 1,
+				reserved: 0,
+				x: 0,
+				y: 0,
+				width: 8,
+			},
+			Message::Set64 {
+				tile_index: 1,
+				length: 1,
+				reserved: 0,
+				x: 0,
+				y: 0,
+				width: 8,
+				duration: 500,
+				colors: pixels,
+			},
+			Message::State64 {
+				tile_index: 1,
+				reserved: 0,
+				x: 0,
+				y: 0,
+				width: 8,
+				colors: pixels,
+			},
+			Message::GetTileEffect {
+				reserved: 0,
+				reserved2: 0,
+			},
+			Message::SetTileEffect {
+				reserved: 0,
+				reserved2: 0,
+				instance_id: 42,
+				typ: TileEffectType::Morph,
+				speed: 2000,
+				duration: 0,
+				reserved3: 0,
+				reserved4: 0,
+				parameters,
+				palette_count: 2,
+				palette,
+			},
+			Message::StateTileEffect {
+				reserved: 0,
+				instance_id: 42,
+				typ: TileEffectType::Sky,
+				speed: 2000,
+				duration: 0,
+				reserved2: 0,
+				reserved3: 0,
+				parameters,
+				palette_count: 2,
+				palette,
+			},
+			Message::GetButton,
+			Message::SetButton {
+				index: 0,
+				buttons_count: 1,
+				buttons: buttons.clone(),
+			},
+			Message::StateButton {
+				count: 4,
+				index: 0,
+				buttons_count: 1,
+				buttons,
+			},
+		];
+
+		for msg in messages {
+			let raw = RawMessage::build(&BuildOptions::default(), msg.clone()).unwrap();
+			assert_eq!(raw.payload.len(), msg.message_type().payload_size(), "{:?}", msg);
+			let unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+			let strict = DecodeOptions::strict();
+			assert_eq!(Message::from_raw_with(&unpacked, &strict).unwrap(), msg);
+		}
+	}
+
+	#[test]
+	fn test_strict_decoding() {
+		let strict = DecodeOptions::strict();
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::LightSetColor {
+				reserved: 0,
+				color: HSBK::white(Kelvin::Neutral, 1.0),
+				duration: 1024,
+			},
+		)
+		.unwrap();
+		let bytes = raw.pack().unwrap();
+		let unpacked = RawMessage::unpack_with(&bytes, &strict).unwrap();
+		assert!(Message::from_raw_with(&unpacked, &strict).is_ok());
+
+		// non-zero reserved payload field
+		let mut msg = unpacked.clone();
+		msg.payload[0] = 1;
+		assert!(Message::from_raw(&msg).is_ok());
+		assert!(matches!(
+			Message::from_raw_with(&msg, &strict),
+			Err(Error::ReservedFieldNonZero {
+				typ: Some(102),
+				field: "reserved"
+			})
+		));
+
+		// truncated payload
+		let mut msg = unpacked.clone();
+		msg.payload.pop();
+		assert!(matches!(
+			Message::from_raw(&msg),
+			Err(Error::PayloadSizeMismatch {
+				typ: 102,
+				expected: 13,
+				actual: 12
+			})
+		));
+
+		// trailing payload data
+		let mut msg = unpacked.clone();
+		msg.payload.push(0);
+		assert!(Message::from_raw(&msg).is_ok());
+		assert!(matches!(
+			Message::from_raw_with(&msg, &strict),
+			Err(Error::PayloadSizeMismatch {
+				typ: 102,
+				expected: 13,
+				actual: 14
+			})
+		));
+
+		// non-zero reserved header field
+		let mut bytes = bytes;
+		bytes[8 + 8] = 1;
+		assert!(RawMessage::unpack(&bytes).is_ok());
+		assert!(matches!(
+			RawMessage::unpack_with(&bytes, &strict),
+			Err(Error::ReservedFieldNonZero { typ: None, .. })
+		));
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_serde_round_trip() {
+		let messages = vec![
+			Message::SetLabel {
+				label: LifxString::new("Kitchen"),
+			},
+			Message::EchoRequest {
+				payload: EchoPayload([0xa5; 64]),
+			},
+			Message::SetExtendedColorZones {
+				duration: 1000,
+				apply: ApplicationRequest::Apply,
+				zone_index: 0,
+				colors_count: 82,
+				colors: [HSBK::white(Kelvin::Neutral, 0.5); 82],
+			},
+		];
+
+		for msg in messages {
+			let json = serde_json::to_string(&msg).unwrap();
+			let decoded: Message = serde_json::from_str(&json).unwrap();
+			assert_eq!(decoded, msg);
+		}
+
+		let raw = RawMessage::build(&BuildOptions::default(), Message::GetService).unwrap();
+		let json = serde_json::to_string(&raw).unwrap();
+		assert_eq!(serde_json::from_str::<RawMessage>(&json).unwrap(), raw);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_saved_state() {
+		use udp::{Color, SavedState};
+
+		let addr = "192.168.1.20:56700".parse().unwrap();
+		let queue = udp::SendQueue::new();
+		let mut bulb = udp::Bulb::new(0, MacAddress([1; 6]), queue.clone(), addr);
+		bulb.name.update("Lamp".to_owned());
+		bulb.set_location(LifxIdent([1; 16]), "Home".to_owned());
+		bulb.set_group(LifxIdent([2; 16]), "Den".to_owned());
+		bulb.set_model(1, 31);
+		bulb.host_firmware.update(0x30050);
+		// not known yet, or not worth saving
+		let mut other = udp::Bulb::new(0, MacAddress([2; 6]), queue.clone(), addr);
+		other.power_level.update(PowerLevel::Enabled);
+
+		let path = std::env::temp_dir().join(format!("lifx-state-{}.json", std::process::id()));
+		let state = SavedState::new(vec![&other, &bulb]);
+		state.save(&path).unwrap();
+		let loaded = SavedState::load(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(loaded, state);
+		assert_eq!(loaded.bulbs[0].target, MacAddress([1; 6]));
+		assert_eq!(loaded.bulbs[1].name, None);
+
+		let restored = loaded.bulbs[0].restore(0, queue.clone());
+		assert_eq!(restored.name.as_ref().map(String::as_str), Some("Lamp"));
+		assert_eq!(restored.location_id(), Some(&LifxIdent([1; 16])));
+		assert_eq!(restored.group.as_ref().map(String::as_str), Some("Den"));
+		assert_eq!(restored.product_info().unwrap().name, "LIFX Z");
+		assert!(matches!(restored.color, Color::Multi(_)));
+		// restored details are confirmed with the bulb on the next refresh
+		assert!(restored.name.needs_refresh());
+		assert!(restored.host_firmware.needs_refresh());
+		assert_eq!(loaded.bulbs[1].restore(0, queue).power(), None);
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn test_bytes_round_trip() {
+		use bytes::{Buf, Bytes, BytesMut};
+
+		let raw = RawMessage::build(
+			&BuildOptions::default(),
+			Message::light_set_color(HSBK::white(Kelvin::Neutral, 1.0), Default::default()),
+		)
+		.unwrap();
+
+		let mut buf = BytesMut::new();
+		raw.pack_buf(&mut buf).unwrap();
+		assert_eq!(&buf[..], &raw.pack().unwrap()[..]);
+
+		assert_eq!(RawMessage::unpack_buf(&mut buf).unwrap(), raw);
+		assert!(buf.is_empty());
+
+		// a message split across two chunks
+		let packed = Bytes::from(raw.pack().unwrap());
+		let chained = packed.slice(..10).chain(packed.slice(10..));
+		assert_eq!(RawMessage::unpack_buf(chained).unwrap(), raw);
+
+		let mut small = [0u8; 16];
+		assert!(matches!(
+			raw.pack_buf(&mut &mut small[..]),
+			Err(Error::BufferTooSmall { .. })
+		));
+	}
+
+	#[test]
+	fn test_extended_color_zones_round_trip() {
+		let mut colors = [HSBK::white(Kelvin::Neutral, 0.0); 82];
+		for (idx, color) in colors.iter_mut().enumerate() {
+			color.hue = idx as u16 * 700;
+			color.saturation = 65535;
+		}
+
+		let msg = Message::SetExtendedColorZones {
+			duration: 1000,
+			apply: ApplicationRequest::Apply,
+			zone_index: 82,
+			colors_count: 24,
+			colors,
+		};
+		let raw = RawMessage::build(&BuildOptions::default(), msg).unwrap();
+		assert_eq!(raw.payload.len(), 664);
+		let bytes = raw.pack().unwrap();
+
+		let unpacked = RawMessage::unpack(&bytes).unwrap();
+		match Message::from_raw(&unpacked).unwrap() {
+			Message::SetExtendedColorZones {
+				duration,
+				apply,
+				zone_index,
+				colors_count,
+				colors: c,
+			} => {
+				assert_eq!(duration, 1000);
+				assert!(matches!(apply, ApplicationRequest::Apply));
+				assert_eq!(zone_index, 82);
+				assert_eq!(colors_count, 24);
+				assert_eq!(c, colors);
+			}
+			msg => panic!("unexpected message {:?}", msg),
+		}
+
+		let msg = Message::StateExtendedColorZones {
+			zones_count: 120,
+			zone_index: 82,
+			colors_count: 38,
+			colors,
+		};
+		let raw = RawMessage::build(&BuildOptions::default(), msg).unwrap();
+		assert_eq!(raw.payload.len(), 661);
+		let unpacked = RawMessage::unpack(&raw.pack().unwrap()).unwrap();
+		match Message::from_raw(&unpacked).unwrap() {
+			Message::StateExtendedColorZones {
+				zones_count,
+				zone_index,
+				colors_count,
+				colors: c,
+			} => {
+				assert_eq!(zones_count, 120);
+				assert_eq!(zone_index, 82);
+				assert_eq!(colors_count, 38);
+				assert_eq!(c, colors);
+			}
+			msg => panic!("unexpected message {:?}", msg),
+		}
 	}
 }
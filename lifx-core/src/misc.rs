@@ -1,38 +1,32 @@
-use std::{io, convert::TryFrom};
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::{
+	convert::TryFrom,
+	io::{self, Read, Write},
+};
 
 use crate::{
 	error::Error,
-	read_write::{LittleEndianReader, LittleEndianWriter},
+	read_write::{invalid_data, Codec},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LifxIdent(pub [u8; 16]);
 
-impl<R: ReadBytesExt> LittleEndianReader<LifxIdent> for R {
-	fn read_val(&mut self) -> Result<LifxIdent, io::Error> {
-		let mut val = [0; 16];
-		for v in &mut val {
-			*v = self.read_val()?;
-		}
-		Ok(LifxIdent(val))
+impl Codec for LifxIdent {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.0.encode(w)
 	}
-}
 
-impl<T> LittleEndianWriter<LifxIdent> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: LifxIdent) -> Result<(), io::Error> {
-		for idx in 0..16 {
-			self.write_u8(v.0[idx])?;
-		}
-		Ok(())
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<LifxIdent, io::Error> {
+		Ok(LifxIdent(Codec::decode(r)?))
 	}
 }
 
-#[derive(Copy, Clone)]
-pub struct EchoPayload(pub [u8; 64]);
+#[derive(Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EchoPayload(
+	#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))] pub [u8; 64],
+);
 
 impl std::fmt::Debug for EchoPayload {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
@@ -40,41 +34,31 @@ impl std::fmt::Debug for EchoPayload {
 	}
 }
 
-impl<R: ReadBytesExt> LittleEndianReader<EchoPayload> for R {
-	fn read_val(&mut self) -> Result<EchoPayload, io::Error> {
-		let mut val = [0; 64];
-		for v in val.iter_mut() {
-			*v = self.read_val()?;
-		}
-		Ok(EchoPayload(val))
+impl Codec for EchoPayload {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.0.encode(w)
 	}
-}
 
-impl<T> LittleEndianWriter<EchoPayload> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: EchoPayload) -> Result<(), io::Error> {
-		for idx in 0..64 {
-			self.write_u8(v.0[idx])?;
-		}
-		Ok(())
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<EchoPayload, io::Error> {
+		Ok(EchoPayload(Codec::decode(r)?))
 	}
 }
 
 #[repr(u16)]
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PowerLevel {
 	Standby = 0,
 	Enabled = 65535,
 }
 
-impl<T> LittleEndianWriter<PowerLevel> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: PowerLevel) -> Result<(), io::Error> {
-		self.write_u16::<LittleEndian>(v as u16)
+impl Codec for PowerLevel {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u16).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<PowerLevel, io::Error> {
+		PowerLevel::try_from(u16::decode(r)?).map_err(invalid_data)
 	}
 }
 
@@ -84,7 +68,11 @@ impl TryFrom<u16> for PowerLevel {
 		match val {
 			x if x == PowerLevel::Enabled as u16 => Ok(PowerLevel::Enabled),
 			x if x == PowerLevel::Standby as u16 => Ok(PowerLevel::Standby),
-			x => Err(Error::ProtocolError(format!("Unknown power level {}", x))),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "level",
+				value: x as u32,
+			}),
 		}
 	}
 }
@@ -96,20 +84,243 @@ impl TryFrom<u16> for PowerLevel {
 /// service cannot be constructed.
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Service {
 	UDP = 1,
 }
 
+impl Codec for Service {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Service, io::Error> {
+		Service::try_from(u8::decode(r)?).map_err(invalid_data)
+	}
+}
+
 impl TryFrom<u8> for Service {
 	type Error = Error;
 	fn try_from(val: u8) -> Result<Service, Error> {
 		if val != Service::UDP as u8 {
-			Err(Error::ProtocolError(format!(
-				"Unknown service value {}",
-				val
-			)))
+			Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "service",
+				value: val as u32,
+			})
 		} else {
 			Ok(Service::UDP)
 		}
 	}
 }
+
+/// The firmware effects that can run on multizone devices.
+///
+/// See also [Message::SetMultiZoneEffect].
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MultiZoneEffectType {
+	Off = 0,
+	/// Moves the current zone colors along the strip.  `parameters[1]` sets the direction: `0`
+	/// moves towards the start of the strip, `1` moves away from it.
+	Move = 1,
+}
+
+impl Codec for MultiZoneEffectType {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<MultiZoneEffectType, io::Error> {
+		MultiZoneEffectType::try_from(u8::decode(r)?).map_err(invalid_data)
+	}
+}
+
+impl TryFrom<u8> for MultiZoneEffectType {
+	type Error = Error;
+	fn try_from(val: u8) -> Result<MultiZoneEffectType, Error> {
+		match val {
+			0 => Ok(MultiZoneEffectType::Off),
+			1 => Ok(MultiZoneEffectType::Move),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "typ",
+				value: x as u32,
+			}),
+		}
+	}
+}
+
+/// The firmware effects that can run on tile-class devices.
+///
+/// See also [Message::SetTileEffect].
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileEffectType {
+	Off = 0,
+	/// Animated blobs of color drawn from the palette
+	Morph = 2,
+	/// A flickering fire effect
+	Flame = 3,
+	/// A sunrise/sunset or clouds effect (only on newer devices)
+	Sky = 5,
+}
+
+impl Codec for TileEffectType {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<TileEffectType, io::Error> {
+		TileEffectType::try_from(u8::decode(r)?).map_err(invalid_data)
+	}
+}
+
+impl TryFrom<u8> for TileEffectType {
+	type Error = Error;
+	fn try_from(val: u8) -> Result<TileEffectType, Error> {
+		match val {
+			0 => Ok(TileEffectType::Off),
+			2 => Ok(TileEffectType::Morph),
+			3 => Ok(TileEffectType::Flame),
+			5 => Ok(TileEffectType::Sky),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "typ",
+				value: x as u32,
+			}),
+		}
+	}
+}
+
+/// The outcome of the most recent HEV cycle on a LIFX Clean device.
+///
+/// See also [Message::StateLastHevCycleResult].
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LastHevCycleResult {
+	Success = 0,
+	Busy = 1,
+	InterruptedByReset = 2,
+	InterruptedByHomekit = 3,
+	InterruptedByLan = 4,
+	InterruptedByCloud = 5,
+	/// No cycle has run yet
+	None = 255,
+}
+
+impl Codec for LastHevCycleResult {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		(*self as u8).encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<LastHevCycleResult, io::Error> {
+		LastHevCycleResult::try_from(u8::decode(r)?).map_err(invalid_data)
+	}
+}
+
+impl TryFrom<u8> for LastHevCycleResult {
+	type Error = Error;
+	fn try_from(val: u8) -> Result<LastHevCycleResult, Error> {
+		match val {
+			0 => Ok(LastHevCycleResult::Success),
+			1 => Ok(LastHevCycleResult::Busy),
+			2 => Ok(LastHevCycleResult::InterruptedByReset),
+			3 => Ok(LastHevCycleResult::InterruptedByHomekit),
+			4 => Ok(LastHevCycleResult::InterruptedByLan),
+			5 => Ok(LastHevCycleResult::InterruptedByCloud),
+			255 => Ok(LastHevCycleResult::None),
+			x => Err(Error::InvalidEnumValue {
+				typ: None,
+				field: "result",
+				value: x as u32,
+			}),
+		}
+	}
+}
+
+/// The MAC address of a device, used as the [FrameAddress::target](crate::FrameAddress::target)
+/// of messages sent to it.
+///
+/// On the wire this is a little-endian `u64`, whose first 6 bytes are the address (in order) and
+/// whose last 2 bytes are zero.  The all-zero address targets every device.
+///
+/// Parses from (and displays as) the usual colon-separated hex form, e.g. `d0:73:d5:02:97:de`.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+	/// The all-zero address, used to target every device.
+	pub const ALL: MacAddress = MacAddress([0; 6]);
+
+	pub fn from_bytes(bytes: [u8; 6]) -> MacAddress {
+		MacAddress(bytes)
+	}
+
+	pub fn to_bytes(self) -> [u8; 6] {
+		self.0
+	}
+
+	/// True if this is the all-zero address (see [MacAddress::ALL]).
+	pub fn is_all(&self) -> bool {
+		*self == MacAddress::ALL
+	}
+}
+
+impl From<u64> for MacAddress {
+	fn from(target: u64) -> MacAddress {
+		let mut bytes = [0; 6];
+		bytes.copy_from_slice(&target.to_le_bytes()[..6]);
+		MacAddress(bytes)
+	}
+}
+
+impl From<MacAddress> for u64 {
+	fn from(mac: MacAddress) -> u64 {
+		let mut bytes = [0; 8];
+		bytes[..6].copy_from_slice(&mac.0);
+		u64::from_le_bytes(bytes)
+	}
+}
+
+impl std::fmt::Display for MacAddress {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let b = &self.0;
+		write!(
+			f,
+			"{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+			b[0], b[1], b[2], b[3], b[4], b[5]
+		)
+	}
+}
+
+impl std::fmt::Debug for MacAddress {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "MacAddress({})", self)
+	}
+}
+
+impl std::str::FromStr for MacAddress {
+	type Err = Error;
+	fn from_str(s: &str) -> Result<MacAddress, Error> {
+		let invalid = || Error::InvalidMacAddress(s.to_owned());
+
+		let mut bytes = [0; 6];
+		let mut parts = s.split(&[':', '-'][..]);
+		for byte in bytes.iter_mut() {
+			let part = parts.next().ok_or_else(invalid)?;
+			if part.len() != 2 {
+				return Err(invalid());
+			}
+			*byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+		}
+		if parts.next().is_some() {
+			return Err(invalid());
+		}
+		Ok(MacAddress(bytes))
+	}
+}
@@ -1,41 +1,88 @@
-use std::{convert::TryInto, io::Cursor};
+use std::{
+	convert::{TryFrom, TryInto},
+	io::Cursor,
+	time::Duration,
+};
+
+use byteorder::WriteBytesExt;
+use smallvec::SmallVec;
 
 use crate::{
+	button::Button,
 	color::{ApplicationRequest, HSBK, Waveform},
 	error::Error,
-	protocol::{Frame, FrameAddress, ProtocolHeader},
-	read_write::{LittleEndianReader, LittleEndianWriter},
+	msg_type::MessageType,
+	protocol::{buffer_prefix, Frame, FrameAddress, ProtocolHeader},
+	read_write::Codec,
 	string::LifxString,
-	misc::{EchoPayload, LifxIdent, PowerLevel, Service},
+	time,
+	tile::Tile,
+	misc::{
+		EchoPayload, LastHevCycleResult, LifxIdent, MacAddress, MultiZoneEffectType, PowerLevel,
+		Service, TileEffectType,
+	},
 };
 
 macro_rules! unpack {
-	($msg:ident, $typ:ident, $( $n:ident: $t:ident ),*) => {
+	($msg:ident, $opts:ident, $typ:ident, $( $n:ident: $t:ty ),*) => {
 		 {
-		 let mut c = Cursor::new(&$msg.payload);
+		 let typ = $msg.protocol_header.typ;
+		 let mut c = Cursor::new($msg.payload);
 		 $(
-			  let $n: $t = c.read_val()?;
+			  let offset = c.position() as usize;
+			  let $n: $t = Codec::decode(&mut c).map_err(|e| {
+					Error::from_read(e, typ, stringify!($n), offset, $msg.payload.len())
+			  })?;
+			  if $opts.strict
+					&& stringify!($n).starts_with("reserved")
+					&& $msg.payload[offset..c.position() as usize].iter().any(|b| *b != 0)
+			  {
+					return Err(Error::ReservedFieldNonZero {
+						 typ: Some(typ),
+						 field: stringify!($n),
+					});
+			  }
 		 )*
 
 			  Message::$typ {
 			  $(
-						 $n: $n.try_into()?,
+						 $n: $n
+							  .try_into()
+							  .map_err(|e| Error::from(e).in_field(typ, stringify!($n)))?,
 			  )*
 		 }
 		 }
 	};
 }
 
+/// The combined size of the [Frame], [FrameAddress] and [ProtocolHeader].
+pub(crate) const HEADER_SIZE: usize =
+	Frame::PACKED_SIZE + FrameAddress::PACKED_SIZE + ProtocolHeader::PACKED_SIZE;
+
+/// The size (in bytes) of the largest known message, a [Message::StateDeviceChain].
+///
+/// Useful for sizing send buffers.  Devices may send undocumented messages that are larger than
+/// this, so receive buffers should have some room to spare.
+pub const MAX_MESSAGE_SIZE: usize = HEADER_SIZE + 882;
+
+/// The storage for [RawMessage::payload].
+///
+/// Payloads of up to 64 bytes (which covers nearly every message) are stored inline, so building or
+/// unpacking a [RawMessage] doesn't allocate.  Larger ones, like the tile and extended multizone
+/// messages, spill onto the heap.
+pub type Payload = SmallVec<[u8; 64]>;
+
 /// Options used to contruct a [RawMessage].
 ///
 /// See also [RawMessage::build].
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BuildOptions {
-	/// If not `None`, this is the ID of the device you want to address.
+	/// If not `None`, this is the MAC address of the device you want to address.
 	///
 	/// To look up the ID of a device, extract it from the [FrameAddress::target] field when a
 	/// device sends a [Message::StateService] message.
-	pub target: Option<u64>,
+	pub target: Option<MacAddress>,
 	/// Acknowledgement message required.
 	///
 	/// Causes the light to send an [Message::Acknowledgement] message.
@@ -56,6 +103,7 @@ pub struct BuildOptions {
 	/// If the source is non-zero, then the LIFX device with send a unicast message to the IP
 	/// address/port of the client that sent the originating message.  If zero, then the LIFX
 	/// device may send a broadcast message that can be received by all clients on the same sub-net.
+	/// See [random_source](crate::random_source) for picking one.
 	pub source: u32,
 }
 
@@ -71,6 +119,51 @@ impl std::default::Default for BuildOptions {
 	}
 }
 
+/// Options used when decoding a [RawMessage] or a [Message].
+///
+/// See also [RawMessage::unpack_with] and [Message::from_raw_with].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DecodeOptions {
+	/// If true, reject anything that doesn't exactly match the documented protocol: non-zero
+	/// reserved fields (in the headers or the payload), payloads of the wrong length, and unknown
+	/// enum values (like an undocumented [Service]).
+	///
+	/// If false (the default), accept anything that can be decoded.  Messages holding unknown enum
+	/// values are kept as [Message::Undecoded].
+	pub strict: bool,
+}
+
+impl DecodeOptions {
+	/// Options that reject anything not exactly matching the documented protocol.
+	pub fn strict() -> DecodeOptions {
+		DecodeOptions { strict: true }
+	}
+
+	/// Options that accept anything that can be decoded.
+	pub fn lenient() -> DecodeOptions {
+		DecodeOptions { strict: false }
+	}
+}
+
+/// The header fields that tie a response to the request that caused it.
+///
+/// A device copies `source` and `sequence` from the request into every reply (including a
+/// [Message::Acknowledgement]), and sets `target` to its own address.
+///
+/// See also [RawMessage::response_meta].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResponseMeta {
+	/// The [Frame::source] of the request
+	pub source: u32,
+	/// The [FrameAddress::sequence] of the request
+	pub sequence: u8,
+	/// The device that sent the response
+	pub target: MacAddress,
+}
+
 impl RawMessage {
 	/// Build a RawMessage (which is suitable for sending on the network) from a given Message
 	/// type.
@@ -87,7 +180,7 @@ impl RawMessage {
 			source: options.source,
 		};
 		let addr = FrameAddress {
-			target: options.target.unwrap_or(0),
+			target: options.target.unwrap_or(MacAddress::ALL),
 			reserved: [0; 6],
 			reserved2: 0,
 			ack_required: options.ack_required,
@@ -100,328 +193,141 @@ impl RawMessage {
 			typ: typ.get_num(),
 		};
 
-		let mut v = Vec::new();
-		match typ {
-			Message::GetService
-			| Message::GetHostInfo
-			| Message::GetHostFirmware
-			| Message::GetWifiFirmware
-			| Message::GetWifiInfo
-			| Message::GetPower
-			| Message::GetLabel
-			| Message::GetVersion
-			| Message::GetInfo
-			| Message::Acknowledgement { .. }
-			| Message::GetLocation
-			| Message::GetGroup
-			| Message::LightGet
-			| Message::LightGetPower
-			| Message::LightGetInfrared => {
-				// these types have no payload
-			}
-			Message::SetColorZones {
-				start_index,
-				end_index,
-				color,
-				duration,
-				apply,
-			} => {
-				v.write_val(start_index)?;
-				v.write_val(end_index)?;
-				v.write_val(color)?;
-				v.write_val(duration)?;
-				v.write_val(apply)?;
-			}
-			Message::SetWaveform {
-				reserved,
-				transient,
-				color,
-				period,
-				cycles,
-				skew_ratio,
-				waveform,
-			} => {
-				v.write_val(reserved)?;
-				v.write_val(transient)?;
-				v.write_val(color)?;
-				v.write_val(period)?;
-				v.write_val(cycles)?;
-				v.write_val(skew_ratio)?;
-				v.write_val(waveform)?;
-			}
-			Message::SetWaveformOptional {
-				reserved,
-				transient,
-				color,
-				period,
-				cycles,
-				skew_ratio,
-				waveform,
-				set_hue,
-				set_saturation,
-				set_brightness,
-				set_kelvin,
-			} => {
-				v.write_val(reserved)?;
-				v.write_val(transient)?;
-				v.write_val(color)?;
-				v.write_val(period)?;
-				v.write_val(cycles)?;
-				v.write_val(skew_ratio)?;
-				v.write_val(waveform)?;
-				v.write_val(set_hue)?;
-				v.write_val(set_saturation)?;
-				v.write_val(set_brightness)?;
-				v.write_val(set_kelvin)?;
-			}
-			Message::GetColorZones {
-				start_index,
-				end_index,
-			} => {
-				v.write_val(start_index)?;
-				v.write_val(end_index)?;
-			}
-			Message::StateZone {
-				count,
-				index,
-				color,
-			} => {
-				v.write_val(count)?;
-				v.write_val(index)?;
-				v.write_val(color)?;
-			}
-			Message::StateMultiZone {
-				count,
-				index,
-				color0,
-				color1,
-				color2,
-				color3,
-				color4,
-				color5,
-				color6,
-				color7,
-			} => {
-				v.write_val(count)?;
-				v.write_val(index)?;
-				v.write_val(color0)?;
-				v.write_val(color1)?;
-				v.write_val(color2)?;
-				v.write_val(color3)?;
-				v.write_val(color4)?;
-				v.write_val(color5)?;
-				v.write_val(color6)?;
-				v.write_val(color7)?;
-			}
-			Message::LightStateInfrared { brightness } => v.write_val(brightness)?,
-			Message::LightSetInfrared { brightness } => v.write_val(brightness)?,
-			Message::SetLocation {
-				location,
-				label,
-				updated_at,
-			} => {
-				v.write_val(location)?;
-				v.write_val(label)?;
-				v.write_val(updated_at)?;
-			}
-			Message::SetGroup {
-				group,
-				label,
-				updated_at,
-			} => {
-				v.write_val(group)?;
-				v.write_val(label)?;
-				v.write_val(updated_at)?;
-			}
-			Message::StateService { port, service } => {
-				v.write_val(port)?;
-				v.write_val(service as u8)?;
-			}
-			Message::StateHostInfo {
-				signal,
-				tx,
-				rx,
-				reserved,
-			} => {
-				v.write_val(signal)?;
-				v.write_val(tx)?;
-				v.write_val(rx)?;
-				v.write_val(reserved)?;
-			}
-			Message::StateHostFirmware {
-				build,
-				reserved,
-				version,
-			} => {
-				v.write_val(build)?;
-				v.write_val(reserved)?;
-				v.write_val(version)?;
-			}
-			Message::StateWifiInfo {
-				signal,
-				tx,
-				rx,
-				reserved,
-			} => {
-				v.write_val(signal)?;
-				v.write_val(tx)?;
-				v.write_val(rx)?;
-				v.write_val(reserved)?;
-			}
-			Message::StateWifiFirmware {
-				build,
-				reserved,
-				version,
-			} => {
-				v.write_val(build)?;
-				v.write_val(reserved)?;
-				v.write_val(version)?;
-			}
-			Message::SetPower { level } => {
-				v.write_val(level)?;
-			}
-			Message::StatePower { level } => {
-				v.write_val(level)?;
-			}
-			Message::SetLabel { label } => {
-				v.write_val(label)?;
-			}
-			Message::StateLabel { label } => {
-				v.write_val(label)?;
-			}
-			Message::StateVersion {
-				vendor,
-				product,
-				version,
-			} => {
-				v.write_val(vendor)?;
-				v.write_val(product)?;
-				v.write_val(version)?;
-			}
-			Message::StateInfo {
-				time,
-				uptime,
-				downtime,
-			} => {
-				v.write_val(time)?;
-				v.write_val(uptime)?;
-				v.write_val(downtime)?;
-			}
-			Message::StateLocation {
-				location,
-				label,
-				updated_at,
-			} => {
-				v.write_val(location)?;
-				v.write_val(label)?;
-				v.write_val(updated_at)?;
-			}
-			Message::StateGroup {
-				group,
-				label,
-				updated_at,
-			} => {
-				v.write_val(group)?;
-				v.write_val(label)?;
-				v.write_val(updated_at)?;
-			}
-			Message::EchoRequest { payload } => {
-				v.write_val(payload)?;
-			}
-			Message::EchoResponse { payload } => {
-				v.write_val(payload)?;
-			}
-			Message::LightSetColor {
-				reserved,
-				color,
-				duration,
-			} => {
-				v.write_val(reserved)?;
-				v.write_val(color)?;
-				v.write_val(duration)?;
-			}
-			Message::LightState {
-				color,
-				reserved,
-				power,
-				label,
-				reserved2,
-			} => {
-				v.write_val(color)?;
-				v.write_val(reserved)?;
-				v.write_val(power)?;
-				v.write_val(label)?;
-				v.write_val(reserved2)?;
-			}
-			Message::LightSetPower { level, duration } => {
-				v.write_val(if level > 0 { 65535u16 } else { 0u16 })?;
-				v.write_val(duration)?;
-			}
-			Message::LightStatePower { level } => {
-				v.write_val(level)?;
-			}
-		}
-
 		let mut msg = RawMessage {
 			frame,
 			frame_addr: addr,
 			protocol_header: phead,
-			payload: v,
+			payload: typ.into_payload()?,
 		};
 
-		msg.frame.size = msg.packed_size() as u16;
+		msg.update_size()?;
 
 		Ok(msg)
 	}
 
+	/// Sets [Frame::size] to match the current payload.
+	///
+	/// Call this after modifying the payload of a hand-constructed message, since [RawMessage::pack]
+	/// refuses to pack a message whose size is wrong.
+	pub fn update_size(&mut self) -> Result<(), Error> {
+		let size = self.packed_size();
+		if size > u16::MAX as usize {
+			return Err(Error::SizeMismatch {
+				claimed: u16::MAX as usize,
+				actual: size,
+			});
+		}
+		self.frame.size = size as u16;
+		Ok(())
+	}
+
 	/// The total size (in bytes) of the packed version of this message.
 	pub fn packed_size(&self) -> usize {
-		Frame::packed_size()
-			+ FrameAddress::packed_size()
-			+ ProtocolHeader::packed_size()
-			+ self.payload.len()
+		HEADER_SIZE + self.payload.len()
+	}
+
+	/// The header fields that identify which request this message is a response to.
+	pub fn response_meta(&self) -> ResponseMeta {
+		ResponseMeta {
+			source: self.frame.source,
+			sequence: self.frame_addr.sequence,
+			target: self.frame_addr.target,
+		}
 	}
 
 	/// Validates that this object was constructed correctly.  Panics if not.
+	///
+	/// See [RawMessage::try_validate] for a version that returns an error instead.
 	pub fn validate(&self) {
 		self.frame.validate();
 		self.frame_addr.validate();
 		self.protocol_header.validate();
 	}
 
+	/// Validates that this object was constructed correctly, returning an error if not.
+	pub fn try_validate(&self) -> Result<(), Error> {
+		self.frame.try_validate()?;
+		self.frame_addr.try_validate()?;
+		self.protocol_header.try_validate()?;
+		Ok(())
+	}
+
 	/// Packs this RawMessage into some bytes that can be send over the network.
 	///
-	/// The length of the returned data will be [RawMessage::packed_size] in size.
+	/// The length of the returned data will be [RawMessage::packed_size] in size.  Returns an error
+	/// if [Frame::size] doesn't match (see [RawMessage::update_size]).
 	pub fn pack(&self) -> Result<Vec<u8>, Error> {
-		let mut v = Vec::with_capacity(self.packed_size());
-		v.extend(self.frame.pack()?);
-		v.extend(self.frame_addr.pack()?);
-		v.extend(self.protocol_header.pack()?);
-		v.extend(&self.payload);
+		let mut v = Vec::with_capacity(self.checked_size()?);
+		self.write_to(&mut v)?;
 		Ok(v)
 	}
+
+	/// Packs this RawMessage into the start of `buf`, returning the number of bytes written.
+	///
+	/// Unlike [RawMessage::pack] this doesn't allocate, so a single buffer can be reused for many
+	/// messages.  Fails with [Error::BufferTooSmall] if `buf` is shorter than
+	/// [RawMessage::packed_size].
+	pub fn pack_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+		let size = self.checked_size()?;
+		self.write_to(buffer_prefix(buf, size)?)?;
+		Ok(size)
+	}
+
+	/// [RawMessage::packed_size], or an error if [Frame::size] doesn't match it.
+	pub(crate) fn checked_size(&self) -> Result<usize, Error> {
+		let size = self.packed_size();
+		if self.frame.size as usize != size {
+			return Err(Error::SizeMismatch {
+				claimed: self.frame.size as usize,
+				actual: size,
+			});
+		}
+		Ok(size)
+	}
+
+	/// Writes the headers and payload in order, without checking the size.
+	pub(crate) fn write_to<W: WriteBytesExt>(&self, mut v: W) -> Result<(), Error> {
+		self.frame.write_to(&mut v)?;
+		self.frame_addr.write_to(&mut v)?;
+		self.protocol_header.write_to(&mut v)?;
+		v.write_all(&self.payload)?;
+		Ok(())
+	}
 	/// Given some bytes (generally read from a network socket), unpack the data into a
 	/// `RawMessage` structure.
+	///
+	/// Returns an error (rather than panicking) if `v` is too short to hold the headers, or if its
+	/// length doesn't match the size claimed by the frame.  To unpack a message followed by other
+	/// data, use [RawMessage::unpack_partial].
+	///
+	/// This decodes leniently; see [RawMessage::unpack_with].
 	pub fn unpack(v: &[u8]) -> Result<RawMessage, Error> {
-		let mut start = 0;
-		let frame = Frame::unpack(v)?;
-		frame.validate();
-		start += Frame::packed_size();
-		let addr = FrameAddress::unpack(&v[start..])?;
-		addr.validate();
-		start += FrameAddress::packed_size();
-		let proto = ProtocolHeader::unpack(&v[start..])?;
-		proto.validate();
-		start += ProtocolHeader::packed_size();
+		RawMessage::unpack_with(v, &DecodeOptions::default())
+	}
 
-		let body = Vec::from(&v[start..(frame.size as usize)]);
+	/// Like [RawMessage::unpack], but with the given [DecodeOptions].
+	///
+	/// In strict mode, messages with non-zero reserved header fields are rejected.
+	pub fn unpack_with(v: &[u8], options: &DecodeOptions) -> Result<RawMessage, Error> {
+		RawMessageRef::unpack_with(v, options).map(RawMessage::from)
+	}
 
-		Ok(RawMessage {
-			frame,
-			frame_addr: addr,
-			protocol_header: proto,
-			payload: body,
-		})
+	/// Unpacks a single message from the start of `v`, returning it along with the number of bytes
+	/// it occupied (as given by [Frame::size]).
+	///
+	/// Any bytes after the message (e.g. stale data in a reused buffer) are left alone, and never
+	/// end up in the payload.
+	pub fn unpack_partial(v: &[u8]) -> Result<(RawMessage, usize), Error> {
+		RawMessage::unpack_partial_with(v, &DecodeOptions::default())
+	}
+
+	/// Like [RawMessage::unpack_partial], but with the given [DecodeOptions].
+	pub fn unpack_partial_with(
+		v: &[u8],
+		options: &DecodeOptions,
+	) -> Result<(RawMessage, usize), Error> {
+		let (msg, size) = RawMessageRef::unpack_partial_with(v, options)?;
+		Ok((msg.into(), size))
 	}
 }
 
@@ -431,46 +337,166 @@ impl RawMessage {
 ///
 /// To parse the payload, use [Message::from_raw].
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RawMessage {
 	pub frame: Frame,
 	pub frame_addr: FrameAddress,
 	pub protocol_header: ProtocolHeader,
-	pub payload: Vec<u8>,
+	pub payload: Payload,
 }
 
-/// Decoded LIFX Messages
-///
-/// This enum lists all of the LIFX message types known to this library.
+/// A [RawMessage] that borrows its payload from the buffer it was unpacked from.
 ///
-/// Note that other message types exist, but are not officially documented (and so are not
-/// available here).
-#[derive(Clone, Debug)]
-pub enum Message {
-	/// GetService - 2
-	///
-	/// Sent by a client to acquire responses from all devices on the local network. No payload is
-	/// required. Causes the devices to transmit a StateService message.
-	GetService,
+/// Unpacking one of these doesn't allocate, which helps when processing a lot of packets (most of
+/// which are thrown away).  Use [Message::from_raw_ref] to decode the payload, or convert it into a
+/// [RawMessage] to keep it around.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawMessageRef<'a> {
+	pub frame: Frame,
+	pub frame_addr: FrameAddress,
+	pub protocol_header: ProtocolHeader,
+	pub payload: &'a [u8],
+}
 
-	/// StateService - 3
-	///
-	/// Response to [Message::GetService] message.
-	StateService {
-		/// Port number of the light.  If the service is temporarily unavailable, then the port value
-		/// will be 0.
-		port: u32,
-		/// unsigned 8-bit integer, maps to `Service`
-		service: Service,
-	},
+impl<'a> RawMessageRef<'a> {
+	/// Like [RawMessage::unpack], but borrows the payload from `v`.
+	pub fn unpack(v: &'a [u8]) -> Result<RawMessageRef<'a>, Error> {
+		RawMessageRef::unpack_with(v, &DecodeOptions::default())
+	}
 
-	/// GetHostInfo - 12
-	///
-	/// Get Host MCU information. No payload is required. Causes the device to transmit a
-	/// [Message::StateHostInfo] message.
-	GetHostInfo,
+	/// Like [RawMessageRef::unpack], but with the given [DecodeOptions].
+	pub fn unpack_with(v: &'a [u8], options: &DecodeOptions) -> Result<RawMessageRef<'a>, Error> {
+		let (msg, size) = RawMessageRef::unpack_partial_with(v, options)?;
+		if size != v.len() {
+			return Err(Error::SizeMismatch {
+				claimed: size,
+				actual: v.len(),
+			});
+		}
+		Ok(msg)
+	}
 
-	/// StateHostInfo - 13
-	///
+	/// Like [RawMessage::unpack_partial], but borrows the payload from `v`.
+	pub fn unpack_partial(v: &'a [u8]) -> Result<(RawMessageRef<'a>, usize), Error> {
+		RawMessageRef::unpack_partial_with(v, &DecodeOptions::default())
+	}
+
+	/// Like [RawMessageRef::unpack_partial], but with the given [DecodeOptions].
+	pub fn unpack_partial_with(
+		v: &'a [u8],
+		options: &DecodeOptions,
+	) -> Result<(RawMessageRef<'a>, usize), Error> {
+		if v.len() < HEADER_SIZE {
+			return Err(Error::MessageTooShort {
+				expected: HEADER_SIZE,
+				actual: v.len(),
+			});
+		}
+
+		let mut start = 0;
+		let frame = Frame::unpack(v)?;
+		frame.try_validate()?;
+		let size = frame.size as usize;
+		if size < HEADER_SIZE {
+			return Err(Error::InvalidHeaderField {
+				field: "size",
+				value: size as u32,
+			});
+		}
+		if size > v.len() {
+			return Err(Error::SizeMismatch {
+				claimed: size,
+				actual: v.len(),
+			});
+		}
+		start += Frame::PACKED_SIZE;
+		let addr = FrameAddress::unpack(&v[start..])?;
+		addr.try_validate()?;
+		start += FrameAddress::PACKED_SIZE;
+		let proto = ProtocolHeader::unpack(&v[start..])?;
+		proto.try_validate()?;
+		start += ProtocolHeader::PACKED_SIZE;
+
+		if options.strict {
+			if frame.origin != 0 {
+				return Err(Error::ReservedFieldNonZero {
+					typ: None,
+					field: "origin",
+				});
+			}
+			addr.check_reserved()?;
+			proto.check_reserved()?;
+		}
+
+		let msg = RawMessageRef {
+			frame,
+			frame_addr: addr,
+			protocol_header: proto,
+			payload: &v[start..size],
+		};
+		Ok((msg, size))
+	}
+}
+
+impl<'a> From<&'a RawMessage> for RawMessageRef<'a> {
+	fn from(msg: &'a RawMessage) -> RawMessageRef<'a> {
+		RawMessageRef {
+			frame: msg.frame,
+			frame_addr: msg.frame_addr,
+			protocol_header: msg.protocol_header,
+			payload: &msg.payload,
+		}
+	}
+}
+
+impl From<RawMessageRef<'_>> for RawMessage {
+	fn from(msg: RawMessageRef) -> RawMessage {
+		RawMessage {
+			frame: msg.frame,
+			frame_addr: msg.frame_addr,
+			protocol_header: msg.protocol_header,
+			payload: Payload::from_slice(msg.payload),
+		}
+	}
+}
+
+/// Decoded LIFX Messages
+///
+/// This enum lists all of the LIFX message types known to this library.
+///
+/// Note that other message types exist, but are not officially documented (and so are not
+/// available here).  New message types are added as they are documented, so this enum is
+/// `#[non_exhaustive]`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Message {
+	/// GetService - 2
+	///
+	/// Sent by a client to acquire responses from all devices on the local network. No payload is
+	/// required. Causes the devices to transmit a StateService message.
+	GetService,
+
+	/// StateService - 3
+	///
+	/// Response to [Message::GetService] message.
+	StateService {
+		/// Port number of the light.  If the service is temporarily unavailable, then the port value
+		/// will be 0.
+		port: u32,
+		/// unsigned 8-bit integer, maps to `Service`
+		service: Service,
+	},
+
+	/// GetHostInfo - 12
+	///
+	/// Get Host MCU information. No payload is required. Causes the device to transmit a
+	/// [Message::StateHostInfo] message.
+	GetHostInfo,
+
+	/// StateHostInfo - 13
+	///
 	/// Response to [Message::GetHostInfo] message.
 	///
 	/// Provides host MCU information.
@@ -624,13 +650,18 @@ pub enum Message {
 		downtime: u64,
 	},
 
+	/// SetReboot - 38
+	///
+	/// Reboots the device. No payload is required.
+	SetReboot,
+
 	/// Acknowledgement - 45
 	///
 	/// Response to any message sent with ack_required set to 1. See message header frame address.
 	///
-	/// (Note that technically this message has no payload, but the frame sequence number is stored
-	/// here for convenience).
-	Acknowledgement { seq: u8 },
+	/// Use [RawMessage::response_meta] (or [Message::from_raw_with_meta]) to find out which message
+	/// is being acknowledged.
+	Acknowledgement,
 
 	/// GetLocation - 48
 	///
@@ -810,6 +841,80 @@ pub enum Message {
 	/// Set the current maximum brightness for the infrared channel.
 	LightSetInfrared { brightness: u16 },
 
+	/// GetHevCycle - 142
+	///
+	/// Gets the state of the HEV (germicidal) cycle on a LIFX Clean device. No payload is
+	/// required. Causes the device to transmit a [Message::StateHevCycle] message.
+	GetHevCycle,
+
+	/// SetHevCycle - 143
+	///
+	/// Starts or stops a HEV cycle.
+	SetHevCycle {
+		/// Set to `false` to stop a running cycle
+		enable: bool,
+		/// Duration of the cycle, in seconds.  Zero uses the default duration (see
+		/// [Message::SetHevCycleConfiguration]).
+		duration: u32,
+	},
+
+	/// StateHevCycle - 144
+	///
+	/// Response to [Message::GetHevCycle] (or [Message::SetHevCycle]).
+	StateHevCycle {
+		/// Duration of the current cycle, in seconds.  Zero if no cycle is running.
+		duration: u32,
+		/// Time left in the current cycle, in seconds
+		remaining: u32,
+		/// Whether the device was powered on before the cycle started
+		last_power: bool,
+	},
+
+	/// GetHevCycleConfiguration - 145
+	///
+	/// Gets the default HEV cycle settings. No payload is required. Causes the device to transmit
+	/// a [Message::StateHevCycleConfiguration] message.
+	GetHevCycleConfiguration,
+
+	/// SetHevCycleConfiguration - 146
+	///
+	/// Sets the default HEV cycle settings.
+	SetHevCycleConfiguration {
+		/// Whether to briefly flash the light when a cycle finishes
+		indication: bool,
+		/// Default cycle duration, in seconds
+		duration: u32,
+	},
+
+	/// StateHevCycleConfiguration - 147
+	///
+	/// Response to [Message::GetHevCycleConfiguration] (or [Message::SetHevCycleConfiguration]).
+	StateHevCycleConfiguration {
+		/// Whether to briefly flash the light when a cycle finishes
+		indication: bool,
+		/// Default cycle duration, in seconds
+		duration: u32,
+	},
+
+	/// GetLastHevCycleResult - 148
+	///
+	/// Gets the outcome of the most recent HEV cycle. No payload is required. Causes the device to
+	/// transmit a [Message::StateLastHevCycleResult] message.
+	GetLastHevCycleResult,
+
+	/// StateLastHevCycleResult - 149
+	///
+	/// Response to [Message::GetLastHevCycleResult].
+	StateLastHevCycleResult { result: LastHevCycleResult },
+
+	/// StateUnhandled - 223
+	///
+	/// Sent by newer firmware in response to a message type that the device does not support.
+	StateUnhandled {
+		/// The message type that was not handled
+		unhandled_type: u16,
+	},
+
 	/// SetColorZones - 501
 	///
 	/// This message is used for changing the color of either a single or multiple zones.
@@ -858,181 +963,1318 @@ pub enum Message {
 		color6: HSBK,
 		color7: HSBK,
 	},
+
+	/// GetMultiZoneEffect - 507
+	///
+	/// Gets the firmware effect currently running on a multizone device. No payload is required.
+	/// Causes the device to transmit a [Message::StateMultiZoneEffect] message.
+	GetMultiZoneEffect,
+
+	/// SetMultiZoneEffect - 508
+	///
+	/// Starts (or stops) a firmware effect on a multizone device.
+	SetMultiZoneEffect {
+		/// A unique value identifying this effect
+		instance_id: u32,
+		typ: MultiZoneEffectType,
+		reserved: u16,
+		/// Duration of a single cycle of the effect, in milliseconds
+		speed: u32,
+		/// How long the effect runs for, in nanoseconds.  Zero runs the effect until it is stopped.
+		duration: u64,
+		reserved2: u32,
+		reserved3: u32,
+		/// Effect-specific parameters (see [MultiZoneEffectType])
+		parameters: [u32; 8],
+	},
+
+	/// StateMultiZoneEffect - 509
+	///
+	/// Response to [Message::GetMultiZoneEffect] (or [Message::SetMultiZoneEffect]).
+	StateMultiZoneEffect {
+		/// The unique value identifying the running effect
+		instance_id: u32,
+		typ: MultiZoneEffectType,
+		reserved: u16,
+		/// Duration of a single cycle of the effect, in milliseconds
+		speed: u32,
+		/// How long the effect runs for, in nanoseconds
+		duration: u64,
+		reserved2: u32,
+		reserved3: u32,
+		/// Effect-specific parameters (see [MultiZoneEffectType])
+		parameters: [u32; 8],
+	},
+
+	/// SetExtendedColorZones - 510
+	///
+	/// Sets the color of up to 82 consecutive zones in a single message, starting at `zone_index`.
+	/// Only the first `colors_count` entries of `colors` are used.
+	///
+	/// Only supported by devices with the extended multizone capability (and recent enough
+	/// firmware); other devices only understand [Message::SetColorZones].
+	SetExtendedColorZones {
+		/// Color transition time in milliseconds
+		duration: u32,
+		apply: ApplicationRequest,
+		/// The zone that `colors[0]` applies to
+		zone_index: u16,
+		/// The number of entries in `colors` that are used
+		colors_count: u8,
+		#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+		colors: [HSBK; 82],
+	},
+
+	/// GetExtendedColorZones - 511
+	///
+	/// Gets the color of every zone on the device. No payload is required. Causes the device to
+	/// transmit one or more [Message::StateExtendedColorZones] messages.
+	GetExtendedColorZones,
+
+	/// StateExtendedColorZones - 512
+	///
+	/// Response to [Message::GetExtendedColorZones] (or [Message::SetExtendedColorZones] when a
+	/// response is required).  Devices with more than 82 zones send several of these messages.
+	StateExtendedColorZones {
+		/// The total number of zones on the device
+		zones_count: u16,
+		/// The zone that `colors[0]` represents
+		zone_index: u16,
+		/// The number of entries in `colors` that are valid
+		colors_count: u8,
+		#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+		colors: [HSBK; 82],
+	},
+
+	/// GetDeviceChain - 701
+	///
+	/// Gets information about the tiles in a device chain. No payload is required. Causes the
+	/// device to transmit a [Message::StateDeviceChain] message.
+	GetDeviceChain,
+
+	/// StateDeviceChain - 702
+	///
+	/// Response to [Message::GetDeviceChain].
+	///
+	/// Provides information about each tile in the chain, starting at `start_index`.
+	StateDeviceChain {
+		/// The index of the first tile in `tile_devices`
+		start_index: u8,
+		tile_devices: [Tile; 16],
+		/// The number of entries in `tile_devices` that are valid
+		tile_devices_count: u8,
+	},
+
+	/// SetUserPosition - 703
+	///
+	/// Sets the position of a tile, as shown in the LIFX app.  This has no effect on how the
+	/// tile behaves.
+	SetUserPosition {
+		tile_index: u8,
+		reserved: u16,
+		user_x: f32,
+		user_y: f32,
+	},
+
+	/// Get64 - 707
+	///
+	/// Gets the color of up to 64 pixels on each of `length` tiles, starting at `tile_index`.
+	/// Causes the device to transmit a [Message::State64] message for each tile.
+	///
+	/// `x`, `y` and `width` select the rectangle of pixels to return; use `0`, `0` and `8` for a
+	/// whole 8x8 tile.
+	Get64 {
+		tile_index: u8,
+		length: u8,
+		reserved: u8,
+		x: u8,
+		y: u8,
+		width: u8,
+	},
+
+	/// State64 - 711
+	///
+	/// Response to [Message::Get64].
+	///
+	/// Provides the color of up to 64 pixels of a single tile, row by row.
+	State64 {
+		tile_index: u8,
+		reserved: u8,
+		x: u8,
+		y: u8,
+		width: u8,
+		#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+		colors: [HSBK; 64],
+	},
+
+	/// Set64 - 715
+	///
+	/// Sets the color of up to 64 pixels on each of `length` tiles, starting at `tile_index`.
+	Set64 {
+		tile_index: u8,
+		length: u8,
+		reserved: u8,
+		x: u8,
+		y: u8,
+		width: u8,
+		/// Color transition time in milliseconds
+		duration: u32,
+		#[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+		colors: [HSBK; 64],
+	},
+
+	/// GetTileEffect - 718
+	///
+	/// Gets the firmware effect currently running on a tile-class device. Causes the device to
+	/// transmit a [Message::StateTileEffect] message.
+	GetTileEffect { reserved: u8, reserved2: u8 },
+
+	/// SetTileEffect - 719
+	///
+	/// Starts (or stops) a firmware effect on a tile-class device.
+	SetTileEffect {
+		reserved: u8,
+		reserved2: u8,
+		/// A unique value identifying this effect
+		instance_id: u32,
+		typ: TileEffectType,
+		/// Duration of a single cycle of the effect, in milliseconds
+		speed: u32,
+		/// How long the effect runs for, in nanoseconds.  Zero runs the effect until it is stopped.
+		duration: u64,
+		reserved3: u32,
+		reserved4: u32,
+		/// Effect-specific parameters
+		parameters: [u32; 8],
+		/// The number of entries in `palette` that are used
+		palette_count: u8,
+		/// The colors used by the effect
+		palette: [HSBK; 16],
+	},
+
+	/// StateTileEffect - 720
+	///
+	/// Response to [Message::GetTileEffect] (or [Message::SetTileEffect]).
+	StateTileEffect {
+		reserved: u8,
+		/// The unique value identifying the running effect
+		instance_id: u32,
+		typ: TileEffectType,
+		/// Duration of a single cycle of the effect, in milliseconds
+		speed: u32,
+		/// How long the effect runs for, in nanoseconds
+		duration: u64,
+		reserved2: u32,
+		reserved3: u32,
+		/// Effect-specific parameters
+		parameters: [u32; 8],
+		/// The number of entries in `palette` that are valid
+		palette_count: u8,
+		palette: [HSBK; 16],
+	},
+
+	/// GetButton - 905
+	///
+	/// Gets the button configuration of a LIFX Switch. No payload is required. Causes the device
+	/// to transmit one or more [Message::StateButton] messages.
+	GetButton,
+
+	/// SetButton - 906
+	///
+	/// Reconfigures the actions bound to the buttons of a LIFX Switch.
+	SetButton {
+		/// The index of the first button in `buttons`
+		index: u8,
+		/// The number of entries in `buttons` that are used
+		buttons_count: u8,
+		buttons: [Button; 8],
+	},
+
+	/// StateButton - 907
+	///
+	/// Response to [Message::GetButton] (or [Message::SetButton]).
+	StateButton {
+		/// The total number of buttons on the device
+		count: u8,
+		/// The index of the first button in `buttons`
+		index: u8,
+		/// The number of entries in `buttons` that are valid
+		buttons_count: u8,
+		buttons: [Button; 8],
+	},
+
+	/// A message of a known type whose payload holds a value this library doesn't know (like an
+	/// undocumented [Service]), kept as it was received.
+	///
+	/// Only produced when decoding leniently (see [DecodeOptions::strict]), and packed back into
+	/// the same bytes.
+	Undecoded {
+		typ: MessageType,
+		payload: Vec<u8>,
+	},
 }
 
 impl Message {
+	/// Builds a [Message::LightSetColor] that transitions to `color` over `duration`.
+	///
+	/// `duration` is clamped to what the protocol can express (see [time::to_millis]).
+	pub fn light_set_color(color: HSBK, duration: Duration) -> Message {
+		Message::LightSetColor {
+			reserved: 0,
+			color,
+			duration: time::to_millis(duration),
+		}
+	}
+
+	/// Builds a [Message::LightSetPower] that transitions to `level` over `duration`.
+	///
+	/// `duration` is clamped to what the protocol can express (see [time::to_millis]).
+	pub fn light_set_power(level: PowerLevel, duration: Duration) -> Message {
+		Message::LightSetPower {
+			level: level as u16,
+			duration: time::to_millis(duration),
+		}
+	}
+
+	/// Builds a [Message::SetWaveform] whose cycles each last `period`.
+	///
+	/// `period` is clamped to what the protocol can express (see [time::to_millis]).
+	pub fn set_waveform(
+		transient: bool,
+		color: HSBK,
+		period: Duration,
+		cycles: f32,
+		skew_ratio: i16,
+		waveform: Waveform,
+	) -> Message {
+		Message::SetWaveform {
+			reserved: 0,
+			transient,
+			color,
+			period: time::to_millis(period),
+			cycles,
+			skew_ratio,
+			waveform,
+		}
+	}
+
+	/// The numeric message type of this message; see also [Message::message_type].
 	pub fn get_num(&self) -> u16 {
+		self.message_type() as u16
+	}
+
+	/// The [MessageType] of this message.
+	pub fn message_type(&self) -> MessageType {
 		match *self {
-			Message::GetService => 2,
-			Message::StateService { .. } => 3,
-			Message::GetHostInfo => 12,
-			Message::StateHostInfo { .. } => 13,
-			Message::GetHostFirmware => 14,
-			Message::StateHostFirmware { .. } => 15,
-			Message::GetWifiInfo => 16,
-			Message::StateWifiInfo { .. } => 17,
-			Message::GetWifiFirmware => 18,
-			Message::StateWifiFirmware { .. } => 19,
-			Message::GetPower => 20,
-			Message::SetPower { .. } => 21,
-			Message::StatePower { .. } => 22,
-			Message::GetLabel => 23,
-			Message::SetLabel { .. } => 24,
-			Message::StateLabel { .. } => 25,
-			Message::GetVersion => 32,
-			Message::StateVersion { .. } => 33,
-			Message::GetInfo => 34,
-			Message::StateInfo { .. } => 35,
-			Message::Acknowledgement { .. } => 45,
-			Message::GetLocation => 48,
-			Message::SetLocation { .. } => 49,
-			Message::StateLocation { .. } => 50,
-			Message::GetGroup => 51,
-			Message::SetGroup { .. } => 52,
-			Message::StateGroup { .. } => 53,
-			Message::EchoRequest { .. } => 58,
-			Message::EchoResponse { .. } => 59,
-			Message::LightGet => 101,
-			Message::LightSetColor { .. } => 102,
-			Message::SetWaveform { .. } => 103,
-			Message::LightState { .. } => 107,
-			Message::LightGetPower => 116,
-			Message::LightSetPower { .. } => 117,
-			Message::LightStatePower { .. } => 118,
-			Message::SetWaveformOptional { .. } => 119,
-			Message::LightGetInfrared => 120,
-			Message::LightStateInfrared { .. } => 121,
-			Message::LightSetInfrared { .. } => 122,
-			Message::SetColorZones { .. } => 501,
-			Message::GetColorZones { .. } => 502,
-			Message::StateZone { .. } => 503,
-			Message::StateMultiZone { .. } => 506,
+			Message::Undecoded { typ, .. } => typ,
+			Message::GetService => MessageType::GetService,
+			Message::StateService { .. } => MessageType::StateService,
+			Message::GetHostInfo => MessageType::GetHostInfo,
+			Message::StateHostInfo { .. } => MessageType::StateHostInfo,
+			Message::GetHostFirmware => MessageType::GetHostFirmware,
+			Message::StateHostFirmware { .. } => MessageType::StateHostFirmware,
+			Message::GetWifiInfo => MessageType::GetWifiInfo,
+			Message::StateWifiInfo { .. } => MessageType::StateWifiInfo,
+			Message::GetWifiFirmware => MessageType::GetWifiFirmware,
+			Message::StateWifiFirmware { .. } => MessageType::StateWifiFirmware,
+			Message::GetPower => MessageType::GetPower,
+			Message::SetPower { .. } => MessageType::SetPower,
+			Message::StatePower { .. } => MessageType::StatePower,
+			Message::GetLabel => MessageType::GetLabel,
+			Message::SetLabel { .. } => MessageType::SetLabel,
+			Message::StateLabel { .. } => MessageType::StateLabel,
+			Message::GetVersion => MessageType::GetVersion,
+			Message::StateVersion { .. } => MessageType::StateVersion,
+			Message::GetInfo => MessageType::GetInfo,
+			Message::StateInfo { .. } => MessageType::StateInfo,
+			Message::SetReboot => MessageType::SetReboot,
+			Message::Acknowledgement => MessageType::Acknowledgement,
+			Message::GetLocation => MessageType::GetLocation,
+			Message::SetLocation { .. } => MessageType::SetLocation,
+			Message::StateLocation { .. } => MessageType::StateLocation,
+			Message::GetGroup => MessageType::GetGroup,
+			Message::SetGroup { .. } => MessageType::SetGroup,
+			Message::StateGroup { .. } => MessageType::StateGroup,
+			Message::EchoRequest { .. } => MessageType::EchoRequest,
+			Message::EchoResponse { .. } => MessageType::EchoResponse,
+			Message::LightGet => MessageType::LightGet,
+			Message::LightSetColor { .. } => MessageType::LightSetColor,
+			Message::SetWaveform { .. } => MessageType::SetWaveform,
+			Message::LightState { .. } => MessageType::LightState,
+			Message::LightGetPower => MessageType::LightGetPower,
+			Message::LightSetPower { .. } => MessageType::LightSetPower,
+			Message::LightStatePower { .. } => MessageType::LightStatePower,
+			Message::SetWaveformOptional { .. } => MessageType::SetWaveformOptional,
+			Message::LightGetInfrared => MessageType::LightGetInfrared,
+			Message::LightStateInfrared { .. } => MessageType::LightStateInfrared,
+			Message::LightSetInfrared { .. } => MessageType::LightSetInfrared,
+			Message::GetHevCycle => MessageType::GetHevCycle,
+			Message::SetHevCycle { .. } => MessageType::SetHevCycle,
+			Message::StateHevCycle { .. } => MessageType::StateHevCycle,
+			Message::GetHevCycleConfiguration => MessageType::GetHevCycleConfiguration,
+			Message::SetHevCycleConfiguration { .. } => MessageType::SetHevCycleConfiguration,
+			Message::StateHevCycleConfiguration { .. } => MessageType::StateHevCycleConfiguration,
+			Message::GetLastHevCycleResult => MessageType::GetLastHevCycleResult,
+			Message::StateLastHevCycleResult { .. } => MessageType::StateLastHevCycleResult,
+			Message::StateUnhandled { .. } => MessageType::StateUnhandled,
+			Message::SetColorZones { .. } => MessageType::SetColorZones,
+			Message::GetColorZones { .. } => MessageType::GetColorZones,
+			Message::StateZone { .. } => MessageType::StateZone,
+			Message::StateMultiZone { .. } => MessageType::StateMultiZone,
+			Message::GetMultiZoneEffect => MessageType::GetMultiZoneEffect,
+			Message::SetMultiZoneEffect { .. } => MessageType::SetMultiZoneEffect,
+			Message::StateMultiZoneEffect { .. } => MessageType::StateMultiZoneEffect,
+			Message::SetExtendedColorZones { .. } => MessageType::SetExtendedColorZones,
+			Message::GetExtendedColorZones => MessageType::GetExtendedColorZones,
+			Message::StateExtendedColorZones { .. } => MessageType::StateExtendedColorZones,
+			Message::GetDeviceChain => MessageType::GetDeviceChain,
+			Message::StateDeviceChain { .. } => MessageType::StateDeviceChain,
+			Message::SetUserPosition { .. } => MessageType::SetUserPosition,
+			Message::Get64 { .. } => MessageType::Get64,
+			Message::State64 { .. } => MessageType::State64,
+			Message::Set64 { .. } => MessageType::Set64,
+			Message::GetTileEffect { .. } => MessageType::GetTileEffect,
+			Message::SetTileEffect { .. } => MessageType::SetTileEffect,
+			Message::StateTileEffect { .. } => MessageType::StateTileEffect,
+			Message::GetButton => MessageType::GetButton,
+			Message::SetButton { .. } => MessageType::SetButton,
+			Message::StateButton { .. } => MessageType::StateButton,
 		}
 	}
 
-	/// Tries to parse the payload in a [RawMessage], based on its message type.
-	pub fn from_raw(msg: &RawMessage) -> Result<Message, Error> {
-		match msg.protocol_header.typ {
-			2 => Ok(Message::GetService),
-			3 => Ok(unpack!(msg, StateService, service: u8, port: u32)),
-			12 => Ok(Message::GetHostInfo),
-			13 => Ok(unpack!(
-				msg,
-				StateHostInfo,
-				signal: f32,
-				tx: u32,
-				rx: u32,
-				reserved: i16
-			)),
-			14 => Ok(Message::GetHostFirmware),
-			15 => Ok(unpack!(
-				msg,
-				StateHostFirmware,
-				build: u64,
-				reserved: u64,
-				version: u32
-			)),
-			16 => Ok(Message::GetWifiInfo),
-			17 => Ok(unpack!(
-				msg,
-				StateWifiInfo,
-				signal: f32,
-				tx: u32,
-				rx: u32,
-				reserved: i16
-			)),
-			18 => Ok(Message::GetWifiFirmware),
-			19 => Ok(unpack!(
-				msg,
-				StateWifiFirmware,
-				build: u64,
-				reserved: u64,
-				version: u32
-			)),
-			20 => Ok(Message::GetPower),
-			22 => Ok(unpack!(msg, StatePower, level: u16)),
-			23 => Ok(Message::GetLabel),
-			25 => Ok(unpack!(msg, StateLabel, label: LifxString)),
-			32 => Ok(Message::GetVersion),
-			33 => Ok(unpack!(
-				msg,
-				StateVersion,
-				vendor: u32,
-				product: u32,
-				version: u32
-			)),
-			35 => Ok(unpack!(
-				msg,
-				StateInfo,
-				time: u64,
-				uptime: u64,
-				downtime: u64
-			)),
-			45 => Ok(Message::Acknowledgement {
-				seq: msg.frame_addr.sequence,
-			}),
-			48 => Ok(Message::GetLocation),
-			50 => Ok(unpack!(
-				msg,
-				StateLocation,
-				location: LifxIdent,
-				label: LifxString,
-				updated_at: u64
-			)),
-			51 => Ok(Message::GetGroup),
-			53 => Ok(unpack!(
-				msg,
-				StateGroup,
-				group: LifxIdent,
-				label: LifxString,
-				updated_at: u64
-			)),
-			58 => Ok(unpack!(msg, EchoRequest, payload: EchoPayload)),
-			59 => Ok(unpack!(msg, EchoResponse, payload: EchoPayload)),
-			101 => Ok(Message::LightGet),
-			102 => Ok(unpack!(
-				msg,
-				LightSetColor,
-				reserved: u8,
-				color: HSBK,
-				duration: u32
-			)),
-			107 => Ok(unpack!(
-				msg,
-				LightState,
-				color: HSBK,
-				reserved: i16,
-				power: u16,
-				label: LifxString,
-				reserved2: u64
-			)),
-			116 => Ok(Message::LightGetPower),
-			117 => Ok(unpack!(msg, LightSetPower, level: u16, duration: u32)),
-			118 => {
-				let mut c = Cursor::new(&msg.payload);
-				Ok(Message::LightStatePower {
-					level: c.read_val()?,
-				})
+	/// The message type(s) a device sends in response to this message.
+	///
+	/// Get messages are always answered, but Set messages are only answered when
+	/// [FrameAddress::res_required](crate::FrameAddress::res_required) is set; otherwise the device
+	/// only sends a [Message::Acknowledgement], if one was requested.  Some messages (like
+	/// [Message::GetColorZones]) have more than one possible response, and some (like
+	/// [Message::Set64] or any State message) have none.
+	///
+	/// Any message may also be answered with a [Message::StateUnhandled] if the device doesn't
+	/// support it; that is not included here.
+	pub fn expected_response(&self) -> &'static [MessageType] {
+		match *self {
+			Message::GetService => &[MessageType::StateService],
+			Message::GetHostInfo => &[MessageType::StateHostInfo],
+			Message::GetHostFirmware => &[MessageType::StateHostFirmware],
+			Message::GetWifiInfo => &[MessageType::StateWifiInfo],
+			Message::GetWifiFirmware => &[MessageType::StateWifiFirmware],
+			Message::GetPower | Message::SetPower { .. } => &[MessageType::StatePower],
+			Message::GetLabel | Message::SetLabel { .. } => &[MessageType::StateLabel],
+			Message::GetVersion => &[MessageType::StateVersion],
+			Message::GetInfo => &[MessageType::StateInfo],
+			Message::GetLocation | Message::SetLocation { .. } => &[MessageType::StateLocation],
+			Message::GetGroup | Message::SetGroup { .. } => &[MessageType::StateGroup],
+			Message::EchoRequest { .. } => &[MessageType::EchoResponse],
+			Message::LightGet
+			| Message::LightSetColor { .. }
+			| Message::SetWaveform { .. }
+			| Message::SetWaveformOptional { .. } => &[MessageType::LightState],
+			Message::LightGetPower | Message::LightSetPower { .. } => {
+				&[MessageType::LightStatePower]
 			}
-			121 => Ok(unpack!(msg, LightStateInfrared, brightness: u16)),
-			501 => Ok(unpack!(
-				msg,
-				SetColorZones,
-				start_index: u8,
-				end_index: u8,
-				color: HSBK,
-				duration: u32,
-				apply: u8
-			)),
-			502 => Ok(unpack!(msg, GetColorZones, start_index: u8, end_index: u8)),
-			503 => Ok(unpack!(msg, StateZone, count: u8, index: u8, color: HSBK)),
-			506 => Ok(unpack!(
-				msg,
-				StateMultiZone,
-				count: u8,
-				index: u8,
-				color0: HSBK,
-				color1: HSBK,
+			Message::LightGetInfrared | Message::LightSetInfrared { .. } => {
+				&[MessageType::LightStateInfrared]
+			}
+			Message::GetHevCycle | Message::SetHevCycle { .. } => &[MessageType::StateHevCycle],
+			Message::GetHevCycleConfiguration | Message::SetHevCycleConfiguration { .. } => {
+				&[MessageType::StateHevCycleConfiguration]
+			}
+			Message::GetLastHevCycleResult => &[MessageType::StateLastHevCycleResult],
+			Message::GetColorZones { .. } | Message::SetColorZones { .. } => {
+				&[MessageType::StateZone, MessageType::StateMultiZone]
+			}
+			Message::GetMultiZoneEffect | Message::SetMultiZoneEffect { .. } => {
+				&[MessageType::StateMultiZoneEffect]
+			}
+			Message::GetExtendedColorZones | Message::SetExtendedColorZones { .. } => {
+				&[MessageType::StateExtendedColorZones]
+			}
+			Message::GetDeviceChain => &[MessageType::StateDeviceChain],
+			Message::Get64 { .. } => &[MessageType::State64],
+			Message::GetTileEffect { .. } | Message::SetTileEffect { .. } => {
+				&[MessageType::StateTileEffect]
+			}
+			Message::GetButton | Message::SetButton { .. } => &[MessageType::StateButton],
+			Message::StateService { .. }
+			| Message::StateHostInfo { .. }
+			| Message::StateHostFirmware { .. }
+			| Message::StateWifiInfo { .. }
+			| Message::StateWifiFirmware { .. }
+			| Message::StatePower { .. }
+			| Message::StateLabel { .. }
+			| Message::StateVersion { .. }
+			| Message::StateInfo { .. }
+			| Message::SetReboot
+			| Message::Acknowledgement
+			| Message::StateLocation { .. }
+			| Message::StateGroup { .. }
+			| Message::EchoResponse { .. }
+			| Message::LightState { .. }
+			| Message::LightStatePower { .. }
+			| Message::LightStateInfrared { .. }
+			| Message::StateHevCycle { .. }
+			| Message::StateHevCycleConfiguration { .. }
+			| Message::StateLastHevCycleResult { .. }
+			| Message::StateUnhandled { .. }
+			| Message::StateZone { .. }
+			| Message::StateMultiZone { .. }
+			| Message::StateMultiZoneEffect { .. }
+			| Message::StateExtendedColorZones { .. }
+			| Message::StateDeviceChain { .. }
+			| Message::SetUserPosition { .. }
+			| Message::State64 { .. }
+			| Message::Set64 { .. }
+			| Message::StateTileEffect { .. }
+			| Message::StateButton { .. } => &[],
+			// without knowing what it says, there's no telling
+			Message::Undecoded { .. } => &[],
+		}
+	}
+
+	/// The size (in bytes) of this message's payload, once packed.
+	///
+	/// Used to allocate the payload buffer up front.  Messages without a payload return zero, and
+	/// so never allocate.
+	pub fn payload_size(&self) -> usize {
+		match self {
+			Message::Undecoded { payload, .. } => payload.len(),
+			_ => self.message_type().payload_size(),
+		}
+	}
+
+	/// Serializes just the payload of this message, without any headers.
+	///
+	/// See also [RawMessage::build].
+	pub fn to_payload(&self) -> Result<Vec<u8>, Error> {
+		Ok(self.clone().into_payload()?.into_vec())
+	}
+
+	fn into_payload(self) -> Result<Payload, Error> {
+		let mut v = Payload::with_capacity(self.payload_size());
+		match self {
+			Message::GetService
+			| Message::GetHostInfo
+			| Message::GetHostFirmware
+			| Message::GetWifiFirmware
+			| Message::GetWifiInfo
+			| Message::GetPower
+			| Message::GetLabel
+			| Message::GetVersion
+			| Message::GetInfo
+			| Message::SetReboot
+			| Message::Acknowledgement
+			| Message::GetLocation
+			| Message::GetGroup
+			| Message::LightGet
+			| Message::LightGetPower
+			| Message::LightGetInfrared
+			| Message::GetHevCycle
+			| Message::GetHevCycleConfiguration
+			| Message::GetLastHevCycleResult
+			| Message::GetMultiZoneEffect
+			| Message::GetExtendedColorZones
+			| Message::GetDeviceChain
+			| Message::GetButton => {
+				// these types have no payload
+			}
+			Message::SetColorZones {
+				start_index,
+				end_index,
+				color,
+				duration,
+				apply,
+			} => {
+				start_index.encode(&mut v)?;
+				end_index.encode(&mut v)?;
+				color.encode(&mut v)?;
+				duration.encode(&mut v)?;
+				apply.encode(&mut v)?;
+			}
+			Message::SetWaveform {
+				reserved,
+				transient,
+				color,
+				period,
+				cycles,
+				skew_ratio,
+				waveform,
+			} => {
+				reserved.encode(&mut v)?;
+				transient.encode(&mut v)?;
+				color.encode(&mut v)?;
+				period.encode(&mut v)?;
+				cycles.encode(&mut v)?;
+				skew_ratio.encode(&mut v)?;
+				waveform.encode(&mut v)?;
+			}
+			Message::SetWaveformOptional {
+				reserved,
+				transient,
+				color,
+				period,
+				cycles,
+				skew_ratio,
+				waveform,
+				set_hue,
+				set_saturation,
+				set_brightness,
+				set_kelvin,
+			} => {
+				reserved.encode(&mut v)?;
+				transient.encode(&mut v)?;
+				color.encode(&mut v)?;
+				period.encode(&mut v)?;
+				cycles.encode(&mut v)?;
+				skew_ratio.encode(&mut v)?;
+				waveform.encode(&mut v)?;
+				set_hue.encode(&mut v)?;
+				set_saturation.encode(&mut v)?;
+				set_brightness.encode(&mut v)?;
+				set_kelvin.encode(&mut v)?;
+			}
+			Message::GetColorZones {
+				start_index,
+				end_index,
+			} => {
+				start_index.encode(&mut v)?;
+				end_index.encode(&mut v)?;
+			}
+			Message::StateZone {
+				count,
+				index,
+				color,
+			} => {
+				count.encode(&mut v)?;
+				index.encode(&mut v)?;
+				color.encode(&mut v)?;
+			}
+			Message::StateMultiZone {
+				count,
+				index,
+				color0,
+				color1,
+				color2,
+				color3,
+				color4,
+				color5,
+				color6,
+				color7,
+			} => {
+				count.encode(&mut v)?;
+				index.encode(&mut v)?;
+				color0.encode(&mut v)?;
+				color1.encode(&mut v)?;
+				color2.encode(&mut v)?;
+				color3.encode(&mut v)?;
+				color4.encode(&mut v)?;
+				color5.encode(&mut v)?;
+				color6.encode(&mut v)?;
+				color7.encode(&mut v)?;
+			}
+			Message::SetMultiZoneEffect {
+				instance_id,
+				typ,
+				reserved,
+				speed,
+				duration,
+				reserved2,
+				reserved3,
+				parameters,
+			}
+			| Message::StateMultiZoneEffect {
+				instance_id,
+				typ,
+				reserved,
+				speed,
+				duration,
+				reserved2,
+				reserved3,
+				parameters,
+			} => {
+				instance_id.encode(&mut v)?;
+				typ.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				speed.encode(&mut v)?;
+				duration.encode(&mut v)?;
+				reserved2.encode(&mut v)?;
+				reserved3.encode(&mut v)?;
+				parameters.encode(&mut v)?;
+			}
+			Message::SetExtendedColorZones {
+				duration,
+				apply,
+				zone_index,
+				colors_count,
+				colors,
+			} => {
+				duration.encode(&mut v)?;
+				apply.encode(&mut v)?;
+				zone_index.encode(&mut v)?;
+				colors_count.encode(&mut v)?;
+				colors.encode(&mut v)?;
+			}
+			Message::StateExtendedColorZones {
+				zones_count,
+				zone_index,
+				colors_count,
+				colors,
+			} => {
+				zones_count.encode(&mut v)?;
+				zone_index.encode(&mut v)?;
+				colors_count.encode(&mut v)?;
+				colors.encode(&mut v)?;
+			}
+			Message::StateDeviceChain {
+				start_index,
+				tile_devices,
+				tile_devices_count,
+			} => {
+				start_index.encode(&mut v)?;
+				tile_devices.encode(&mut v)?;
+				tile_devices_count.encode(&mut v)?;
+			}
+			Message::SetUserPosition {
+				tile_index,
+				reserved,
+				user_x,
+				user_y,
+			} => {
+				tile_index.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				user_x.encode(&mut v)?;
+				user_y.encode(&mut v)?;
+			}
+			Message::Get64 {
+				tile_index,
+				length,
+				reserved,
+				x,
+				y,
+				width,
+			} => {
+				tile_index.encode(&mut v)?;
+				length.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				x.encode(&mut v)?;
+				y.encode(&mut v)?;
+				width.encode(&mut v)?;
+			}
+			Message::State64 {
+				tile_index,
+				reserved,
+				x,
+				y,
+				width,
+				colors,
+			} => {
+				tile_index.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				x.encode(&mut v)?;
+				y.encode(&mut v)?;
+				width.encode(&mut v)?;
+				colors.encode(&mut v)?;
+			}
+			Message::Set64 {
+				tile_index,
+				length,
+				reserved,
+				x,
+				y,
+				width,
+				duration,
+				colors,
+			} => {
+				tile_index.encode(&mut v)?;
+				length.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				x.encode(&mut v)?;
+				y.encode(&mut v)?;
+				width.encode(&mut v)?;
+				duration.encode(&mut v)?;
+				colors.encode(&mut v)?;
+			}
+			Message::GetTileEffect {
+				reserved,
+				reserved2,
+			} => {
+				reserved.encode(&mut v)?;
+				reserved2.encode(&mut v)?;
+			}
+			Message::SetTileEffect {
+				reserved,
+				reserved2,
+				instance_id,
+				typ,
+				speed,
+				duration,
+				reserved3,
+				reserved4,
+				parameters,
+				palette_count,
+				palette,
+			} => {
+				reserved.encode(&mut v)?;
+				reserved2.encode(&mut v)?;
+				instance_id.encode(&mut v)?;
+				typ.encode(&mut v)?;
+				speed.encode(&mut v)?;
+				duration.encode(&mut v)?;
+				reserved3.encode(&mut v)?;
+				reserved4.encode(&mut v)?;
+				parameters.encode(&mut v)?;
+				palette_count.encode(&mut v)?;
+				palette.encode(&mut v)?;
+			}
+			Message::StateTileEffect {
+				reserved,
+				instance_id,
+				typ,
+				speed,
+				duration,
+				reserved2,
+				reserved3,
+				parameters,
+				palette_count,
+				palette,
+			} => {
+				reserved.encode(&mut v)?;
+				instance_id.encode(&mut v)?;
+				typ.encode(&mut v)?;
+				speed.encode(&mut v)?;
+				duration.encode(&mut v)?;
+				reserved2.encode(&mut v)?;
+				reserved3.encode(&mut v)?;
+				parameters.encode(&mut v)?;
+				palette_count.encode(&mut v)?;
+				palette.encode(&mut v)?;
+			}
+			Message::SetButton {
+				index,
+				buttons_count,
+				buttons,
+			} => {
+				index.encode(&mut v)?;
+				buttons_count.encode(&mut v)?;
+				buttons.encode(&mut v)?;
+			}
+			Message::StateButton {
+				count,
+				index,
+				buttons_count,
+				buttons,
+			} => {
+				count.encode(&mut v)?;
+				index.encode(&mut v)?;
+				buttons_count.encode(&mut v)?;
+				buttons.encode(&mut v)?;
+			}
+			Message::LightStateInfrared { brightness } => brightness.encode(&mut v)?,
+			Message::LightSetInfrared { brightness } => brightness.encode(&mut v)?,
+			Message::SetHevCycle { enable, duration } => {
+				enable.encode(&mut v)?;
+				duration.encode(&mut v)?;
+			}
+			Message::StateHevCycle {
+				duration,
+				remaining,
+				last_power,
+			} => {
+				duration.encode(&mut v)?;
+				remaining.encode(&mut v)?;
+				last_power.encode(&mut v)?;
+			}
+			Message::SetHevCycleConfiguration {
+				indication,
+				duration,
+			}
+			| Message::StateHevCycleConfiguration {
+				indication,
+				duration,
+			} => {
+				indication.encode(&mut v)?;
+				duration.encode(&mut v)?;
+			}
+			Message::StateLastHevCycleResult { result } => result.encode(&mut v)?,
+			Message::StateUnhandled { unhandled_type } => unhandled_type.encode(&mut v)?,
+			Message::SetLocation {
+				location,
+				label,
+				updated_at,
+			} => {
+				location.encode(&mut v)?;
+				label.encode(&mut v)?;
+				updated_at.encode(&mut v)?;
+			}
+			Message::SetGroup {
+				group,
+				label,
+				updated_at,
+			} => {
+				group.encode(&mut v)?;
+				label.encode(&mut v)?;
+				updated_at.encode(&mut v)?;
+			}
+			Message::StateService { port, service } => {
+				service.encode(&mut v)?;
+				port.encode(&mut v)?;
+			}
+			Message::StateHostInfo {
+				signal,
+				tx,
+				rx,
+				reserved,
+			} => {
+				signal.encode(&mut v)?;
+				tx.encode(&mut v)?;
+				rx.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+			}
+			Message::StateHostFirmware {
+				build,
+				reserved,
+				version,
+			} => {
+				build.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				version.encode(&mut v)?;
+			}
+			Message::StateWifiInfo {
+				signal,
+				tx,
+				rx,
+				reserved,
+			} => {
+				signal.encode(&mut v)?;
+				tx.encode(&mut v)?;
+				rx.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+			}
+			Message::StateWifiFirmware {
+				build,
+				reserved,
+				version,
+			} => {
+				build.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				version.encode(&mut v)?;
+			}
+			Message::SetPower { level } => {
+				level.encode(&mut v)?;
+			}
+			Message::StatePower { level } => {
+				level.encode(&mut v)?;
+			}
+			Message::SetLabel { label } => {
+				label.encode(&mut v)?;
+			}
+			Message::StateLabel { label } => {
+				label.encode(&mut v)?;
+			}
+			Message::StateVersion {
+				vendor,
+				product,
+				version,
+			} => {
+				vendor.encode(&mut v)?;
+				product.encode(&mut v)?;
+				version.encode(&mut v)?;
+			}
+			Message::StateInfo {
+				time,
+				uptime,
+				downtime,
+			} => {
+				time.encode(&mut v)?;
+				uptime.encode(&mut v)?;
+				downtime.encode(&mut v)?;
+			}
+			Message::StateLocation {
+				location,
+				label,
+				updated_at,
+			} => {
+				location.encode(&mut v)?;
+				label.encode(&mut v)?;
+				updated_at.encode(&mut v)?;
+			}
+			Message::StateGroup {
+				group,
+				label,
+				updated_at,
+			} => {
+				group.encode(&mut v)?;
+				label.encode(&mut v)?;
+				updated_at.encode(&mut v)?;
+			}
+			Message::EchoRequest { payload } => {
+				payload.encode(&mut v)?;
+			}
+			Message::EchoResponse { payload } => {
+				payload.encode(&mut v)?;
+			}
+			Message::LightSetColor {
+				reserved,
+				color,
+				duration,
+			} => {
+				reserved.encode(&mut v)?;
+				color.encode(&mut v)?;
+				duration.encode(&mut v)?;
+			}
+			Message::LightState {
+				color,
+				reserved,
+				power,
+				label,
+				reserved2,
+			} => {
+				color.encode(&mut v)?;
+				reserved.encode(&mut v)?;
+				power.encode(&mut v)?;
+				label.encode(&mut v)?;
+				reserved2.encode(&mut v)?;
+			}
+			Message::LightSetPower { level, duration } => {
+				let level: u16 = if level > 0 { 65535 } else { 0 };
+				level.encode(&mut v)?;
+				duration.encode(&mut v)?;
+			}
+			Message::LightStatePower { level } => {
+				level.encode(&mut v)?;
+			}
+			Message::Undecoded { payload, .. } => v.extend_from_slice(&payload),
+		}
+
+		Ok(v)
+	}
+
+	/// The size (in bytes) of the payload of the given message type, or `None` if the message
+	/// type is unknown.
+	///
+	/// Every known message type has a fixed-size payload.
+	pub fn expected_payload_size(typ: u16) -> Option<usize> {
+		MessageType::try_from(typ).ok().map(MessageType::payload_size)
+	}
+
+	/// Tries to parse the payload in a [RawMessage], based on its message type.
+	///
+	/// This decodes leniently; see [Message::from_raw_with].
+	pub fn from_raw(msg: &RawMessage) -> Result<Message, Error> {
+		Message::from_raw_with(msg, &DecodeOptions::default())
+	}
+
+	/// Like [Message::from_raw], but also returns the [ResponseMeta] needed to match the message
+	/// up with the request it answers.
+	pub fn from_raw_with_meta(msg: &RawMessage) -> Result<(Message, ResponseMeta), Error> {
+		Ok((Message::from_raw(msg)?, msg.response_meta()))
+	}
+
+	/// Like [Message::from_raw], but with the given [DecodeOptions].
+	///
+	/// The payload length is checked against [Message::expected_payload_size] before decoding.  A
+	/// short payload is always an error; a long one is only rejected in strict mode (as are
+	/// messages with non-zero reserved fields).
+	pub fn from_raw_with(msg: &RawMessage, options: &DecodeOptions) -> Result<Message, Error> {
+		Message::from_raw_ref_with(&msg.into(), options)
+	}
+
+	/// Like [Message::from_raw], but decodes a [RawMessageRef].
+	pub fn from_raw_ref(msg: &RawMessageRef) -> Result<Message, Error> {
+		Message::from_raw_ref_with(msg, &DecodeOptions::default())
+	}
+
+	/// Like [Message::from_raw_with], but decodes a [RawMessageRef].
+	pub fn from_raw_ref_with(
+		msg: &RawMessageRef,
+		options: &DecodeOptions,
+	) -> Result<Message, Error> {
+		let typ = msg.protocol_header.typ;
+		if let Some(expected) = Message::expected_payload_size(typ) {
+			let actual = msg.payload.len();
+			if actual < expected || (options.strict && actual > expected) {
+				return Err(Error::PayloadSizeMismatch {
+					typ,
+					expected,
+					actual,
+				});
+			}
+		}
+		match Message::decode(msg, options) {
+			Err(Error::InvalidEnumValue { .. }) if !options.strict => Ok(Message::Undecoded {
+				typ: MessageType::try_from(typ)?,
+				payload: msg.payload.to_vec(),
+			}),
+			result => result,
+		}
+	}
+
+	fn decode(msg: &RawMessageRef, options: &DecodeOptions) -> Result<Message, Error> {
+		match MessageType::try_from(msg.protocol_header.typ)? {
+			MessageType::GetService => Ok(Message::GetService),
+			MessageType::StateService => Ok(unpack!(
+				msg,
+				options,
+				StateService,
+				service: u8,
+				port: u32
+			)),
+			MessageType::GetHostInfo => Ok(Message::GetHostInfo),
+			MessageType::StateHostInfo => Ok(unpack!(
+				msg,
+				options,
+				StateHostInfo,
+				signal: f32,
+				tx: u32,
+				rx: u32,
+				reserved: i16
+			)),
+			MessageType::GetHostFirmware => Ok(Message::GetHostFirmware),
+			MessageType::StateHostFirmware => Ok(unpack!(
+				msg,
+				options,
+				StateHostFirmware,
+				build: u64,
+				reserved: u64,
+				version: u32
+			)),
+			MessageType::GetWifiInfo => Ok(Message::GetWifiInfo),
+			MessageType::StateWifiInfo => Ok(unpack!(
+				msg,
+				options,
+				StateWifiInfo,
+				signal: f32,
+				tx: u32,
+				rx: u32,
+				reserved: i16
+			)),
+			MessageType::GetWifiFirmware => Ok(Message::GetWifiFirmware),
+			MessageType::StateWifiFirmware => Ok(unpack!(
+				msg,
+				options,
+				StateWifiFirmware,
+				build: u64,
+				reserved: u64,
+				version: u32
+			)),
+			MessageType::GetPower => Ok(Message::GetPower),
+			MessageType::SetPower => Ok(unpack!(msg, options, SetPower, level: u16)),
+			MessageType::StatePower => Ok(unpack!(msg, options, StatePower, level: u16)),
+			MessageType::GetLabel => Ok(Message::GetLabel),
+			MessageType::SetLabel => Ok(unpack!(msg, options, SetLabel, label: LifxString)),
+			MessageType::StateLabel => Ok(unpack!(msg, options, StateLabel, label: LifxString)),
+			MessageType::GetVersion => Ok(Message::GetVersion),
+			MessageType::StateVersion => Ok(unpack!(
+				msg,
+				options,
+				StateVersion,
+				vendor: u32,
+				product: u32,
+				version: u32
+			)),
+			MessageType::GetInfo => Ok(Message::GetInfo),
+			MessageType::StateInfo => Ok(unpack!(
+				msg,
+				options,
+				StateInfo,
+				time: u64,
+				uptime: u64,
+				downtime: u64
+			)),
+			MessageType::SetReboot => Ok(Message::SetReboot),
+			MessageType::Acknowledgement => Ok(Message::Acknowledgement),
+			MessageType::GetLocation => Ok(Message::GetLocation),
+			MessageType::SetLocation => Ok(unpack!(
+				msg,
+				options,
+				SetLocation,
+				location: LifxIdent,
+				label: LifxString,
+				updated_at: u64
+			)),
+			MessageType::StateLocation => Ok(unpack!(
+				msg,
+				options,
+				StateLocation,
+				location: LifxIdent,
+				label: LifxString,
+				updated_at: u64
+			)),
+			MessageType::GetGroup => Ok(Message::GetGroup),
+			MessageType::SetGroup => Ok(unpack!(
+				msg,
+				options,
+				SetGroup,
+				group: LifxIdent,
+				label: LifxString,
+				updated_at: u64
+			)),
+			MessageType::StateGroup => Ok(unpack!(
+				msg,
+				options,
+				StateGroup,
+				group: LifxIdent,
+				label: LifxString,
+				updated_at: u64
+			)),
+			MessageType::EchoRequest => Ok(unpack!(
+				msg,
+				options,
+				EchoRequest,
+				payload: EchoPayload
+			)),
+			MessageType::EchoResponse => Ok(unpack!(
+				msg,
+				options,
+				EchoResponse,
+				payload: EchoPayload
+			)),
+			MessageType::LightGet => Ok(Message::LightGet),
+			MessageType::LightSetColor => Ok(unpack!(
+				msg,
+				options,
+				LightSetColor,
+				reserved: u8,
+				color: HSBK,
+				duration: u32
+			)),
+			MessageType::SetWaveform => Ok(unpack!(
+				msg,
+				options,
+				SetWaveform,
+				reserved: u8,
+				transient: bool,
+				color: HSBK,
+				period: u32,
+				cycles: f32,
+				skew_ratio: i16,
+				waveform: u8
+			)),
+			MessageType::LightState => Ok(unpack!(
+				msg,
+				options,
+				LightState,
+				color: HSBK,
+				reserved: i16,
+				power: u16,
+				label: LifxString,
+				reserved2: u64
+			)),
+			MessageType::LightGetPower => Ok(Message::LightGetPower),
+			MessageType::LightSetPower => Ok(unpack!(
+				msg,
+				options,
+				LightSetPower,
+				level: u16,
+				duration: u32
+			)),
+			MessageType::LightStatePower => Ok(unpack!(msg, options, LightStatePower, level: u16)),
+			MessageType::SetWaveformOptional => Ok(unpack!(
+				msg,
+				options,
+				SetWaveformOptional,
+				reserved: u8,
+				transient: bool,
+				color: HSBK,
+				period: u32,
+				cycles: f32,
+				skew_ratio: i16,
+				waveform: u8,
+				set_hue: bool,
+				set_saturation: bool,
+				set_brightness: bool,
+				set_kelvin: bool
+			)),
+			MessageType::LightGetInfrared => Ok(Message::LightGetInfrared),
+			MessageType::LightStateInfrared => Ok(unpack!(
+				msg,
+				options,
+				LightStateInfrared,
+				brightness: u16
+			)),
+			MessageType::LightSetInfrared => Ok(unpack!(
+				msg,
+				options,
+				LightSetInfrared,
+				brightness: u16
+			)),
+			MessageType::GetHevCycle => Ok(Message::GetHevCycle),
+			MessageType::SetHevCycle => Ok(unpack!(
+				msg,
+				options,
+				SetHevCycle,
+				enable: bool,
+				duration: u32
+			)),
+			MessageType::StateHevCycle => Ok(unpack!(
+				msg,
+				options,
+				StateHevCycle,
+				duration: u32,
+				remaining: u32,
+				last_power: bool
+			)),
+			MessageType::GetHevCycleConfiguration => Ok(Message::GetHevCycleConfiguration),
+			MessageType::SetHevCycleConfiguration => Ok(unpack!(
+				msg,
+				options,
+				SetHevCycleConfiguration,
+				indication: bool,
+				duration: u32
+			)),
+			MessageType::StateHevCycleConfiguration => Ok(unpack!(
+				msg,
+				options,
+				StateHevCycleConfiguration,
+				indication: bool,
+				duration: u32
+			)),
+			MessageType::GetLastHevCycleResult => Ok(Message::GetLastHevCycleResult),
+			MessageType::StateLastHevCycleResult => Ok(unpack!(
+				msg,
+				options,
+				StateLastHevCycleResult,
+				result: u8
+			)),
+			MessageType::StateUnhandled => Ok(unpack!(
+				msg,
+				options,
+				StateUnhandled,
+				unhandled_type: u16
+			)),
+			MessageType::SetColorZones => Ok(unpack!(
+				msg,
+				options,
+				SetColorZones,
+				start_index: u8,
+				end_index: u8,
+				color: HSBK,
+				duration: u32,
+				apply: u8
+			)),
+			MessageType::GetColorZones => Ok(unpack!(
+				msg,
+				options,
+				GetColorZones,
+				start_index: u8,
+				end_index: u8
+			)),
+			MessageType::StateZone => Ok(unpack!(
+				msg,
+				options,
+				StateZone,
+				count: u8,
+				index: u8,
+				color: HSBK
+			)),
+			MessageType::StateMultiZone => Ok(unpack!(
+				msg,
+				options,
+				StateMultiZone,
+				count: u8,
+				index: u8,
+				color0: HSBK,
+				color1: HSBK,
 				color2: HSBK,
 				color3: HSBK,
 				color4: HSBK,
@@ -1040,7 +2282,162 @@ impl Message {
 				color6: HSBK,
 				color7: HSBK
 			)),
-			_ => Err(Error::UnknownMessageType(msg.protocol_header.typ)),
+			MessageType::GetMultiZoneEffect => Ok(Message::GetMultiZoneEffect),
+			MessageType::SetMultiZoneEffect => Ok(unpack!(
+				msg,
+				options,
+				SetMultiZoneEffect,
+				instance_id: u32,
+				typ: u8,
+				reserved: u16,
+				speed: u32,
+				duration: u64,
+				reserved2: u32,
+				reserved3: u32,
+				parameters: [u32; 8]
+			)),
+			MessageType::StateMultiZoneEffect => Ok(unpack!(
+				msg,
+				options,
+				StateMultiZoneEffect,
+				instance_id: u32,
+				typ: u8,
+				reserved: u16,
+				speed: u32,
+				duration: u64,
+				reserved2: u32,
+				reserved3: u32,
+				parameters: [u32; 8]
+			)),
+			MessageType::SetExtendedColorZones => Ok(unpack!(
+				msg,
+				options,
+				SetExtendedColorZones,
+				duration: u32,
+				apply: u8,
+				zone_index: u16,
+				colors_count: u8,
+				colors: [HSBK; 82]
+			)),
+			MessageType::GetExtendedColorZones => Ok(Message::GetExtendedColorZones),
+			MessageType::StateExtendedColorZones => Ok(unpack!(
+				msg,
+				options,
+				StateExtendedColorZones,
+				zones_count: u16,
+				zone_index: u16,
+				colors_count: u8,
+				colors: [HSBK; 82]
+			)),
+			MessageType::GetDeviceChain => Ok(Message::GetDeviceChain),
+			MessageType::StateDeviceChain => Ok(unpack!(
+				msg,
+				options,
+				StateDeviceChain,
+				start_index: u8,
+				tile_devices: [Tile; 16],
+				tile_devices_count: u8
+			)),
+			MessageType::SetUserPosition => Ok(unpack!(
+				msg,
+				options,
+				SetUserPosition,
+				tile_index: u8,
+				reserved: u16,
+				user_x: f32,
+				user_y: f32
+			)),
+			MessageType::Get64 => Ok(unpack!(
+				msg,
+				options,
+				Get64,
+				tile_index: u8,
+				length: u8,
+				reserved: u8,
+				x: u8,
+				y: u8,
+				width: u8
+			)),
+			MessageType::State64 => Ok(unpack!(
+				msg,
+				options,
+				State64,
+				tile_index: u8,
+				reserved: u8,
+				x: u8,
+				y: u8,
+				width: u8,
+				colors: [HSBK; 64]
+			)),
+			MessageType::Set64 => Ok(unpack!(
+				msg,
+				options,
+				Set64,
+				tile_index: u8,
+				length: u8,
+				reserved: u8,
+				x: u8,
+				y: u8,
+				width: u8,
+				duration: u32,
+				colors: [HSBK; 64]
+			)),
+			MessageType::GetTileEffect => Ok(unpack!(
+				msg,
+				options,
+				GetTileEffect,
+				reserved: u8,
+				reserved2: u8
+			)),
+			MessageType::SetTileEffect => Ok(unpack!(
+				msg,
+				options,
+				SetTileEffect,
+				reserved: u8,
+				reserved2: u8,
+				instance_id: u32,
+				typ: u8,
+				speed: u32,
+				duration: u64,
+				reserved3: u32,
+				reserved4: u32,
+				parameters: [u32; 8],
+				palette_count: u8,
+				palette: [HSBK; 16]
+			)),
+			MessageType::StateTileEffect => Ok(unpack!(
+				msg,
+				options,
+				StateTileEffect,
+				reserved: u8,
+				instance_id: u32,
+				typ: u8,
+				speed: u32,
+				duration: u64,
+				reserved2: u32,
+				reserved3: u32,
+				parameters: [u32; 8],
+				palette_count: u8,
+				palette: [HSBK; 16]
+			)),
+			MessageType::GetButton => Ok(Message::GetButton),
+			MessageType::SetButton => Ok(unpack!(
+				msg,
+				options,
+				SetButton,
+				index: u8,
+				buttons_count: u8,
+				buttons: [Button; 8]
+			)),
+			MessageType::StateButton => Ok(unpack!(
+				msg,
+				options,
+				StateButton,
+				count: u8,
+				index: u8,
+				buttons_count: u8,
+				buttons: [Button; 8]
+			)),
 		}
 	}
 }
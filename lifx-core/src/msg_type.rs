@@ -0,0 +1,341 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::Error;
+
+/// The type of a [Message](crate::Message), as found in
+/// [ProtocolHeader::typ](crate::ProtocolHeader::typ).
+///
+/// Each variant is named after the [Message](crate::Message) variant it identifies, and its
+/// discriminant is the message number.
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum MessageType {
+	GetService = 2,
+	StateService = 3,
+	GetHostInfo = 12,
+	StateHostInfo = 13,
+	GetHostFirmware = 14,
+	StateHostFirmware = 15,
+	GetWifiInfo = 16,
+	StateWifiInfo = 17,
+	GetWifiFirmware = 18,
+	StateWifiFirmware = 19,
+	GetPower = 20,
+	SetPower = 21,
+	StatePower = 22,
+	GetLabel = 23,
+	SetLabel = 24,
+	StateLabel = 25,
+	GetVersion = 32,
+	StateVersion = 33,
+	GetInfo = 34,
+	StateInfo = 35,
+	SetReboot = 38,
+	Acknowledgement = 45,
+	GetLocation = 48,
+	SetLocation = 49,
+	StateLocation = 50,
+	GetGroup = 51,
+	SetGroup = 52,
+	StateGroup = 53,
+	EchoRequest = 58,
+	EchoResponse = 59,
+	LightGet = 101,
+	LightSetColor = 102,
+	SetWaveform = 103,
+	LightState = 107,
+	LightGetPower = 116,
+	LightSetPower = 117,
+	LightStatePower = 118,
+	SetWaveformOptional = 119,
+	LightGetInfrared = 120,
+	LightStateInfrared = 121,
+	LightSetInfrared = 122,
+	GetHevCycle = 142,
+	SetHevCycle = 143,
+	StateHevCycle = 144,
+	GetHevCycleConfiguration = 145,
+	SetHevCycleConfiguration = 146,
+	StateHevCycleConfiguration = 147,
+	GetLastHevCycleResult = 148,
+	StateLastHevCycleResult = 149,
+	StateUnhandled = 223,
+	SetColorZones = 501,
+	GetColorZones = 502,
+	StateZone = 503,
+	StateMultiZone = 506,
+	GetMultiZoneEffect = 507,
+	SetMultiZoneEffect = 508,
+	StateMultiZoneEffect = 509,
+	SetExtendedColorZones = 510,
+	GetExtendedColorZones = 511,
+	StateExtendedColorZones = 512,
+	GetDeviceChain = 701,
+	StateDeviceChain = 702,
+	SetUserPosition = 703,
+	Get64 = 707,
+	State64 = 711,
+	Set64 = 715,
+	GetTileEffect = 718,
+	SetTileEffect = 719,
+	StateTileEffect = 720,
+	GetButton = 905,
+	SetButton = 906,
+	StateButton = 907,
+}
+
+impl MessageType {
+	/// The name of this message type, e.g. `"GetService"`.
+	pub fn name(self) -> &'static str {
+		match self {
+			MessageType::GetService => "GetService",
+			MessageType::StateService => "StateService",
+			MessageType::GetHostInfo => "GetHostInfo",
+			MessageType::StateHostInfo => "StateHostInfo",
+			MessageType::GetHostFirmware => "GetHostFirmware",
+			MessageType::StateHostFirmware => "StateHostFirmware",
+			MessageType::GetWifiInfo => "GetWifiInfo",
+			MessageType::StateWifiInfo => "StateWifiInfo",
+			MessageType::GetWifiFirmware => "GetWifiFirmware",
+			MessageType::StateWifiFirmware => "StateWifiFirmware",
+			MessageType::GetPower => "GetPower",
+			MessageType::SetPower => "SetPower",
+			MessageType::StatePower => "StatePower",
+			MessageType::GetLabel => "GetLabel",
+			MessageType::SetLabel => "SetLabel",
+			MessageType::StateLabel => "StateLabel",
+			MessageType::GetVersion => "GetVersion",
+			MessageType::StateVersion => "StateVersion",
+			MessageType::GetInfo => "GetInfo",
+			MessageType::StateInfo => "StateInfo",
+			MessageType::SetReboot => "SetReboot",
+			MessageType::Acknowledgement => "Acknowledgement",
+			MessageType::GetLocation => "GetLocation",
+			MessageType::SetLocation => "SetLocation",
+			MessageType::StateLocation => "StateLocation",
+			MessageType::GetGroup => "GetGroup",
+			MessageType::SetGroup => "SetGroup",
+			MessageType::StateGroup => "StateGroup",
+			MessageType::EchoRequest => "EchoRequest",
+			MessageType::EchoResponse => "EchoResponse",
+			MessageType::LightGet => "LightGet",
+			MessageType::LightSetColor => "LightSetColor",
+			MessageType::SetWaveform => "SetWaveform",
+			MessageType::LightState => "LightState",
+			MessageType::LightGetPower => "LightGetPower",
+			MessageType::LightSetPower => "LightSetPower",
+			MessageType::LightStatePower => "LightStatePower",
+			MessageType::SetWaveformOptional => "SetWaveformOptional",
+			MessageType::LightGetInfrared => "LightGetInfrared",
+			MessageType::LightStateInfrared => "LightStateInfrared",
+			MessageType::LightSetInfrared => "LightSetInfrared",
+			MessageType::GetHevCycle => "GetHevCycle",
+			MessageType::SetHevCycle => "SetHevCycle",
+			MessageType::StateHevCycle => "StateHevCycle",
+			MessageType::GetHevCycleConfiguration => "GetHevCycleConfiguration",
+			MessageType::SetHevCycleConfiguration => "SetHevCycleConfiguration",
+			MessageType::StateHevCycleConfiguration => "StateHevCycleConfiguration",
+			MessageType::GetLastHevCycleResult => "GetLastHevCycleResult",
+			MessageType::StateLastHevCycleResult => "StateLastHevCycleResult",
+			MessageType::StateUnhandled => "StateUnhandled",
+			MessageType::SetColorZones => "SetColorZones",
+			MessageType::GetColorZones => "GetColorZones",
+			MessageType::StateZone => "StateZone",
+			MessageType::StateMultiZone => "StateMultiZone",
+			MessageType::GetMultiZoneEffect => "GetMultiZoneEffect",
+			MessageType::SetMultiZoneEffect => "SetMultiZoneEffect",
+			MessageType::StateMultiZoneEffect => "StateMultiZoneEffect",
+			MessageType::SetExtendedColorZones => "SetExtendedColorZones",
+			MessageType::GetExtendedColorZones => "GetExtendedColorZones",
+			MessageType::StateExtendedColorZones => "StateExtendedColorZones",
+			MessageType::GetDeviceChain => "GetDeviceChain",
+			MessageType::StateDeviceChain => "StateDeviceChain",
+			MessageType::SetUserPosition => "SetUserPosition",
+			MessageType::Get64 => "Get64",
+			MessageType::State64 => "State64",
+			MessageType::Set64 => "Set64",
+			MessageType::GetTileEffect => "GetTileEffect",
+			MessageType::SetTileEffect => "SetTileEffect",
+			MessageType::StateTileEffect => "StateTileEffect",
+			MessageType::GetButton => "GetButton",
+			MessageType::SetButton => "SetButton",
+			MessageType::StateButton => "StateButton",
+		}
+	}
+
+	/// The size (in bytes) of the payload of this message type.
+	pub fn payload_size(self) -> usize {
+		match self {
+			MessageType::GetService => 0,
+			MessageType::StateService => 5,
+			MessageType::GetHostInfo => 0,
+			MessageType::StateHostInfo => 14,
+			MessageType::GetHostFirmware => 0,
+			MessageType::StateHostFirmware => 20,
+			MessageType::GetWifiInfo => 0,
+			MessageType::StateWifiInfo => 14,
+			MessageType::GetWifiFirmware => 0,
+			MessageType::StateWifiFirmware => 20,
+			MessageType::GetPower => 0,
+			MessageType::SetPower => 2,
+			MessageType::StatePower => 2,
+			MessageType::GetLabel => 0,
+			MessageType::SetLabel => 32,
+			MessageType::StateLabel => 32,
+			MessageType::GetVersion => 0,
+			MessageType::StateVersion => 12,
+			MessageType::GetInfo => 0,
+			MessageType::StateInfo => 24,
+			MessageType::SetReboot => 0,
+			MessageType::Acknowledgement => 0,
+			MessageType::GetLocation => 0,
+			MessageType::SetLocation => 56,
+			MessageType::StateLocation => 56,
+			MessageType::GetGroup => 0,
+			MessageType::SetGroup => 56,
+			MessageType::StateGroup => 56,
+			MessageType::EchoRequest => 64,
+			MessageType::EchoResponse => 64,
+			MessageType::LightGet => 0,
+			MessageType::LightSetColor => 13,
+			MessageType::SetWaveform => 21,
+			MessageType::LightState => 52,
+			MessageType::LightGetPower => 0,
+			MessageType::LightSetPower => 6,
+			MessageType::LightStatePower => 2,
+			MessageType::SetWaveformOptional => 25,
+			MessageType::LightGetInfrared => 0,
+			MessageType::LightStateInfrared => 2,
+			MessageType::LightSetInfrared => 2,
+			MessageType::GetHevCycle => 0,
+			MessageType::SetHevCycle => 5,
+			MessageType::StateHevCycle => 9,
+			MessageType::GetHevCycleConfiguration => 0,
+			MessageType::SetHevCycleConfiguration => 5,
+			MessageType::StateHevCycleConfiguration => 5,
+			MessageType::GetLastHevCycleResult => 0,
+			MessageType::StateLastHevCycleResult => 1,
+			MessageType::StateUnhandled => 2,
+			MessageType::SetColorZones => 15,
+			MessageType::GetColorZones => 2,
+			MessageType::StateZone => 10,
+			MessageType::StateMultiZone => 66,
+			MessageType::GetMultiZoneEffect => 0,
+			MessageType::SetMultiZoneEffect => 59,
+			MessageType::StateMultiZoneEffect => 59,
+			MessageType::SetExtendedColorZones => 664,
+			MessageType::GetExtendedColorZones => 0,
+			MessageType::StateExtendedColorZones => 661,
+			MessageType::GetDeviceChain => 0,
+			MessageType::StateDeviceChain => 882,
+			MessageType::SetUserPosition => 11,
+			MessageType::Get64 => 6,
+			MessageType::State64 => 517,
+			MessageType::Set64 => 522,
+			MessageType::GetTileEffect => 2,
+			MessageType::SetTileEffect => 188,
+			MessageType::StateTileEffect => 187,
+			MessageType::GetButton => 0,
+			MessageType::SetButton => 810,
+			MessageType::StateButton => 811,
+		}
+	}
+}
+
+impl TryFrom<u16> for MessageType {
+	type Error = Error;
+
+	/// Fails with [Error::UnknownMessageType] for message numbers that aren't known.
+	fn try_from(val: u16) -> Result<MessageType, Error> {
+		match val {
+			2 => Ok(MessageType::GetService),
+			3 => Ok(MessageType::StateService),
+			12 => Ok(MessageType::GetHostInfo),
+			13 => Ok(MessageType::StateHostInfo),
+			14 => Ok(MessageType::GetHostFirmware),
+			15 => Ok(MessageType::StateHostFirmware),
+			16 => Ok(MessageType::GetWifiInfo),
+			17 => Ok(MessageType::StateWifiInfo),
+			18 => Ok(MessageType::GetWifiFirmware),
+			19 => Ok(MessageType::StateWifiFirmware),
+			20 => Ok(MessageType::GetPower),
+			21 => Ok(MessageType::SetPower),
+			22 => Ok(MessageType::StatePower),
+			23 => Ok(MessageType::GetLabel),
+			24 => Ok(MessageType::SetLabel),
+			25 => Ok(MessageType::StateLabel),
+			32 => Ok(MessageType::GetVersion),
+			33 => Ok(MessageType::StateVersion),
+			34 => Ok(MessageType::GetInfo),
+			35 => Ok(MessageType::StateInfo),
+			38 => Ok(MessageType::SetReboot),
+			45 => Ok(MessageType::Acknowledgement),
+			48 => Ok(MessageType::GetLocation),
+			49 => Ok(MessageType::SetLocation),
+			50 => Ok(MessageType::StateLocation),
+			51 => Ok(MessageType::GetGroup),
+			52 => Ok(MessageType::SetGroup),
+			53 => Ok(MessageType::StateGroup),
+			58 => Ok(MessageType::EchoRequest),
+			59 => Ok(MessageType::EchoResponse),
+			101 => Ok(MessageType::LightGet),
+			102 => Ok(MessageType::LightSetColor),
+			103 => Ok(MessageType::SetWaveform),
+			107 => Ok(MessageType::LightState),
+			116 => Ok(MessageType::LightGetPower),
+			117 => Ok(MessageType::LightSetPower),
+			118 => Ok(MessageType::LightStatePower),
+			119 => Ok(MessageType::SetWaveformOptional),
+			120 => Ok(MessageType::LightGetInfrared),
+			121 => Ok(MessageType::LightStateInfrared),
+			122 => Ok(MessageType::LightSetInfrared),
+			142 => Ok(MessageType::GetHevCycle),
+			143 => Ok(MessageType::SetHevCycle),
+			144 => Ok(MessageType::StateHevCycle),
+			145 => Ok(MessageType::GetHevCycleConfiguration),
+			146 => Ok(MessageType::SetHevCycleConfiguration),
+			147 => Ok(MessageType::StateHevCycleConfiguration),
+			148 => Ok(MessageType::GetLastHevCycleResult),
+			149 => Ok(MessageType::StateLastHevCycleResult),
+			223 => Ok(MessageType::StateUnhandled),
+			501 => Ok(MessageType::SetColorZones),
+			502 => Ok(MessageType::GetColorZones),
+			503 => Ok(MessageType::StateZone),
+			506 => Ok(MessageType::StateMultiZone),
+			507 => Ok(MessageType::GetMultiZoneEffect),
+			508 => Ok(MessageType::SetMultiZoneEffect),
+			509 => Ok(MessageType::StateMultiZoneEffect),
+			510 => Ok(MessageType::SetExtendedColorZones),
+			511 => Ok(MessageType::GetExtendedColorZones),
+			512 => Ok(MessageType::StateExtendedColorZones),
+			701 => Ok(MessageType::GetDeviceChain),
+			702 => Ok(MessageType::StateDeviceChain),
+			703 => Ok(MessageType::SetUserPosition),
+			707 => Ok(MessageType::Get64),
+			711 => Ok(MessageType::State64),
+			715 => Ok(MessageType::Set64),
+			718 => Ok(MessageType::GetTileEffect),
+			719 => Ok(MessageType::SetTileEffect),
+			720 => Ok(MessageType::StateTileEffect),
+			905 => Ok(MessageType::GetButton),
+			906 => Ok(MessageType::SetButton),
+			907 => Ok(MessageType::StateButton),
+			x => Err(Error::UnknownMessageType(x)),
+		}
+	}
+}
+
+impl From<MessageType> for u16 {
+	fn from(typ: MessageType) -> u16 {
+		typ as u16
+	}
+}
+
+impl fmt::Display for MessageType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
@@ -1,21 +1,71 @@
 // NOTE: THIS FILE IS AUTOMATICALLY GENERATED
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProductInfo {
 	pub name: &'static str,
 	pub color: bool,
 	pub chain: bool,
 	pub matrix: bool,
 	pub infrared: bool,
+	/// Whether the device has a germicidal (HEV) light, controlled with the [SetHevCycle] family
+	/// of messages.
+	///
+	/// [SetHevCycle]: crate::Message::SetHevCycle
+	pub hev: bool,
+	/// Whether the device has relays (e.g. a Switch), which are toggled with the relay power
+	/// messages rather than [SetPower](crate::Message::SetPower).
+	pub relays: bool,
+	/// Whether the device has physical buttons, configured with
+	/// [SetButton](crate::Message::SetButton).
+	pub buttons: bool,
 	pub multizone: bool,
+	/// Whether the device understands [SetExtendedColorZones] and friends, which update up to 82
+	/// zones at once.  Some devices only gained support in a firmware update; see
+	/// [ProductInfo::supports_extended_multizone].
+	///
+	/// [SetExtendedColorZones]: crate::Message::SetExtendedColorZones
+	pub extended_multizone: bool,
+	/// The build time of the first host firmware with extended multizone support, if it wasn't
+	/// supported from the start.
+	pub min_ext_mz_firmware: Option<u32>,
+	/// The `(major, minor)` version of the first host firmware with extended multizone support, if
+	/// it wasn't supported from the start.
+	pub min_ext_mz_firmware_components: Option<(u16, u16)>,
+	/// The lowest and highest color temperatures (in kelvin) the device can display, or `None` if
+	/// it doesn't emit light (e.g. a Switch).  Some white bulbs have only one temperature.
 	pub temperature_range: Option<(u16, u16)>,
 }
 
+impl ProductInfo {
+	/// Clamps `kelvin` to [ProductInfo::temperature_range], if there is one.
+	pub fn clamp_kelvin(&self, kelvin: u16) -> u16 {
+		match self.temperature_range {
+			Some((low, high)) => kelvin.clamp(low, high),
+			None => kelvin,
+		}
+	}
+
+	/// Whether a device running host firmware `version` (as reported by
+	/// [StateHostFirmware](crate::Message::StateHostFirmware)) supports extended multizone
+	/// messages.
+	pub fn supports_extended_multizone(&self, version: u32) -> bool {
+		match self.min_ext_mz_firmware_components {
+			Some((major, minor)) => {
+				self.extended_multizone && version >= (major as u32) << 16 | minor as u32
+			}
+			None => self.extended_multizone,
+		}
+	}
+}
+
 /// Look up info about what a LIFX product supports.
 ///
 /// You can get the vendor and product IDs from a bulb by receiving a [Message::StateVersion] message
 ///
-/// Data is taken from https://github.com/LIFX/products/blob/master/products.json
+/// Data is taken from https://github.com/LIFX/products/blob/master/products.json.  Products that
+/// aren't in that list (including other vendors' devices) can be added with [register_product].
+///
+/// [register_product]: crate::register_product
 pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInfo> {
 	match (vendor, product) {
 		(1, 1) => Some(&ProductInfo {
@@ -24,7 +74,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 3) => Some(&ProductInfo {
@@ -33,7 +89,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 10) => Some(&ProductInfo {
@@ -42,7 +104,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 6500)),
 		}),
 		(1, 11) => Some(&ProductInfo {
@@ -51,7 +119,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 6500)),
 		}),
 		(1, 15) => Some(&ProductInfo {
@@ -60,7 +134,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 18) => Some(&ProductInfo {
@@ -69,7 +149,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 19) => Some(&ProductInfo {
@@ -78,7 +164,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 20) => Some(&ProductInfo {
@@ -87,7 +179,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 22) => Some(&ProductInfo {
@@ -96,7 +194,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 27) => Some(&ProductInfo {
@@ -105,7 +209,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 28) => Some(&ProductInfo {
@@ -114,7 +224,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 29) => Some(&ProductInfo {
@@ -123,7 +239,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 30) => Some(&ProductInfo {
@@ -132,7 +254,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 31) => Some(&ProductInfo {
@@ -141,7 +269,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: true,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 32) => Some(&ProductInfo {
@@ -150,7 +284,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: Some(1532997580),
+			min_ext_mz_firmware_components: Some((2, 77)),
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 36) => Some(&ProductInfo {
@@ -159,7 +299,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 37) => Some(&ProductInfo {
@@ -168,7 +314,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 38) => Some(&ProductInfo {
@@ -177,7 +329,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: Some(1532997580),
+			min_ext_mz_firmware_components: Some((2, 77)),
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 39) => Some(&ProductInfo {
@@ -186,7 +344,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 40) => Some(&ProductInfo {
@@ -195,7 +359,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 43) => Some(&ProductInfo {
@@ -204,7 +374,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 44) => Some(&ProductInfo {
@@ -213,7 +389,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 45) => Some(&ProductInfo {
@@ -222,7 +404,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 46) => Some(&ProductInfo {
@@ -231,7 +419,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 49) => Some(&ProductInfo {
@@ -240,7 +434,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 50) => Some(&ProductInfo {
@@ -249,7 +449,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 6500)),
 		}),
 		(1, 51) => Some(&ProductInfo {
@@ -258,7 +464,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 2700)),
 		}),
 		(1, 52) => Some(&ProductInfo {
@@ -267,7 +479,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 53) => Some(&ProductInfo {
@@ -276,7 +494,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 55) => Some(&ProductInfo {
@@ -285,7 +509,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: true,
 			matrix: true,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2500, 9000)),
 		}),
 		(1, 57) => Some(&ProductInfo {
@@ -294,7 +524,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: true,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 59) => Some(&ProductInfo {
@@ -303,7 +539,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 60) => Some(&ProductInfo {
@@ -312,7 +554,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 6500)),
 		}),
 		(1, 61) => Some(&ProductInfo {
@@ -321,7 +569,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 2700)),
 		}),
 		(1, 62) => Some(&ProductInfo {
@@ -330,7 +584,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 63) => Some(&ProductInfo {
@@ -339,7 +599,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 64) => Some(&ProductInfo {
@@ -348,7 +614,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 65) => Some(&ProductInfo {
@@ -357,7 +629,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 66) => Some(&ProductInfo {
@@ -366,7 +644,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 2700)),
 		}),
 		(1, 68) => Some(&ProductInfo {
@@ -375,7 +659,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: true,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 70) => Some(&ProductInfo {
@@ -384,7 +674,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: true,
+			buttons: true,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: None,
 		}),
 		(1, 71) => Some(&ProductInfo {
@@ -393,7 +689,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: true,
+			buttons: true,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: None,
 		}),
 		(1, 81) => Some(&ProductInfo {
@@ -402,7 +704,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2200, 6500)),
 		}),
 		(1, 82) => Some(&ProductInfo {
@@ -411,7 +719,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2100, 2100)),
 		}),
 		(1, 85) => Some(&ProductInfo {
@@ -420,7 +734,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2000, 2000)),
 		}),
 		(1, 87) => Some(&ProductInfo {
@@ -429,7 +749,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 2700)),
 		}),
 		(1, 88) => Some(&ProductInfo {
@@ -438,7 +764,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2700, 2700)),
 		}),
 		(1, 89) => Some(&ProductInfo {
@@ -447,7 +779,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: true,
+			buttons: true,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: None,
 		}),
 		(1, 90) => Some(&ProductInfo {
@@ -456,7 +794,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: true,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 91) => Some(&ProductInfo {
@@ -465,7 +809,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 92) => Some(&ProductInfo {
@@ -474,7 +824,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 93) => Some(&ProductInfo {
@@ -483,7 +839,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 94) => Some(&ProductInfo {
@@ -492,7 +854,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 96) => Some(&ProductInfo {
@@ -501,7 +869,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2200, 6500)),
 		}),
 		(1, 97) => Some(&ProductInfo {
@@ -510,7 +884,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 98) => Some(&ProductInfo {
@@ -519,7 +899,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 99) => Some(&ProductInfo {
@@ -528,7 +914,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: true,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 100) => Some(&ProductInfo {
@@ -537,7 +929,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2100, 2100)),
 		}),
 		(1, 101) => Some(&ProductInfo {
@@ -546,7 +944,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((2000, 2000)),
 		}),
 		(1, 109) => Some(&ProductInfo {
@@ -555,7 +959,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 110) => Some(&ProductInfo {
@@ -564,7 +974,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 111) => Some(&ProductInfo {
@@ -573,7 +989,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 112) => Some(&ProductInfo {
@@ -582,7 +1004,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: true,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 113) => Some(&ProductInfo {
@@ -591,7 +1019,13 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
 		(1, 114) => Some(&ProductInfo {
@@ -600,9 +1034,1005 @@ pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInf
 			chain: false,
 			matrix: false,
 			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 115) => Some(&ProductInfo {
+			name: "LIFX Switch",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: true,
+			buttons: true,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: None,
+		}),
+		(1, 116) => Some(&ProductInfo {
+			name: "LIFX Switch",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: true,
+			buttons: true,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: None,
+		}),
+		(1, 117) => Some(&ProductInfo {
+			name: "LIFX Z US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 118) => Some(&ProductInfo {
+			name: "LIFX Z Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 119) => Some(&ProductInfo {
+			name: "LIFX Beam US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 120) => Some(&ProductInfo {
+			name: "LIFX Beam Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 121) => Some(&ProductInfo {
+			name: "LIFX Downlight Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 122) => Some(&ProductInfo {
+			name: "LIFX Downlight US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 123) => Some(&ProductInfo {
+			name: "LIFX Color US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 124) => Some(&ProductInfo {
+			name: "LIFX Color Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 125) => Some(&ProductInfo {
+			name: "LIFX White to Warm US",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 126) => Some(&ProductInfo {
+			name: "LIFX White to Warm Intl",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 127) => Some(&ProductInfo {
+			name: "LIFX White US",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((2700, 2700)),
+		}),
+		(1, 128) => Some(&ProductInfo {
+			name: "LIFX White Intl",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((2700, 2700)),
+		}),
+		(1, 129) => Some(&ProductInfo {
+			name: "LIFX Color US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 130) => Some(&ProductInfo {
+			name: "LIFX Color Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 131) => Some(&ProductInfo {
+			name: "LIFX White To Warm US",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 132) => Some(&ProductInfo {
+			name: "LIFX White To Warm Intl",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 133) => Some(&ProductInfo {
+			name: "LIFX White US",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((2700, 2700)),
+		}),
+		(1, 134) => Some(&ProductInfo {
+			name: "LIFX White Intl",
+			color: false,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((2700, 2700)),
+		}),
+		(1, 135) => Some(&ProductInfo {
+			name: "LIFX GU10 Color US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 136) => Some(&ProductInfo {
+			name: "LIFX GU10 Color Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 137) => Some(&ProductInfo {
+			name: "LIFX Candle Color US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 138) => Some(&ProductInfo {
+			name: "LIFX Candle Color Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 141) => Some(&ProductInfo {
+			name: "LIFX Neon US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 142) => Some(&ProductInfo {
+			name: "LIFX Neon Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 143) => Some(&ProductInfo {
+			name: "LIFX String US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 144) => Some(&ProductInfo {
+			name: "LIFX String Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 161) => Some(&ProductInfo {
+			name: "LIFX Outdoor Neon US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 162) => Some(&ProductInfo {
+			name: "LIFX Outdoor Neon Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 163) => Some(&ProductInfo {
+			name: "LIFX A19 US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 164) => Some(&ProductInfo {
+			name: "LIFX BR30 US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 165) => Some(&ProductInfo {
+			name: "LIFX A19 Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 166) => Some(&ProductInfo {
+			name: "LIFX BR30 Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 167) => Some(&ProductInfo {
+			name: "LIFX Downlight",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 168) => Some(&ProductInfo {
+			name: "LIFX Downlight",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 169) => Some(&ProductInfo {
+			name: "LIFX A21 1600lm US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 170) => Some(&ProductInfo {
+			name: "LIFX A21 1600lm Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 171) => Some(&ProductInfo {
+			name: "LIFX Round Spot US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 173) => Some(&ProductInfo {
+			name: "LIFX Round Path US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 174) => Some(&ProductInfo {
+			name: "LIFX Square Path US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 175) => Some(&ProductInfo {
+			name: "LIFX PAR38 US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 176) => Some(&ProductInfo {
+			name: "LIFX Ceiling US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 177) => Some(&ProductInfo {
+			name: "LIFX Ceiling Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 181) => Some(&ProductInfo {
+			name: "LIFX Color US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 182) => Some(&ProductInfo {
+			name: "LIFX Color Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 185) => Some(&ProductInfo {
+			name: "LIFX Candle Color US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 186) => Some(&ProductInfo {
+			name: "LIFX Candle Color Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 187) => Some(&ProductInfo {
+			name: "LIFX Candle Color US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 188) => Some(&ProductInfo {
+			name: "LIFX Candle Color Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 201) => Some(&ProductInfo {
+			name: "LIFX Ceiling 13x26\" US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 202) => Some(&ProductInfo {
+			name: "LIFX Ceiling 13x26\" Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 203) => Some(&ProductInfo {
+			name: "LIFX String US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 204) => Some(&ProductInfo {
+			name: "LIFX String Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 205) => Some(&ProductInfo {
+			name: "LIFX Indoor Neon US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 206) => Some(&ProductInfo {
+			name: "LIFX Indoor Neon Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 213) => Some(&ProductInfo {
+			name: "LIFX Permanent Outdoor US",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 214) => Some(&ProductInfo {
+			name: "LIFX Permanent Outdoor Intl",
+			color: true,
+			chain: false,
+			matrix: false,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: true,
+			extended_multizone: true,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 215) => Some(&ProductInfo {
+			name: "LIFX Candle Color US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 216) => Some(&ProductInfo {
+			name: "LIFX Candle Color Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 217) => Some(&ProductInfo {
+			name: "LIFX Tube US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 218) => Some(&ProductInfo {
+			name: "LIFX Tube Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 219) => Some(&ProductInfo {
+			name: "LIFX Luna US",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 220) => Some(&ProductInfo {
+			name: "LIFX Luna Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 221) => Some(&ProductInfo {
+			name: "LIFX Round Spot Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
+			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
+			temperature_range: Some((1500, 9000)),
+		}),
+		(1, 222) => Some(&ProductInfo {
+			name: "LIFX Round Path Intl",
+			color: true,
+			chain: false,
+			matrix: true,
+			infrared: false,
+			hev: false,
+			relays: false,
+			buttons: false,
 			multizone: false,
+			extended_multizone: false,
+			min_ext_mz_firmware: None,
+			min_ext_mz_firmware_components: None,
 			temperature_range: Some((1500, 9000)),
 		}),
-		(_, _) => None,
+		(vendor, product) => crate::vendor::registered_product(vendor, product),
 	}
 }
@@ -1,7 +1,7 @@
 use byteorder::{LittleEndian, WriteBytesExt};
 use std::io::Cursor;
 
-use crate::{error::Error, read_write::LittleEndianReader};
+use crate::{error::Error, misc::MacAddress, read_write::Codec};
 
 /// The Frame section contains information about the following:
 ///
@@ -14,6 +14,7 @@ use crate::{error::Error, read_write::LittleEndianReader};
 /// being used to address an individual device or all devices.  If `tagged` is true, then the
 /// `target` field should be all zeros.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
 	/// 16 bits: Size of entire message in bytes including this field
 	pub size: u16,
@@ -47,9 +48,10 @@ pub struct Frame {
 /// * State response message is required flag
 /// * Message sequence number
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FrameAddress {
 	/// 64 bits: 6 byte device address (MAC address) or zero (0) means all devices
-	pub target: u64,
+	pub target: MacAddress,
 
 	/// 48 bits: Must all be zero (0)
 	pub reserved: [u8; 6],
@@ -68,6 +70,7 @@ pub struct FrameAddress {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProtocolHeader {
 	/// 64 bits: Reserved
 	pub reserved: u64,
@@ -81,20 +84,60 @@ pub struct ProtocolHeader {
 
 impl Frame {
 	/// packed sized, in bytes
-	pub(crate) fn packed_size() -> usize {
-		8
+	pub const PACKED_SIZE: usize = 8;
+
+	/// packed sized, in bytes
+	pub fn packed_size() -> usize {
+		Self::PACKED_SIZE
 	}
 
-	#[allow(clippy::bool_assert_comparison)]
+	/// Panics if this frame is malformed.  See [Frame::try_validate].
 	pub(crate) fn validate(&self) {
-		assert!(self.origin < 4);
-		assert_eq!(self.addressable, true);
-		assert_eq!(self.protocol, 1024);
+		if let Err(e) = self.try_validate() {
+			panic!("{}", e);
+		}
+	}
+
+	/// Checks that this frame is well-formed, returning an error if not.
+	pub fn try_validate(&self) -> Result<(), Error> {
+		if self.origin >= 4 {
+			return Err(Error::InvalidHeaderField {
+				field: "origin",
+				value: self.origin as u32,
+			});
+		}
+		if !self.addressable {
+			return Err(Error::InvalidHeaderField {
+				field: "addressable",
+				value: 0,
+			});
+		}
+		if self.protocol != 1024 {
+			return Err(Error::InvalidHeaderField {
+				field: "protocol",
+				value: self.protocol as u32,
+			});
+		}
+		Ok(())
 	}
 
-	pub(crate) fn pack(&self) -> Result<Vec<u8>, Error> {
-		let mut v = Vec::with_capacity(Self::packed_size());
+	/// Packs this part of the packet into some bytes
+	pub fn pack(&self) -> Result<Vec<u8>, Error> {
+		let mut v = Vec::with_capacity(Self::PACKED_SIZE);
+		self.write_to(&mut v)?;
+		Ok(v)
+	}
+
+	/// Packs this frame into the start of `buf`, returning the number of bytes written.
+	///
+	/// Fails with [Error::BufferTooSmall] if `buf` is shorter than [Frame::packed_size].
+	pub fn pack_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+		let size = Self::PACKED_SIZE;
+		self.write_to(buffer_prefix(buf, size)?)?;
+		Ok(size)
+	}
 
+	pub(crate) fn write_to<W: WriteBytesExt>(&self, mut v: W) -> Result<(), Error> {
 		v.write_u16::<LittleEndian>(self.size)?;
 
 		// pack origin + tagged + addressable +  protocol as a u16
@@ -107,16 +150,16 @@ impl Frame {
 
 		v.write_u32::<LittleEndian>(self.source)?;
 
-		Ok(v)
+		Ok(())
 	}
 
 	pub(crate) fn unpack(v: &[u8]) -> Result<Frame, Error> {
 		let mut c = Cursor::new(v);
 
-		let size = c.read_val()?;
+		let size = Codec::decode(&mut c)?;
 
 		// origin + tagged + addressable + protocol
-		let d: u16 = c.read_val()?;
+		let d: u16 = Codec::decode(&mut c)?;
 
 		let origin: u8 = ((d & 0b1100_0000_0000_0000) >> 14) as u8;
 		let tagged: bool = (d & 0b0010_0000_0000_0000) > 0;
@@ -124,13 +167,13 @@ impl Frame {
 		let protocol: u16 = d & 0b0000_1111_1111_1111;
 
 		if protocol != 1024 {
-			return Err(Error::ProtocolError(format!(
-				"Unpacked frame had protocol version {}",
-				protocol
-			)));
+			return Err(Error::InvalidHeaderField {
+				field: "protocol",
+				value: protocol as u32,
+			});
 		}
 
-		let source = c.read_val()?;
+		let source = Codec::decode(&mut c)?;
 
 		let frame = Frame {
 			size,
@@ -145,18 +188,63 @@ impl Frame {
 }
 
 impl FrameAddress {
-	pub(crate) fn packed_size() -> usize {
-		16
+	/// packed sized, in bytes
+	pub const PACKED_SIZE: usize = 16;
+
+	/// packed sized, in bytes
+	pub fn packed_size() -> usize {
+		Self::PACKED_SIZE
 	}
 
+	/// Panics if this frame address is malformed.  See [FrameAddress::try_validate].
 	pub(crate) fn validate(&self) {
+		if let Err(e) = self.try_validate() {
+			panic!("{}", e);
+		}
+	}
+
+	/// Checks that this frame address is well-formed, returning an error if not.
+	pub fn try_validate(&self) -> Result<(), Error> {
 		//assert_eq!(self.reserved, [0;6]);
 		//assert_eq!(self.reserved2, 0);
+		Ok(())
 	}
 
-	pub(crate) fn pack(&self) -> Result<Vec<u8>, Error> {
-		let mut v = Vec::with_capacity(Self::packed_size());
-		v.write_u64::<LittleEndian>(self.target)?;
+	/// Returns an error if any reserved field is non-zero.
+	pub(crate) fn check_reserved(&self) -> Result<(), Error> {
+		if self.reserved != [0; 6] {
+			return Err(Error::ReservedFieldNonZero {
+				typ: None,
+				field: "reserved",
+			});
+		}
+		if self.reserved2 != 0 {
+			return Err(Error::ReservedFieldNonZero {
+				typ: None,
+				field: "reserved2",
+			});
+		}
+		Ok(())
+	}
+
+	/// Packs this part of the packet into some bytes
+	pub fn pack(&self) -> Result<Vec<u8>, Error> {
+		let mut v = Vec::with_capacity(Self::PACKED_SIZE);
+		self.write_to(&mut v)?;
+		Ok(v)
+	}
+
+	/// Packs this frame address into the start of `buf`, returning the number of bytes written.
+	///
+	/// Fails with [Error::BufferTooSmall] if `buf` is shorter than [FrameAddress::packed_size].
+	pub fn pack_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+		let size = Self::PACKED_SIZE;
+		self.write_to(buffer_prefix(buf, size)?)?;
+		Ok(size)
+	}
+
+	pub(crate) fn write_to<W: WriteBytesExt>(&self, mut v: W) -> Result<(), Error> {
+		v.write_u64::<LittleEndian>(self.target.into())?;
 		for idx in 0..6 {
 			v.write_u8(self.reserved[idx])?;
 		}
@@ -166,71 +254,121 @@ impl FrameAddress {
 			+ if self.res_required { 1 } else { 0 };
 		v.write_u8(b)?;
 		v.write_u8(self.sequence)?;
-		Ok(v)
+		Ok(())
 	}
 
 	pub(crate) fn unpack(v: &[u8]) -> Result<FrameAddress, Error> {
 		let mut c = Cursor::new(v);
 
-		let target = c.read_val()?;
+		let target: u64 = Codec::decode(&mut c)?;
 
-		let mut reserved: [u8; 6] = [0; 6];
-		for slot in &mut reserved {
-			*slot = c.read_val()?;
-		}
+		let reserved: [u8; 6] = Codec::decode(&mut c)?;
 
-		let b: u8 = c.read_val()?;
+		let b: u8 = Codec::decode(&mut c)?;
 		let reserved2: u8 = (b & 0b1111_1100) >> 2;
 		let ack_required = (b & 0b10) > 0;
 		let res_required = (b & 0b01) > 0;
 
-		let sequence = c.read_val()?;
+		let sequence = Codec::decode(&mut c)?;
 
 		let f = FrameAddress {
-			target,
+			target: target.into(),
 			reserved,
 			reserved2,
 			ack_required,
 			res_required,
 			sequence,
 		};
-		f.validate();
+		f.try_validate()?;
 		Ok(f)
 	}
 }
 
 impl ProtocolHeader {
-	pub(crate) fn packed_size() -> usize {
-		12
+	/// packed sized, in bytes
+	pub const PACKED_SIZE: usize = 12;
+
+	/// packed sized, in bytes
+	pub fn packed_size() -> usize {
+		Self::PACKED_SIZE
 	}
 
+	/// Panics if this protocol header is malformed.  See [ProtocolHeader::try_validate].
 	pub(crate) fn validate(&self) {
+		if let Err(e) = self.try_validate() {
+			panic!("{}", e);
+		}
+	}
+
+	/// Checks that this protocol header is well-formed, returning an error if not.
+	pub fn try_validate(&self) -> Result<(), Error> {
 		//assert_eq!(self.reserved, 0);
 		//assert_eq!(self.reserved2, 0);
+		Ok(())
+	}
+
+	/// Returns an error if any reserved field is non-zero.
+	pub(crate) fn check_reserved(&self) -> Result<(), Error> {
+		if self.reserved != 0 {
+			return Err(Error::ReservedFieldNonZero {
+				typ: Some(self.typ),
+				field: "reserved",
+			});
+		}
+		if self.reserved2 != 0 {
+			return Err(Error::ReservedFieldNonZero {
+				typ: Some(self.typ),
+				field: "reserved2",
+			});
+		}
+		Ok(())
 	}
 
 	/// Packs this part of the packet into some bytes
 	pub fn pack(&self) -> Result<Vec<u8>, Error> {
-		let mut v = Vec::with_capacity(Self::packed_size());
+		let mut v = Vec::with_capacity(Self::PACKED_SIZE);
+		self.write_to(&mut v)?;
+		Ok(v)
+	}
+
+	/// Packs this protocol header into the start of `buf`, returning the number of bytes written.
+	///
+	/// Fails with [Error::BufferTooSmall] if `buf` is shorter than [ProtocolHeader::packed_size].
+	pub fn pack_into(&self, buf: &mut [u8]) -> Result<usize, Error> {
+		let size = Self::PACKED_SIZE;
+		self.write_to(buffer_prefix(buf, size)?)?;
+		Ok(size)
+	}
+
+	pub(crate) fn write_to<W: WriteBytesExt>(&self, mut v: W) -> Result<(), Error> {
 		v.write_u64::<LittleEndian>(self.reserved)?;
 		v.write_u16::<LittleEndian>(self.typ)?;
 		v.write_u16::<LittleEndian>(self.reserved2)?;
-		Ok(v)
+		Ok(())
 	}
 
 	pub(crate) fn unpack(v: &[u8]) -> Result<ProtocolHeader, Error> {
 		let mut c = Cursor::new(v);
 
-		let reserved = c.read_val()?;
-		let typ = c.read_val()?;
-		let reserved2 = c.read_val()?;
+		let reserved = Codec::decode(&mut c)?;
+		let typ = Codec::decode(&mut c)?;
+		let reserved2 = Codec::decode(&mut c)?;
 
 		let f = ProtocolHeader {
 			reserved,
 			typ,
 			reserved2,
 		};
-		f.validate();
+		f.try_validate()?;
 		Ok(f)
 	}
 }
+
+/// Returns the first `size` bytes of `buf`, or an error if it's too short.
+pub(crate) fn buffer_prefix(buf: &mut [u8], size: usize) -> Result<&mut [u8], Error> {
+	let actual = buf.len();
+	buf.get_mut(..size).ok_or(Error::BufferTooSmall {
+		needed: size,
+		actual,
+	})
+}
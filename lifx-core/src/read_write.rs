@@ -1,51 +1,85 @@
-use std::io;
+use std::io::{self, Read, Write};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-pub trait LittleEndianReader<T> {
-	fn read_val(&mut self) -> Result<T, io::Error>;
-}
-impl<R: ReadBytesExt> LittleEndianReader<u8> for R {
-	fn read_val(&mut self) -> Result<u8, io::Error> {
-		self.read_u8()
-	}
-}
-macro_rules! derive_reader {
-{ $( $m:ident: $t:ty ),*} => {
-		$(
-			impl<T: ReadBytesExt> LittleEndianReader<$t> for T {
-				fn read_val(&mut self) -> Result<$t, io::Error> {
-						self . $m ::<LittleEndian>()
-				}
-			}
-		)*
+use crate::error::Error;
 
+/// A field type that can be written to and read from a message payload.
+///
+/// Values are encoded in order, little-endian, with no padding.  Since each type reads exactly
+/// what it needs, its size can depend on its contents (see [ButtonTarget](crate::ButtonTarget)).
+pub trait Codec: Sized {
+	/// Writes this value to `w`.
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error>;
+
+	/// Reads a value from `r`.
+	///
+	/// Values that aren't valid for this type (e.g. an unknown enum discriminant) are reported as
+	/// [io::ErrorKind::InvalidData].
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Self, io::Error>;
 }
-}
-derive_reader! { read_u32: u32, read_u16: u16, read_i16: i16, read_u64: u64, read_f32: f32 }
 
-pub trait LittleEndianWriter<T>: WriteBytesExt {
-	fn write_val(&mut self, v: T) -> Result<(), io::Error>;
+/// Wraps an [Error] from converting a decoded value, so it can be recovered by
+/// [Error::from_read].
+pub(crate) fn invalid_data(e: Error) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, e)
 }
-impl<T: WriteBytesExt> LittleEndianWriter<u8> for T {
-	fn write_val(&mut self, v: u8) -> Result<(), io::Error> {
-		self.write_u8(v)
+
+impl Codec for u8 {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		w.write_u8(*self)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<u8, io::Error> {
+		r.read_u8()
 	}
 }
-impl<T: WriteBytesExt> LittleEndianWriter<bool> for T {
-	fn write_val(&mut self, v: bool) -> Result<(), io::Error> {
-		self.write_u8(if v { 1 } else { 0 })
+
+impl Codec for bool {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		w.write_u8(if *self { 1 } else { 0 })
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<bool, io::Error> {
+		Ok(r.read_u8()? != 0)
 	}
 }
-macro_rules! derive_writer {
-{ $( $m:ident: $t:ty ),*} => {
+
+macro_rules! derive_codec {
+{ $( $t:ty: $r:ident, $w:ident ),* } => {
 	$(
-		impl<T: WriteBytesExt> LittleEndianWriter<$t> for T {
-			fn write_val(&mut self, v: $t) -> Result<(), io::Error> {
-				self . $m ::<LittleEndian>(v)
+		impl Codec for $t {
+			fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+				w . $w ::<LittleEndian>(*self)
+			}
+
+			fn decode<R: Read + ?Sized>(r: &mut R) -> Result<$t, io::Error> {
+				r . $r ::<LittleEndian>()
 			}
 		}
 	)*
-
 }
 }
-derive_writer! { write_u32: u32, write_u16: u16, write_i16: i16, write_u64: u64, write_f32: f32 }
+derive_codec! {
+	u16: read_u16, write_u16,
+	i16: read_i16, write_i16,
+	u32: read_u32, write_u32,
+	u64: read_u64, write_u64,
+	f32: read_f32, write_f32
+}
+
+impl<T: Codec + Default, const N: usize> Codec for [T; N] {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		for x in self {
+			x.encode(w)?;
+		}
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<[T; N], io::Error> {
+		let mut val: [T; N] = std::array::from_fn(|_| T::default());
+		for v in val.iter_mut() {
+			*v = T::decode(r)?;
+		}
+		Ok(val)
+	}
+}
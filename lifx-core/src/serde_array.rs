@@ -0,0 +1,55 @@
+//! (De)serializes fixed-size arrays of any length.
+//!
+//! serde only implements its traits for arrays of up to 32 elements; this is used (via
+//! `#[serde(with = "crate::serde_array")]`) for the larger arrays in the protocol.
+
+use std::{convert::TryInto, fmt, marker::PhantomData};
+
+use serde::{
+	de::{Error, SeqAccess, Visitor},
+	ser::SerializeTuple,
+	Deserialize, Deserializer, Serialize, Serializer,
+};
+
+pub fn serialize<S, T, const N: usize>(v: &[T; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+	S: Serializer,
+	T: Serialize,
+{
+	let mut tup = serializer.serialize_tuple(N)?;
+	for x in v.iter() {
+		tup.serialize_element(x)?;
+	}
+	tup.end()
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T, const N: usize> Visitor<'de> for ArrayVisitor<T, N>
+where
+	T: Deserialize<'de>,
+{
+	type Value = [T; N];
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "an array of length {}", N)
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+		let mut v = Vec::with_capacity(N);
+		while let Some(x) = seq.next_element()? {
+			v.push(x);
+		}
+		let len = v.len();
+		v.try_into()
+			.map_err(|_| A::Error::invalid_length(len, &self))
+	}
+}
+
+pub fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+	D: Deserializer<'de>,
+	T: Deserialize<'de>,
+{
+	deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
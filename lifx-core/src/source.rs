@@ -0,0 +1,28 @@
+use std::{
+	collections::hash_map::RandomState,
+	hash::{BuildHasher, Hasher},
+	process,
+	time::SystemTime,
+};
+
+/// Picks a random, non-zero [Frame::source](crate::Frame::source) for a new client.
+///
+/// Devices send their replies to whichever client used a message's source, so clients on the same
+/// network should each use a different one.  A source of zero would make devices broadcast their
+/// replies instead, so it's never returned.
+///
+/// This draws on the standard library's per-process hash keys, the time and the process ID, which
+/// is plenty to keep clients apart but isn't suitable for anything security-sensitive.
+pub fn random_source() -> u32 {
+	let mut hasher = RandomState::new().build_hasher();
+	if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+		hasher.write_u128(elapsed.as_nanos());
+	}
+	hasher.write_u32(process::id());
+
+	let hash = hasher.finish();
+	match (hash as u32) ^ (hash >> 32) as u32 {
+		0 => 1,
+		source => source,
+	}
+}
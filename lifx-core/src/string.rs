@@ -1,61 +1,102 @@
-use std::io;
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use std::{
+	borrow::Cow,
+	convert::TryFrom,
+	io::{self, Read, Write},
+};
 
-use crate::read_write::{LittleEndianReader, LittleEndianWriter};
+use crate::{read_write::Codec, Error};
 
-/// Lifx strings are fixed-length (32-bytes maximum)
-#[derive(Debug, Clone, PartialEq)]
-pub struct LifxString(pub String);
+/// Lifx strings are fixed-length, 32-byte buffers.
+///
+/// The raw bytes are kept as-is, so a label read from a device is written back unchanged.  They are
+/// decoded as UTF-8 up to the first NUL; invalid sequences are replaced with U+FFFD.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct LifxString([u8; 32]);
 
 impl LifxString {
-	/// Constructs a new LifxString, truncating to 32 characters.
+	/// Constructs a new LifxString, truncating to at most 32 bytes.
+	///
+	/// Truncation happens on a character boundary, so a multi-byte character is never split.  Use
+	/// [LifxString::try_from] to get an error instead.
 	pub fn new(s: &str) -> LifxString {
-		LifxString(if s.len() > 32 {
-			s[..32].to_owned()
-		} else {
-			s.to_owned()
-		})
+		let mut len = s.len().min(32);
+		while !s.is_char_boundary(len) {
+			len -= 1;
+		}
+		let mut bytes = [0; 32];
+		bytes[..len].copy_from_slice(&s.as_bytes()[..len]);
+		LifxString(bytes)
+	}
+
+	/// Constructs a LifxString from its raw bytes.
+	pub fn from_bytes(bytes: [u8; 32]) -> LifxString {
+		LifxString(bytes)
+	}
+
+	/// The raw bytes, including any NUL padding.
+	pub fn as_bytes(&self) -> &[u8; 32] {
+		&self.0
+	}
+
+	/// Decodes the string, up to the first NUL.
+	pub fn to_str(&self) -> Cow<'_, str> {
+		let len = self.0.iter().position(|&b| b == 0).unwrap_or(32);
+		String::from_utf8_lossy(&self.0[..len])
+	}
+}
+
+impl TryFrom<&str> for LifxString {
+	type Error = Error;
+
+	/// Constructs a new LifxString, failing with [Error::StringTooLong] if `s` is more than 32
+	/// bytes.
+	fn try_from(s: &str) -> Result<LifxString, Error> {
+		if s.len() > 32 {
+			return Err(Error::StringTooLong { len: s.len() });
+		}
+		Ok(LifxString::new(s))
+	}
+}
+
+impl std::fmt::Debug for LifxString {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+		write!(fmt, "LifxString({:?})", self.to_str())
 	}
 }
 
 impl std::fmt::Display for LifxString {
 	fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-		write!(fmt, "{}", self.0)
+		write!(fmt, "{}", self.to_str())
 	}
 }
 
 impl std::cmp::PartialEq<str> for LifxString {
 	fn eq(&self, other: &str) -> bool {
-		self.0 == other
+		self.to_str() == other
 	}
 }
 
-impl<R: ReadBytesExt> LittleEndianReader<LifxString> for R {
-	fn read_val(&mut self) -> Result<LifxString, io::Error> {
-		let mut label = String::with_capacity(32);
-		for _ in 0..32 {
-			let c: u8 = self.read_val()?;
-			if c > 0 {
-				label.push(c as char);
-			}
-		}
-		Ok(LifxString(label))
+#[cfg(feature = "serde")]
+impl serde::Serialize for LifxString {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.to_str())
 	}
 }
 
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LifxString {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<LifxString, D::Error> {
+		let s = <Cow<str>>::deserialize(deserializer)?;
+		Ok(LifxString::new(&s))
+	}
+}
 
-impl<T> LittleEndianWriter<LifxString> for T
-where
-	T: WriteBytesExt,
-{
-	fn write_val(&mut self, v: LifxString) -> Result<(), io::Error> {
-		for idx in 0..32 {
-			if idx >= v.0.len() {
-				self.write_u8(0)?;
-			} else {
-				self.write_u8(v.0.chars().nth(idx).unwrap() as u8)?;
-			}
-		}
-		Ok(())
+impl Codec for LifxString {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.0.encode(w)
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<LifxString, io::Error> {
+		Ok(LifxString(Codec::decode(r)?))
 	}
 }
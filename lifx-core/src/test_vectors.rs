@@ -0,0 +1,106 @@
+//! Known packets and their decoded forms.
+//!
+//! These are useful for validating a transport layer (or another implementation of the protocol)
+//! without having every kind of device on hand.  Each [TestVector] pairs the bytes of a complete
+//! packet with the header fields and [Message] it should decode to.
+//!
+//! Requires the `test-vectors` feature.
+
+use crate::{LifxString, MacAddress, Message, PowerLevel, HSBK};
+
+/// A complete packet, along with what it should decode to.
+#[derive(Debug, Clone)]
+pub struct TestVector {
+	/// A short description of where the packet came from
+	pub name: &'static str,
+	/// The packet, exactly as sent over the network
+	pub bytes: &'static [u8],
+	/// The expected [FrameAddress::target](crate::FrameAddress::target)
+	pub target: MacAddress,
+	/// The expected [Frame::source](crate::Frame::source)
+	pub source: u32,
+	/// The expected [FrameAddress::sequence](crate::FrameAddress::sequence)
+	pub sequence: u8,
+	/// The expected decoded payload
+	pub message: Message,
+}
+
+/// Returns every known test vector.
+pub fn all() -> Vec<TestVector> {
+	vec![
+		TestVector {
+			name: "GetService broadcast (documented discovery packet)",
+			bytes: &[
+				0x24, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+			],
+			target: MacAddress::ALL,
+			source: 0,
+			sequence: 0,
+			message: Message::GetService,
+		},
+		TestVector {
+			// https://lan.developer.lifx.com/docs/building-a-lifx-packet
+			name: "LightSetColor (documented example packet)",
+			bytes: &[
+				0x31, 0x00, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x66, 0x00, 0x00, 0x00, 0x00, 0x55, 0x55, 0xFF, 0xFF, 0xFF,
+				0xFF, 0xAC, 0x0D, 0x00, 0x04, 0x00, 0x00,
+			],
+			target: MacAddress::ALL,
+			source: 0,
+			sequence: 0,
+			message: Message::LightSetColor {
+				reserved: 0,
+				color: HSBK {
+					hue: 21845,
+					saturation: 0xffff,
+					brightness: 0xffff,
+					kelvin: 3500,
+				},
+				duration: 1024,
+			},
+		},
+		TestVector {
+			name: "GetGroup (captured from a client)",
+			bytes: &[
+				0x24, 0x00, 0x00, 0x14, 0xca, 0x41, 0x37, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x98, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x33, 0x00, 0x00, 0x00,
+			],
+			target: MacAddress::ALL,
+			source: 0x0537_41ca,
+			sequence: 0x98,
+			message: Message::GetGroup,
+		},
+		TestVector {
+			name: "LightState (captured from a bulb)",
+			bytes: &[
+				0x58, 0x00, 0x00, 0x54, 0xca, 0x41, 0x37, 0x05, 0xd0, 0x73, 0xd5, 0x02, 0x97, 0xde,
+				0x00, 0x00, 0x4c, 0x49, 0x46, 0x58, 0x56, 0x32, 0x00, 0xc0, 0x44, 0x30, 0xeb, 0x47,
+				0xc4, 0x48, 0x18, 0x14, 0x6b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+				0xb8, 0x0b, 0x00, 0x00, 0xff, 0xff, 0x4b, 0x69, 0x74, 0x63, 0x68, 0x65, 0x6e, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+				0x00, 0x00, 0x00, 0x00,
+			],
+			target: MacAddress([0xd0, 0x73, 0xd5, 0x02, 0x97, 0xde]),
+			source: 0x0537_41ca,
+			sequence: 0xc0,
+			message: Message::LightState {
+				color: HSBK {
+					hue: 0,
+					saturation: 0,
+					brightness: 0xffff,
+					kelvin: 3000,
+				},
+				reserved: 0,
+				power: PowerLevel::Enabled,
+				label: LifxString::new("Kitchen"),
+				reserved2: 0,
+			},
+		},
+	]
+}
@@ -0,0 +1,78 @@
+use std::io::{self, Read, Write};
+
+use crate::read_write::Codec;
+
+/// Information about a single tile in a device chain.
+///
+/// See also [Message::StateDeviceChain].
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tile {
+	/// Accelerometer readings, used to determine the orientation of the tile
+	pub accel_meas_x: i16,
+	pub accel_meas_y: i16,
+	pub accel_meas_z: i16,
+	pub reserved: i16,
+	/// Position of the tile, as set by the user (see [Message::SetUserPosition])
+	pub user_x: f32,
+	pub user_y: f32,
+	/// Number of pixels along the x axis
+	pub width: u8,
+	/// Number of pixels along the y axis
+	pub height: u8,
+	pub reserved2: u8,
+	pub device_version_vendor: u32,
+	pub device_version_product: u32,
+	pub device_version_version: u32,
+	/// Firmware build time (absolute time in nanoseconds since epoch)
+	pub firmware_build: u64,
+	pub reserved3: u64,
+	pub firmware_version_minor: u16,
+	pub firmware_version_major: u16,
+	pub reserved4: u32,
+}
+
+impl Codec for Tile {
+	fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<(), io::Error> {
+		self.accel_meas_x.encode(w)?;
+		self.accel_meas_y.encode(w)?;
+		self.accel_meas_z.encode(w)?;
+		self.reserved.encode(w)?;
+		self.user_x.encode(w)?;
+		self.user_y.encode(w)?;
+		self.width.encode(w)?;
+		self.height.encode(w)?;
+		self.reserved2.encode(w)?;
+		self.device_version_vendor.encode(w)?;
+		self.device_version_product.encode(w)?;
+		self.device_version_version.encode(w)?;
+		self.firmware_build.encode(w)?;
+		self.reserved3.encode(w)?;
+		self.firmware_version_minor.encode(w)?;
+		self.firmware_version_major.encode(w)?;
+		self.reserved4.encode(w)?;
+		Ok(())
+	}
+
+	fn decode<R: Read + ?Sized>(r: &mut R) -> Result<Tile, io::Error> {
+		Ok(Tile {
+			accel_meas_x: i16::decode(r)?,
+			accel_meas_y: i16::decode(r)?,
+			accel_meas_z: i16::decode(r)?,
+			reserved: i16::decode(r)?,
+			user_x: f32::decode(r)?,
+			user_y: f32::decode(r)?,
+			width: u8::decode(r)?,
+			height: u8::decode(r)?,
+			reserved2: u8::decode(r)?,
+			device_version_vendor: u32::decode(r)?,
+			device_version_product: u32::decode(r)?,
+			device_version_version: u32::decode(r)?,
+			firmware_build: u64::decode(r)?,
+			reserved3: u64::decode(r)?,
+			firmware_version_minor: u16::decode(r)?,
+			firmware_version_major: u16::decode(r)?,
+			reserved4: u32::decode(r)?,
+		})
+	}
+}
@@ -0,0 +1,59 @@
+//! Conversions between [std::time] types and the raw integers used by message fields.
+//!
+//! Transition times and waveform periods (e.g. [Message::LightSetColor]'s `duration`) are
+//! milliseconds in a `u32`, while firmware effect durations are nanoseconds in a `u64`.  Converting
+//! to these clamps rather than overflows, so an overly long [Duration] becomes the longest one the
+//! protocol can express.
+//!
+//! Timestamps (e.g. [Message::StateHostFirmware]'s `build`, [Message::StateInfo]'s `time`, and the
+//! `updated_at` of locations and groups) are nanoseconds since the Unix epoch in a `u64`; see
+//! [to_system_time] and [from_system_time].
+//!
+//! [Message::LightSetColor]: crate::Message::LightSetColor
+//! [Message::StateHostFirmware]: crate::Message::StateHostFirmware
+//! [Message::StateInfo]: crate::Message::StateInfo
+
+use std::{
+	convert::TryInto,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Converts a [Duration] to whole milliseconds, clamping at `u32::MAX` (about 49 days).
+pub fn to_millis(d: Duration) -> u32 {
+	d.as_millis().try_into().unwrap_or(u32::MAX)
+}
+
+/// Converts a millisecond field to a [Duration].
+pub fn from_millis(ms: u32) -> Duration {
+	Duration::from_millis(ms as u64)
+}
+
+/// Converts a [Duration] to whole nanoseconds, clamping at `u64::MAX` (about 584 years).
+pub fn to_nanos(d: Duration) -> u64 {
+	d.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+/// Converts a nanosecond field to a [Duration].
+pub fn from_nanos(ns: u64) -> Duration {
+	Duration::from_nanos(ns)
+}
+
+/// Converts a timestamp field (nanoseconds since the Unix epoch) to a [SystemTime].
+pub fn to_system_time(ns: u64) -> SystemTime {
+	UNIX_EPOCH + Duration::from_nanos(ns)
+}
+
+/// Converts a [SystemTime] to a timestamp field (nanoseconds since the Unix epoch).
+///
+/// Times before the epoch become zero, and times too far in the future clamp at `u64::MAX` (some
+/// time in 2554).
+pub fn from_system_time(t: SystemTime) -> u64 {
+	t.duration_since(UNIX_EPOCH).map(to_nanos).unwrap_or(0)
+}
+
+/// The current time, as a timestamp field.  Useful for the `updated_at` field of
+/// [Message::SetLocation](crate::Message::SetLocation) and
+/// [Message::SetGroup](crate::Message::SetGroup).
+pub fn now() -> u64 {
+	from_system_time(SystemTime::now())
+}
@@ -0,0 +1,574 @@
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::{
+	collections::HashMap,
+	net::SocketAddr,
+	sync::{
+		atomic::{AtomicU8, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant},
+};
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use tokio::net::UdpSocket;
+
+use crate::{
+	BuildOptions,
+	HSBK,
+	LifxIdent,
+	MacAddress,
+	Message,
+	RawMessage,
+	random_source,
+	udp::{
+		discovery::{self, DiscoverySchedule},
+		location::{for_each_in_group, for_each_in_location},
+		manager::{self, Packet, Request},
+		AutoDiscovery,
+		Bulb,
+		BulbSnapshot,
+		Effect,
+		Health,
+		InterfaceFilter,
+		Keepalive,
+		Location,
+		ManagerConfig,
+		QueueMetrics,
+		RateLimiter,
+		RateLimits,
+		RefreshPolicies,
+		RetryPolicy,
+		SendQueue,
+		Timeouts,
+	},
+};
+#[cfg(feature = "serde")]
+use crate::udp::SavedState;
+#[cfg(doc)]
+use crate::udp::Manager;
+
+/// Like [Manager], but built on tokio's [UdpSocket] for use inside an async application.
+///
+/// There are no background threads: the `AsyncManager` owns its bulbs, and they are only updated
+/// while [AsyncManager::recv] is being awaited.  A typical service runs a loop that `select!`s
+/// between `recv` and its own work, calling [AsyncManager::refresh] every so often.
+///
+/// Commands sent through a [Bulb] (like [Bulb::set_color]) are queued, and go out on the next
+/// [AsyncManager::flush] (which `refresh` also does).
+///
+/// Requires the `tokio` feature.
+pub struct AsyncManager {
+	bulbs: HashMap<MacAddress, Bulb>,
+	sock: Arc<UdpSocket>,
+	/// This AsyncManager's [Frame::source](crate::Frame::source), random unless given to
+	/// [AsyncManager::bind_with_source].  Bulbs already discovered keep the source they were
+	/// created with.
+	pub source: u32,
+	/// Sequence numbers for broadcasts, which aren't addressed to any one bulb.
+	sequence: AtomicU8,
+	/// The network interfaces that discovery broadcasts are sent on.
+	pub interfaces: InterfaceFilter,
+	/// Retry policy used for discovery broadcasts and requests, unless one is given for the call
+	/// (as with [AsyncManager::discover_with] or [AsyncManager::request_with]).
+	pub retry_policy: RetryPolicy,
+	pub timeouts: Timeouts,
+	/// If set, bulbs are sent echo requests on every [AsyncManager::refresh] (see
+	/// [Manager::health]).
+	pub keepalive: Option<Keepalive>,
+	/// How often each bulb's cached state is refreshed, applied on every [AsyncManager::refresh].
+	pub refresh_policies: RefreshPolicies,
+	/// If set, [AsyncManager::refresh] also re-runs discovery when it's due.
+	pub auto_discovery: Option<AutoDiscovery>,
+	discovery: DiscoverySchedule,
+	queue: SendQueue,
+	rate_limiter: RateLimiter,
+}
+
+impl AsyncManager {
+	/// Binds the LIFX port and starts discovering bulbs.
+	pub async fn new() -> Result<AsyncManager> {
+		AsyncManager::bind(SocketAddr::from(([0, 0, 0, 0], 56700))).await
+	}
+
+	/// Like [AsyncManager::new], but binds the socket to `addr` (see [Manager::bind]).
+	pub async fn bind(addr: SocketAddr) -> Result<AsyncManager> {
+		AsyncManager::bind_with_interfaces(addr, InterfaceFilter::all()).await
+	}
+
+	/// Like [AsyncManager::bind], but only discovers bulbs on the given interfaces (see
+	/// [Manager::bind_with_interfaces]).
+	pub async fn bind_with_interfaces(
+		addr: SocketAddr,
+		interfaces: InterfaceFilter,
+	) -> Result<AsyncManager> {
+		AsyncManager::bind_with_source(addr, interfaces, random_source()).await
+	}
+
+	/// Like [AsyncManager::bind_with_interfaces], but uses `source` instead of a random one (see
+	/// [Manager::bind_with_source]).
+	pub async fn bind_with_source(
+		addr: SocketAddr,
+		interfaces: InterfaceFilter,
+		source: u32,
+	) -> Result<AsyncManager> {
+		let config = ManagerConfig {
+			addr,
+			interfaces,
+			source: Some(source),
+			..Default::default()
+		};
+		AsyncManager::start(config).await
+	}
+
+	/// Binds [ManagerConfig::addr] and starts discovering bulbs, as configured by `config`.
+	pub(super) async fn start(config: ManagerConfig) -> Result<AsyncManager> {
+		let sock = UdpSocket::bind(config.addr).await?;
+		sock.set_broadcast(true)?;
+
+		let mut mgr = AsyncManager {
+			bulbs: HashMap::new(),
+			sock: Arc::new(sock),
+			source: config.source.unwrap_or_else(random_source),
+			sequence: AtomicU8::new(0),
+			interfaces: config.interfaces,
+			retry_policy: config.retry_policy,
+			timeouts: config.timeouts,
+			keepalive: config.keepalive,
+			refresh_policies: config.refresh_policies,
+			auto_discovery: config.auto_discovery,
+			discovery: DiscoverySchedule::new(Instant::now()),
+			queue: SendQueue::new(),
+			rate_limiter: RateLimiter::new(config.rate_limits),
+		};
+		mgr.discover().await?;
+
+		Ok(mgr)
+	}
+
+	/// Every bulb that has replied so far.
+	pub fn bulbs(&self) -> &HashMap<MacAddress, Bulb> {
+		&self.bulbs
+	}
+
+	pub fn bulb(&self, target: MacAddress) -> Option<&Bulb> {
+		self.bulbs.get(&target)
+	}
+
+	pub fn bulb_mut(&mut self, target: MacAddress) -> Option<&mut Bulb> {
+		self.bulbs.get_mut(&target)
+	}
+
+	/// Broadcasts a discovery request right away (see [Manager::discover]).
+	pub async fn discover(&mut self) -> Result<()> {
+		let policy = self.retry_policy;
+		self.discover_with(&policy).await
+	}
+
+	/// Broadcasts a discovery request, re-broadcasting it according to `policy`.
+	///
+	/// The retries are sent from a spawned task, so this returns as soon as the first broadcast
+	/// is out.
+	pub async fn discover_with(&mut self, policy: &RetryPolicy) -> Result<()> {
+		self.broadcast_discovery(policy).await?;
+		self.discovery.discovered(Instant::now(), self.bulbs.len());
+
+		Ok(())
+	}
+
+	/// When discovery last ran, whether it was asked for or automatic.
+	pub fn last_discovery(&self) -> Instant {
+		self.discovery.last
+	}
+
+	async fn broadcast_discovery(&self, policy: &RetryPolicy) -> Result<()> {
+		let targets = discovery::discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		self.send_repeatedly(Message::GetService, targets, policy).await
+	}
+
+	/// Sends a discovery request straight to `addr` (see [Manager::probe]).
+	pub async fn probe(&self, addr: SocketAddr) -> Result<()> {
+		let policy = self.retry_policy;
+		let addr = discovery::reachable_from(&self.sock.local_addr()?, addr);
+		self.send_repeatedly(Message::GetService, vec![addr], &policy).await
+	}
+
+	/// Probes the last known address of every bulb (see [Manager::rediscover_known]).
+	pub async fn rediscover_known(&self) -> Result<()> {
+		let mut targets: Vec<SocketAddr> = self.bulbs.values().map(|bulb| bulb.addr).collect();
+		targets.sort();
+		targets.dedup();
+
+		let policy = self.retry_policy;
+		self.send_repeatedly(Message::GetService, targets, &policy).await
+	}
+
+	/// Sends `message` untargeted to each of `targets`, re-sending it according to `policy` from a
+	/// spawned task.
+	async fn send_repeatedly(
+		&self,
+		message: Message,
+		targets: Vec<SocketAddr>,
+		policy: &RetryPolicy,
+	) -> Result<()> {
+		let opts = BuildOptions {
+			sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+			source: self.source,
+			..Default::default()
+		};
+		let bytes = RawMessage::build(&opts, message)?.pack()?;
+
+		for addr in &targets {
+			self.sock.send_to(&bytes, addr).await?;
+		}
+
+		let delays: Vec<Duration> = policy.delays().collect();
+		if !delays.is_empty() && !targets.is_empty() {
+			let sock = self.sock.clone();
+			tokio::spawn(async move {
+				for delay in delays {
+					tokio::time::sleep(delay).await;
+					for addr in &targets {
+						if let Err(e) = sock.send_to(&bytes, addr).await {
+							warn!("Error re-sending to {}: {}", addr, e);
+						}
+					}
+				}
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Sets the color of every bulb on the network with a single broadcast (see
+	/// [Manager::set_all_color]).
+	pub async fn set_all_color(&self, color: HSBK, duration: Duration) -> Result<()> {
+		let targets = discovery::discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		let policy = self.retry_policy;
+		self.send_repeatedly(Message::light_set_color(color, duration), targets, &policy).await
+	}
+
+	/// Turns every bulb on the network on or off with a single broadcast (see
+	/// [Manager::set_all_power]).
+	pub async fn set_all_power(&self, on: bool, duration: Duration) -> Result<()> {
+		let targets = discovery::discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		let policy = self.retry_policy;
+		self.send_repeatedly(Bulb::power_message(on, duration), targets, &policy).await
+	}
+
+	/// Waits for the next message from a bulb, and updates that bulb's cached state.
+	///
+	/// Returns the bulb that sent it.  Packets that can't be parsed, or that weren't sent by a
+	/// single bulb, are skipped.
+	pub async fn recv(&mut self) -> Result<MacAddress> {
+		Ok(self.recv_raw().await?.frame_addr.target)
+	}
+
+	/// Sends `message` (usually one of the `Get` messages) to a bulb, and returns its reply (see
+	/// [Manager::request]).
+	///
+	/// Other messages that arrive in the meantime update the cache as if [AsyncManager::recv]
+	/// had been called.
+	pub async fn request(&mut self, target: MacAddress, message: Message) -> Result<Message> {
+		let policy = self.retry_policy;
+		let timeout = self.timeouts.response;
+		self.request_with(target, message, &policy, timeout).await
+	}
+
+	/// Like [AsyncManager::request], but re-sends according to `policy`, waiting `timeout` (plus
+	/// the policy's delay) for each reply.
+	pub async fn request_with(
+		&mut self,
+		target: MacAddress,
+		message: Message,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<Message> {
+		self.send_and_wait(target, message, false, policy, timeout).await
+	}
+
+	/// Sends `message` to a bulb, and waits for it to be acknowledged (see
+	/// [Manager::send_with_ack]).
+	///
+	/// Other messages that arrive in the meantime update the cache, as with
+	/// [AsyncManager::request].
+	pub async fn send_with_ack(&mut self, target: MacAddress, message: Message) -> Result<()> {
+		let policy = self.retry_policy;
+		let timeout = self.timeouts.response;
+		self.send_with_ack_with(target, message, &policy, timeout).await
+	}
+
+	/// Like [AsyncManager::send_with_ack], but re-sends according to `policy`, waiting `timeout`
+	/// (plus the policy's delay) for each acknowledgement.
+	pub async fn send_with_ack_with(
+		&mut self,
+		target: MacAddress,
+		message: Message,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<()> {
+		self.send_and_wait(target, message, true, policy, timeout).await.map(|_| ())
+	}
+
+	/// Sends `message` to a known bulb, asking for an acknowledgement or a response, and waits for
+	/// it (retrying as needed).
+	async fn send_and_wait(
+		&mut self,
+		target: MacAddress,
+		message: Message,
+		ack: bool,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<Message> {
+		let bulb = self.bulbs.get(&target).ok_or_else(|| anyhow!("unknown bulb {}", target))?;
+		let request = Request::new(bulb, self.source, message, ack)?;
+
+		let mut attempts = 1;
+		loop {
+			self.sock.send_to(&request.bytes, request.addr).await?;
+			let retry = policy.delay(attempts);
+			let wait = timeout + retry.unwrap_or_default();
+			let deadline = tokio::time::Instant::now() + wait;
+			while let Ok(raw) = tokio::time::timeout_at(deadline, self.recv_raw()).await {
+				let raw = raw?;
+				// as with the Manager, a reply that can't be decoded is returned as an error
+				if raw.response_meta() == request.meta {
+					return Ok(Message::from_raw(&raw)?);
+				}
+			}
+			if retry.is_none() {
+				return Err(request.unanswered(attempts));
+			}
+			attempts += 1;
+		}
+	}
+
+	/// Like [AsyncManager::recv], but returns the whole message.
+	async fn recv_raw(&mut self) -> Result<RawMessage> {
+		let mut buf = [0; 1024];
+		loop {
+			let (nbytes, addr) = self.sock.recv_from(&mut buf).await?;
+			if nbytes == 0 {
+				debug!("Received a zero-byte datagram from {:?}", addr);
+				continue;
+			}
+			let packet = Packet {
+				bytes: &buf[0..nbytes],
+				addr,
+				source: self.source,
+			};
+			let raw = match packet.unpack() {
+				Ok(Some(raw)) => raw,
+				Ok(None) => continue,
+				Err(e) => {
+					warn!("{}", e);
+					continue;
+				}
+			};
+			if let Err(e) = packet.update(&mut self.bulbs, &self.queue, raw.clone(), |_| ()) {
+				debug!("{}", e)
+			}
+			return Ok(raw);
+		}
+	}
+
+	/// Queues refresh queries for any missing or stale data, then sends everything queued.
+	///
+	/// Like [Manager::refresh], this first marks quiet bulbs offline or forgets them, and re-runs
+	/// discovery if it's due.
+	pub async fn refresh(&mut self) -> Result<()> {
+		manager::sweep_offline(&mut self.bulbs, &self.timeouts, |_| ());
+		if let Some(config) = self.auto_discovery {
+			if self.discovery.due(&config, Instant::now(), self.bulbs.len()) {
+				let policy = self.retry_policy;
+				self.broadcast_discovery(&policy).await?;
+			}
+		}
+		for bulb in self.bulbs.values_mut() {
+			bulb.set_refresh_policies(self.refresh_policies);
+			bulb.query_for_missing_info(self.timeouts.refresh)?;
+		}
+		self.send_echoes().await?;
+		self.flush().await
+	}
+
+	/// How a bulb has been answering echo requests, if it's known (see [Manager::health]).
+	pub fn health(&self, target: MacAddress) -> Option<Health> {
+		let keepalive = self.keepalive.unwrap_or_default();
+		self.bulbs.get(&target).map(|bulb| bulb.health(&keepalive))
+	}
+
+	/// Sends an echo request to every bulb that's due one, if [AsyncManager::keepalive] is set.
+	async fn send_echoes(&mut self) -> Result<()> {
+		let keepalive = match self.keepalive {
+			Some(keepalive) => keepalive,
+			None => return Ok(()),
+		};
+		for bulb in self.bulbs.values_mut() {
+			if let Some(echo) = bulb.start_echo(&keepalive) {
+				let options = BuildOptions {
+					target: Some(bulb.target),
+					sequence: bulb.next_sequence(),
+					source: self.source,
+					..Default::default()
+				};
+				let bytes = RawMessage::build(&options, echo)?.pack()?;
+				if let Err(e) = self.sock.send_to(&bytes, bulb.addr).await {
+					warn!("Error sending echo request to {}: {}", bulb.addr, e);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Sends every queued message, most urgent first, waiting as needed to stay within the
+	/// [RateLimits].
+	pub async fn flush(&mut self) -> Result<()> {
+		while let Some(outgoing) = self.queue.try_pop() {
+			while let Err(wait) = self.rate_limiter.try_acquire(outgoing.target, Instant::now()) {
+				tokio::time::sleep(wait).await;
+			}
+			if let Err(e) = self.sock.send_to(&outgoing.bytes, outgoing.addr).await {
+				warn!("Error sending to {}: {}", outgoing.addr, e);
+			}
+			self.queue.recycle(outgoing.bytes);
+		}
+		Ok(())
+	}
+
+	/// Sends `message` straight to a known bulb, bypassing the queue.
+	pub async fn send(&self, target: MacAddress, message: Message) -> Result<()> {
+		let addr = self.addr_of(target)?;
+		let options = BuildOptions {
+			target: Some(target),
+			sequence: self.bulbs[&target].next_sequence(),
+			source: self.source,
+			..Default::default()
+		};
+		let bytes = RawMessage::build(&options, message)?.pack()?;
+		self.sock.send_to(&bytes, addr).await?;
+		Ok(())
+	}
+
+	/// Every location that the bulbs belong to, with its groups (see [Location::collect]).
+	pub fn locations(&self) -> Vec<Location> {
+		Location::collect(self.bulbs.values())
+	}
+
+	/// A copy of every known bulb's state, sorted by address; see [Manager::snapshot].
+	pub fn snapshot(&self) -> Vec<BulbSnapshot> {
+		manager::snapshot(self.bulbs.values())
+	}
+
+	/// The bulb with address `target`, if it's known.
+	pub fn get(&self, target: MacAddress) -> Option<BulbSnapshot> {
+		self.bulbs.get(&target).map(Bulb::snapshot)
+	}
+
+	/// The bulb last heard from at `addr`, if there is one.
+	pub fn find_by_addr(&self, addr: SocketAddr) -> Option<BulbSnapshot> {
+		self.bulbs.values().find(|bulb| bulb.addr == addr).map(Bulb::snapshot)
+	}
+
+	/// Every bulb labelled `label`, sorted by address.
+	pub fn find_by_label(&self, label: &str) -> Vec<BulbSnapshot> {
+		manager::snapshot(self.bulbs.values().filter(|bulb| bulb.label() == Some(label)))
+	}
+
+	/// Every bulb in a group labelled `group`, sorted by address; see [Manager::find_by_group].
+	pub fn find_by_group(&self, group: &str) -> Vec<BulbSnapshot> {
+		let in_group = |bulb: &&Bulb| bulb.group.as_ref().map(String::as_str) == Some(group);
+		manager::snapshot(self.bulbs.values().filter(in_group))
+	}
+
+	/// Queues power commands for every online bulb in a location (see
+	/// [Manager::set_location_power]).
+	pub fn set_location_power(
+		&self,
+		location: &LifxIdent,
+		on: bool,
+		duration: Duration,
+	) -> Result<()> {
+		for_each_in_location(self.bulbs.values(), location, |bulb| bulb.set_power(on, duration))
+	}
+
+	/// Queues color commands for every online bulb in a location (see
+	/// [Manager::set_location_color]).
+	pub fn set_location_color(
+		&self,
+		location: &LifxIdent,
+		color: HSBK,
+		duration: Duration,
+	) -> Result<()> {
+		for_each_in_location(self.bulbs.values(), location, |bulb| {
+			let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+			bulb.set_color(color, duration)
+		})
+	}
+
+	/// Queues `effect` for the bulb `target` (see [Manager::run_effect]).
+	pub fn run_effect(&self, target: MacAddress, effect: impl Into<Effect>) -> Result<()> {
+		let bulb = self.bulbs.get(&target).ok_or_else(|| anyhow!("unknown bulb {}", target))?;
+		effect.into().start(bulb)
+	}
+
+	/// Queues `effect` for every online bulb in a group (see [Manager::run_group_effect]).
+	pub fn run_group_effect(&self, group: &LifxIdent, effect: impl Into<Effect>) -> Result<()> {
+		let effect = effect.into();
+		for_each_in_group(self.bulbs.values(), group, |bulb| effect.start(bulb))
+	}
+
+	/// Saves what's known about every bulb to `path` (see [Manager::save_state]).
+	///
+	/// Requires the `serde` feature.
+	#[cfg(feature = "serde")]
+	pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+		SavedState::new(self.bulbs.values()).save(path)
+	}
+
+	/// Adds the bulbs saved by [AsyncManager::save_state], and probes them (see
+	/// [Manager::load_state]).  Returns how many bulbs were added.
+	///
+	/// Requires the `serde` feature.
+	#[cfg(feature = "serde")]
+	pub async fn load_state(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+		let state = SavedState::load(path)?;
+		let local = self.sock.local_addr()?;
+
+		let mut added = Vec::new();
+		for saved in &state.bulbs {
+			if !self.bulbs.contains_key(&saved.target) {
+				let mut bulb = saved.restore(self.source, self.queue.clone());
+				bulb.addr = discovery::reachable_from(&local, bulb.addr);
+				added.push(bulb.addr);
+				self.bulbs.insert(saved.target, bulb);
+			}
+		}
+
+		let policy = self.retry_policy;
+		let count = added.len();
+		self.send_repeatedly(Message::GetService, added, &policy).await?;
+		Ok(count)
+	}
+
+	/// Depth and drop/coalesce counters for the outgoing message queue.
+	pub fn queue_metrics(&self) -> QueueMetrics {
+		self.queue.metrics()
+	}
+
+	/// The limits on how fast [AsyncManager::flush] sends queued messages.
+	pub fn rate_limits(&self) -> RateLimits {
+		self.rate_limiter.limits()
+	}
+
+	pub fn set_rate_limits(&mut self, limits: RateLimits) {
+		self.rate_limiter.set_limits(limits);
+	}
+
+	fn addr_of(&self, target: MacAddress) -> Result<SocketAddr> {
+		self.bulbs
+			.get(&target)
+			.map(|bulb| bulb.addr)
+			.ok_or_else(|| anyhow!("unknown bulb {}", target))
+	}
+}
@@ -0,0 +1,169 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+
+#[cfg(feature = "tokio")]
+use crate::udp::AsyncManager;
+use crate::udp::{
+	AutoDiscovery,
+	InterfaceFilter,
+	Keepalive,
+	Manager,
+	RateLimits,
+	RefreshPolicies,
+	RetryPolicy,
+	Timeouts,
+	Transport,
+};
+
+/// Everything about how a [Manager] (or [AsyncManager]) behaves, as set up by [ManagerBuilder].
+///
+/// Most of these become the Manager's public fields of the same name, so they can still be
+/// changed later; the rest (like `addr` and `source`) are fixed once it's started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagerConfig {
+	/// The address to bind the socket to (see [Manager::bind]).
+	pub addr: SocketAddr,
+	/// The network interfaces that discovery broadcasts are sent on.
+	pub interfaces: InterfaceFilter,
+	/// The [Frame::source](crate::Frame::source) of every message, or `None` for a random one.
+	pub source: Option<u32>,
+	pub retry_policy: RetryPolicy,
+	pub timeouts: Timeouts,
+	pub keepalive: Option<Keepalive>,
+	pub refresh_policies: RefreshPolicies,
+	pub auto_discovery: Option<AutoDiscovery>,
+	pub rate_limits: RateLimits,
+}
+
+impl Default for ManagerConfig {
+	fn default() -> ManagerConfig {
+		ManagerConfig {
+			addr: SocketAddr::from(([0, 0, 0, 0], 56700)),
+			interfaces: InterfaceFilter::all(),
+			source: None,
+			retry_policy: RetryPolicy::default(),
+			timeouts: Timeouts::default(),
+			keepalive: None,
+			refresh_policies: RefreshPolicies::default(),
+			auto_discovery: Some(AutoDiscovery::default()),
+			rate_limits: RateLimits::default(),
+		}
+	}
+}
+
+/// Configures and starts a [Manager] (see [Manager::builder]).
+///
+/// Everything is applied before the first discovery broadcast goes out.
+///
+/// ```no_run
+/// use lifx_core::udp::{Keepalive, Manager};
+///
+/// let builder = Manager::builder().port(56701).keepalive(Keepalive::default());
+/// let mgr = builder.build().unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ManagerBuilder {
+	config: ManagerConfig,
+}
+
+impl ManagerBuilder {
+	/// A builder with the default configuration, the same one [Manager::new] uses.
+	pub fn new() -> ManagerBuilder {
+		ManagerBuilder::default()
+	}
+
+	/// A builder that starts from `config`, e.g. one loaded from a file.
+	pub fn from_config(config: ManagerConfig) -> ManagerBuilder {
+		ManagerBuilder { config }
+	}
+
+	/// The configuration so far.
+	pub fn config(&self) -> &ManagerConfig {
+		&self.config
+	}
+
+	pub fn addr(mut self, addr: SocketAddr) -> ManagerBuilder {
+		self.config.addr = addr;
+		self
+	}
+
+	/// Binds a different port, keeping the address.
+	pub fn port(mut self, port: u16) -> ManagerBuilder {
+		self.config.addr.set_port(port);
+		self
+	}
+
+	pub fn interfaces(mut self, interfaces: InterfaceFilter) -> ManagerBuilder {
+		self.config.interfaces = interfaces;
+		self
+	}
+
+	/// Uses `source` instead of a random one (see [Manager::bind_with_source]).
+	pub fn source(mut self, source: u32) -> ManagerBuilder {
+		self.config.source = Some(source);
+		self
+	}
+
+	pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ManagerBuilder {
+		self.config.retry_policy = retry_policy;
+		self
+	}
+
+	pub fn timeouts(mut self, timeouts: Timeouts) -> ManagerBuilder {
+		self.config.timeouts = timeouts;
+		self
+	}
+
+	pub fn keepalive(mut self, keepalive: Keepalive) -> ManagerBuilder {
+		self.config.keepalive = Some(keepalive);
+		self
+	}
+
+	pub fn refresh_policies(mut self, refresh_policies: RefreshPolicies) -> ManagerBuilder {
+		self.config.refresh_policies = refresh_policies;
+		self
+	}
+
+	/// Sets how discovery is repeated, or turns that off with `None`.
+	pub fn auto_discovery(mut self, auto_discovery: Option<AutoDiscovery>) -> ManagerBuilder {
+		self.config.auto_discovery = auto_discovery;
+		self
+	}
+
+	pub fn rate_limits(mut self, rate_limits: RateLimits) -> ManagerBuilder {
+		self.config.rate_limits = rate_limits;
+		self
+	}
+
+	/// Binds the socket and starts the Manager, with its background threads.
+	pub fn build(self) -> Result<Manager> {
+		let sock = Manager::bind_socket(self.config.addr)?;
+		Manager::start(sock, self.config)
+	}
+
+	/// Like [ManagerBuilder::build], but without background threads (see [Manager::bind_polled]).
+	pub fn build_polled(self) -> Result<Manager> {
+		let sock = Manager::bind_socket(self.config.addr)?;
+		Manager::start_polled(sock, self.config)
+	}
+
+	/// Like [ManagerBuilder::build], but talks through `transport` (see
+	/// [Manager::with_transport]).  The configured `addr` isn't used.
+	pub fn build_with_transport<T: Transport>(self, transport: T) -> Result<Manager<T>> {
+		Manager::start(transport, self.config)
+	}
+
+	/// Like [ManagerBuilder::build_with_transport], but without background threads.
+	pub fn build_polled_with_transport<T: Transport>(self, transport: T) -> Result<Manager<T>> {
+		Manager::start_polled(transport, self.config)
+	}
+
+	/// Binds the socket and starts an [AsyncManager] instead.
+	///
+	/// Requires the `tokio` feature.
+	#[cfg(feature = "tokio")]
+	pub async fn build_async(self) -> Result<AsyncManager> {
+		AsyncManager::start(self.config).await
+	}
+}
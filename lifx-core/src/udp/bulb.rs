@@ -1,16 +1,36 @@
 #![allow(dead_code)]
 
-use std::{net::{SocketAddr, UdpSocket}, thread, time::{Duration, Instant}};
-use anyhow::Result;
+use std::{
+	convert::TryFrom,
+	net::SocketAddr,
+	ops::RangeInclusive,
+	sync::atomic::{AtomicU8, Ordering},
+	time::{Duration, Instant},
+};
+use anyhow::{anyhow, Result};
 
 use crate::{
 	self as lifx,
+	ApplicationRequest,
 	BuildOptions,
+	EchoPayload,
 	HSBK,
+	Kelvin,
+	LastHevCycleResult,
+	LifxIdent,
+	LifxString,
+	MacAddress,
 	Message,
 	PowerLevel,
+	ProductInfo,
 	RawMessage,
-	udp::RefreshableData,
+	Tile,
+	time,
+	udp::{
+		EchoTracker, Health, Keepalive, Outgoing, Priority, RefreshPolicies, RefreshableData,
+		SendQueue, WaveformSpec, WifiSignal,
+	},
+	Waveform,
 };
 
 const HOUR: Duration = Duration::from_secs(60 * 60);
@@ -18,7 +38,7 @@ const HOUR: Duration = Duration::from_secs(60 * 60);
 pub struct Bulb {
 	pub last_seen: Instant,
 	pub source: u32,
-	pub target: u64,
+	pub target: MacAddress,
 	pub addr: SocketAddr,
 	pub model: RefreshableData<(u32, u32)>,
 	pub location: RefreshableData<String>,
@@ -26,9 +46,25 @@ pub struct Bulb {
 	pub name: RefreshableData<String>,
 	pub host_firmware: RefreshableData<u32>,
 	pub wifi_firmware: RefreshableData<u32>,
+	/// The Wi-Fi signal, in mW, as reported by [Message::StateWifiInfo]; see [Bulb::wifi_signal].
+	pub wifi_signal: RefreshableData<f32>,
+	pub runtime: RefreshableData<RuntimeInfo>,
 	pub power_level: RefreshableData<PowerLevel>,
+	/// The brightness of the infrared channel.  Only refreshed for models that have one.
+	pub infrared: RefreshableData<u16>,
+	/// The HEV (germicidal) cycle.  Only refreshed for models with a HEV light.
+	pub hev_cycle: RefreshableData<HevCycle>,
+	/// How the most recent HEV cycle ended.  Only refreshed for models with a HEV light.
+	pub last_hev_result: RefreshableData<LastHevCycleResult>,
 	pub color: Color,
-	sock: UdpSocket,
+	product_info: Option<&'static ProductInfo>,
+	location_id: Option<LifxIdent>,
+	group_id: Option<LifxIdent>,
+	echo: EchoTracker,
+	refresh_policies: RefreshPolicies,
+	sequence: AtomicU8,
+	online: bool,
+	queue: SendQueue,
 }
 
 #[derive(Debug)]
@@ -36,10 +72,146 @@ pub enum Color {
 	Unknown,
 	Single(RefreshableData<HSBK>),
 	Multi(RefreshableData<Vec<Option<HSBK>>>),
+	Tile(Box<TileState>),
+}
+
+/// An owned copy of what's known about a [Bulb], made by [Bulb::snapshot].
+///
+/// `power`, `color` and `zones` are `None` while the bulb is offline, as with [Bulb::power].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulbSnapshot {
+	pub target: MacAddress,
+	pub addr: SocketAddr,
+	pub online: bool,
+	pub last_seen: Instant,
+	pub label: Option<String>,
+	pub location: Option<String>,
+	pub group: Option<String>,
+	pub product: Option<&'static ProductInfo>,
+	pub host_firmware: Option<u32>,
+	pub wifi_firmware: Option<u32>,
+	pub wifi_signal: Option<WifiSignal>,
+	pub uptime: Option<Duration>,
+	pub power: Option<PowerLevel>,
+	pub color: Option<HSBK>,
+	pub zones: Option<Vec<Option<HSBK>>>,
+}
+
+/// How long a bulb has been powered on and off, from [Message::StateInfo].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeInfo {
+	/// The time since the bulb was powered on.
+	pub uptime: Duration,
+	/// How long the bulb was powered off before that.
+	pub last_downtime: Duration,
+}
+
+impl RuntimeInfo {
+	/// Whether `self` comes from a later power cycle than `earlier`, i.e. the bulb has been
+	/// power-cycled (or rebooted) in between.
+	pub fn restarted_since(&self, earlier: &RuntimeInfo) -> bool {
+		self.uptime < earlier.uptime || self.last_downtime != earlier.last_downtime
+	}
+}
+
+/// The state of a HEV (germicidal) cleaning cycle, from [Message::StateHevCycle].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HevCycle {
+	/// How long the current cycle lasts in total.  Zero if no cycle is running.
+	pub duration: Duration,
+	/// How long is left of the current cycle.
+	pub remaining: Duration,
+	/// Whether the device was powered on before the cycle started.
+	pub last_power: bool,
+}
+
+impl HevCycle {
+	pub fn is_running(&self) -> bool {
+		!self.duration.is_zero()
+	}
+}
+
+/// The cached state of a matrix device (such as a LIFX Tile): the tiles in its chain, and their
+/// pixels.
+#[derive(Debug)]
+pub struct TileState {
+	/// The tiles in the chain, in chain order, from [Message::StateDeviceChain].
+	pub chain: RefreshableData<Vec<Tile>>,
+	/// The 64 pixels of each tile in the chain, row by row, from [Message::State64].  Tiles that
+	/// haven't reported their pixels yet are `None`.
+	pub pixels: RefreshableData<Vec<Option<[HSBK; 64]>>>,
+}
+
+impl TileState {
+	fn new() -> TileState {
+		TileState {
+			chain: RefreshableData::empty(HOUR, Message::GetDeviceChain),
+			pixels: RefreshableData::empty(Duration::from_secs(15), get_64(0, 8)),
+		}
+	}
+
+	/// Records part of the chain, from a [Message::StateDeviceChain].
+	///
+	/// Returns whether anything changed.
+	pub(crate) fn update_chain(&mut self, start_index: u8, tiles: &[Tile]) -> bool {
+		let mut chain = self.chain.as_ref().cloned().unwrap_or_default();
+		chain.truncate(start_index as usize);
+		chain.resize(start_index as usize, Tile::default());
+		chain.extend_from_slice(tiles);
+
+		let len = chain.len();
+		let changed = self.chain.update(chain);
+		if let Some(pixels) = self.pixels.data.as_mut() {
+			pixels.resize(len, None);
+		}
+		changed
+	}
+
+	/// Records a rectangle of one tile's pixels, from a [Message::State64].
+	///
+	/// Returns whether anything changed.
+	pub(crate) fn update_pixels(
+		&mut self,
+		tile_index: u8,
+		x: u8,
+		y: u8,
+		width: u8,
+		colors: &[HSBK; 64],
+	) -> bool {
+		let len = self.chain.as_ref().map_or(0, Vec::len).max(tile_index as usize + 1);
+		let pixels = self.pixels.data.get_or_insert_with(Vec::new);
+		if pixels.len() < len {
+			pixels.resize(len, None);
+		}
+		let tile = pixels[tile_index as usize].get_or_insert([HSBK::default(); 64]);
+
+		let width = width.clamp(1, 8) as usize;
+		let mut changed = false;
+		for (i, color) in colors.iter().enumerate() {
+			let (px, py) = (x as usize + i % width, y as usize + i / width);
+			if px < 8 && py < 8 && tile[py * 8 + px] != *color {
+				tile[py * 8 + px] = *color;
+				changed = true;
+			}
+		}
+		self.pixels.touch(changed);
+		changed
+	}
+}
+
+fn get_64(length: u8, width: u8) -> Message {
+	Message::Get64 {
+		tile_index: 0,
+		length,
+		reserved: 0,
+		x: 0,
+		y: 0,
+		width,
+	}
 }
 
 impl Bulb {
-	pub fn new(source: u32, target: u64, sock: UdpSocket, addr: SocketAddr) -> Bulb {
+	pub fn new(source: u32, target: MacAddress, queue: SendQueue, addr: SocketAddr) -> Bulb {
 		Bulb {
 			last_seen: Instant::now(),
 			source,
@@ -51,71 +223,693 @@ impl Bulb {
 			name: RefreshableData::empty(HOUR, Message::GetLabel),
 			host_firmware: RefreshableData::empty(HOUR, Message::GetHostFirmware),
 			wifi_firmware: RefreshableData::empty(HOUR, Message::GetWifiFirmware),
+			wifi_signal: RefreshableData::empty(Duration::from_secs(60), Message::GetWifiInfo),
+			runtime: RefreshableData::empty(Duration::from_secs(60), Message::GetInfo),
 			power_level: RefreshableData::empty(Duration::from_secs(15), Message::GetPower),
+			infrared: RefreshableData::empty(Duration::from_secs(15), Message::LightGetInfrared),
+			hev_cycle: RefreshableData::empty(Duration::from_secs(15), Message::GetHevCycle),
+			last_hev_result: RefreshableData::empty(
+				Duration::from_secs(15),
+				Message::GetLastHevCycleResult,
+			),
 			color: Color::Unknown,
-			sock,
+			product_info: None,
+			location_id: None,
+			group_id: None,
+			echo: EchoTracker::default(),
+			refresh_policies: RefreshPolicies::default(),
+			sequence: AtomicU8::new(0),
+			online: true,
+			queue,
 		}
 	}
 
 	pub fn update(&mut self, addr: SocketAddr) {
 		self.last_seen = Instant::now();
 		self.addr = addr;
+		self.online = true;
+	}
+
+	/// False if the bulb has stopped replying (see [Timeouts::offline_after]), until it's heard
+	/// from again.
+	///
+	/// The cached state of an offline bulb is kept, but [Bulb::power] and the methods that adjust
+	/// the cached color refuse to use it.
+	///
+	/// [Timeouts::offline_after]: crate::udp::Timeouts::offline_after
+	pub fn is_online(&self) -> bool {
+		self.online
+	}
+
+	pub(crate) fn mark_offline(&mut self) {
+		self.online = false;
+	}
+
+	/// How the bulb has been answering the [Keepalive] echoes, judged by `keepalive`'s
+	/// thresholds.
+	pub fn health(&self, keepalive: &Keepalive) -> Health {
+		self.echo.health(keepalive)
+	}
+
+	/// Returns a [Message::EchoRequest] to send the bulb, if one is due.
+	pub(crate) fn start_echo(&mut self, keepalive: &Keepalive) -> Option<Message> {
+		let payload = self.echo.start(keepalive, Instant::now())?;
+		Some(Message::EchoRequest { payload })
+	}
+
+	/// Records the reply to an echo started with [Bulb::start_echo].
+	pub(crate) fn echo_received(&mut self, payload: &EchoPayload) {
+		self.echo.finish(payload, Instant::now());
+	}
+
+	/// The sequence number for the next message sent to this bulb, which its reply will carry too
+	/// (see [ResponseMeta](crate::ResponseMeta)).
+	///
+	/// Each bulb counts separately, wrapping after 255.  Everything the managers send to a single
+	/// bulb is numbered this way.
+	pub fn next_sequence(&self) -> u8 {
+		self.sequence.fetch_add(1, Ordering::Relaxed)
+	}
+
+	/// What this bulb's model supports, once it has reported its model and if the model is known.
+	pub fn product_info(&self) -> Option<&'static ProductInfo> {
+		self.product_info
+	}
+
+	/// Records the model from a [Message::StateVersion], looking up its [ProductInfo].
+	///
+	/// If the model is known, this also resets the cached color to the right kind (single,
+	/// multizone or matrix) for it.
+	pub(crate) fn set_model(&mut self, vendor: u32, product: u32) -> Option<&'static ProductInfo> {
+		self.model.update((vendor, product));
+		self.product_info = lifx::get_product_info(vendor, product);
+		if let Some(info) = self.product_info {
+			if info.matrix {
+				self.color = Color::Tile(Box::new(TileState::new()));
+			} else if info.multizone {
+				self.color = Color::Multi(RefreshableData::empty(
+					Duration::from_secs(15),
+					Message::GetColorZones {
+						start_index: 0,
+						end_index: 255,
+					},
+				))
+			} else {
+				self.color = Color::Single(RefreshableData::empty(
+					Duration::from_secs(15),
+					Message::LightGet,
+				))
+			}
+			self.apply_color_policies();
+		}
+		self.product_info
+	}
+
+	pub fn refresh_policies(&self) -> &RefreshPolicies {
+		&self.refresh_policies
+	}
+
+	/// Changes how often each piece of cached data is refreshed.
+	pub fn set_refresh_policies(&mut self, policies: RefreshPolicies) {
+		if self.refresh_policies == policies {
+			return;
+		}
+		self.refresh_policies = policies;
+		self.model.set_policy(policies.model);
+		self.location.set_policy(policies.location);
+		self.group.set_policy(policies.group);
+		self.name.set_policy(policies.name);
+		self.host_firmware.set_policy(policies.host_firmware);
+		self.wifi_firmware.set_policy(policies.wifi_firmware);
+		self.wifi_signal.set_policy(policies.wifi_signal);
+		self.runtime.set_policy(policies.runtime);
+		self.power_level.set_policy(policies.power);
+		self.infrared.set_policy(policies.infrared);
+		self.hev_cycle.set_policy(policies.hev_cycle);
+		self.last_hev_result.set_policy(policies.hev_cycle);
+		self.apply_color_policies();
+	}
+
+	fn apply_color_policies(&mut self) {
+		let policies = &self.refresh_policies;
+		match &mut self.color {
+			Color::Unknown => (),
+			Color::Single(d) => d.set_policy(policies.color),
+			Color::Multi(d) => d.set_policy(policies.color),
+			Color::Tile(tiles) => {
+				tiles.chain.set_policy(policies.model);
+				tiles.pixels.set_policy(policies.color);
+			}
+		}
+	}
+
+	/// The ID of the [Location](crate::udp::Location) this bulb belongs to, once it has reported
+	/// it.
+	///
+	/// Location labels aren't unique, so this is what identifies the location.
+	pub fn location_id(&self) -> Option<&LifxIdent> {
+		self.location_id.as_ref()
+	}
+
+	/// The ID of the [Group](crate::udp::Group) this bulb belongs to, once it has reported it.
+	pub fn group_id(&self) -> Option<&LifxIdent> {
+		self.group_id.as_ref()
+	}
+
+	/// Records the location from a [Message::StateLocation], returning whether it changed.
+	pub(crate) fn set_location(&mut self, id: LifxIdent, label: String) -> bool {
+		let label_changed = self.location.update(label);
+		let id_changed = self.location_id.as_ref() != Some(&id);
+		self.location_id = Some(id);
+		label_changed || id_changed
+	}
+
+	/// Records the group from a [Message::StateGroup], returning whether it changed.
+	pub(crate) fn set_group(&mut self, id: LifxIdent, label: String) -> bool {
+		let label_changed = self.group.update(label);
+		let id_changed = self.group_id.as_ref() != Some(&id);
+		self.group_id = Some(id);
+		label_changed || id_changed
 	}
 
-	pub fn query_for_missing_info(&self, sock: &UdpSocket) -> Result<()> {
-		self.refresh_if_needed(sock, &self.name)?;
-		self.refresh_if_needed(sock, &self.group)?;
-		self.refresh_if_needed(sock, &self.model)?;
-		self.refresh_if_needed(sock, &self.location)?;
-		self.refresh_if_needed(sock, &self.host_firmware)?;
-		self.refresh_if_needed(sock, &self.wifi_firmware)?;
-		self.refresh_if_needed(sock, &self.power_level)?;
-		match &self.color {
+	/// Whether the bulb understands the extended multizone messages, such as
+	/// [Message::SetExtendedColorZones].
+	///
+	/// This depends on both the model and the host firmware, so it's false until the bulb has
+	/// reported them.
+	pub fn supports_extended_multizone(&self) -> bool {
+		match (self.product_info, self.host_firmware.as_ref()) {
+			(Some(info), Some(&version)) => info.supports_extended_multizone(version),
+			_ => false,
+		}
+	}
+
+	/// Sends a refresh query for every piece of cached data that is missing or stale.
+	///
+	/// Queries that were sent less than `timeout` ago and are still unanswered are not repeated.
+	///
+	/// Queries are queued at [Priority::Background], behind any commands sent to the bulb.
+	pub fn query_for_missing_info(&mut self, timeout: Duration) -> Result<()> {
+		let refresher = Refresher {
+			queue: self.queue.clone(),
+			addr: self.addr,
+			target: self.target,
+			source: self.source,
+			sequence: &self.sequence,
+			timeout,
+		};
+
+		refresher.refresh(&mut self.name)?;
+		refresher.refresh(&mut self.group)?;
+		refresher.refresh(&mut self.model)?;
+		refresher.refresh(&mut self.location)?;
+		refresher.refresh(&mut self.host_firmware)?;
+		refresher.refresh(&mut self.wifi_firmware)?;
+		refresher.refresh(&mut self.wifi_signal)?;
+		refresher.refresh(&mut self.runtime)?;
+		refresher.refresh(&mut self.power_level)?;
+		if matches!(self.product_info, Some(info) if info.infrared) {
+			refresher.refresh(&mut self.infrared)?;
+		}
+		if matches!(self.product_info, Some(info) if info.hev) {
+			refresher.refresh(&mut self.hev_cycle)?;
+			refresher.refresh(&mut self.last_hev_result)?;
+		}
+		let extended = self.supports_extended_multizone();
+		match &mut self.color {
 			Color::Unknown => (), // we'll need to wait to get info about this bulb's model, so we'll know if it's multizone or not
-			Color::Single(d) => self.refresh_if_needed(sock, d)?,
-			Color::Multi(d) => self.refresh_if_needed(sock, d)?,
+			Color::Single(d) => refresher.refresh(d)?,
+			Color::Multi(d) => {
+				d.refresh_msg = if extended {
+					Message::GetExtendedColorZones
+				} else {
+					Message::GetColorZones {
+						start_index: 0,
+						end_index: 255,
+					}
+				};
+				refresher.refresh(d)?
+			}
+			Color::Tile(tiles) => {
+				refresher.refresh(&mut tiles.chain)?;
+				// the pixels can only be asked for once we know how many tiles there are
+				if let Some(chain) = tiles.chain.as_ref() {
+					let width = chain.first().map_or(8, |tile| tile.width);
+					tiles.pixels.refresh_msg = get_64(chain.len() as u8, width);
+					refresher.refresh(&mut tiles.pixels)?;
+				}
+			}
 		}
 
 		Ok(())
 	}
 
 	pub fn set_color(&self, color: HSBK, duration: Duration) -> Result<()> {
-		let options = BuildOptions {
-			target: Some(self.target),
-			res_required: true,
-			source: self.source,
-			..Default::default()
-		};
-		let message = RawMessage::build(&options, Message::LightSetColor {
-			color,
-			duration: duration.as_millis() as u32,
-			reserved: 0,
-		})?.pack()?;
+		self.send(Message::light_set_color(color, duration))
+	}
+
+	/// The cached power state, if the bulb has reported it yet and is still online.
+	pub fn power(&self) -> Option<PowerLevel> {
+		self.power_level.as_ref().copied().filter(|_| self.online)
+	}
+
+	/// The bulb's label, if it has reported it yet.
+	pub fn label(&self) -> Option<&str> {
+		self.name.as_ref().map(String::as_str)
+	}
+
+	/// The cached color of a single-color bulb, if it has reported it yet and is still online.
+	///
+	/// Multizone devices have no single color; see [Bulb::zones].
+	pub fn color(&self) -> Option<HSBK> {
+		match self.color {
+			Color::Single(ref d) => d.as_ref().copied().filter(|_| self.online),
+			_ => None,
+		}
+	}
+
+	/// The cached zone colors of a multizone device, if it has reported any yet and is still
+	/// online.  Zones that haven't been reported yet are `None`.
+	pub fn zones(&self) -> Option<&[Option<HSBK>]> {
+		match self.color {
+			Color::Multi(ref d) => d.as_ref().map(Vec::as_slice).filter(|_| self.online),
+			_ => None,
+		}
+	}
+
+	/// How good the bulb's Wi-Fi reception is, if it has reported it yet.
+	pub fn wifi_signal(&self) -> Option<WifiSignal> {
+		self.wifi_signal.as_ref().map(|&signal| WifiSignal::from_signal(signal))
+	}
+
+	/// How long the bulb has been powered on, as of its last [Message::StateInfo].
+	pub fn uptime(&self) -> Option<Duration> {
+		self.runtime.as_ref().map(|info| info.uptime)
+	}
 
-		let sock = self.sock.try_clone()?;
-		let addr = self.addr;
+	/// How long the bulb was powered off before it was last turned on (at mains, not with
+	/// [Bulb::set_power]), to within 5 seconds.
+	pub fn last_downtime(&self) -> Option<Duration> {
+		self.runtime.as_ref().map(|info| info.last_downtime)
+	}
+
+	/// Copies out what's currently known about the bulb, e.g. to hand to a UI thread.
+	pub fn snapshot(&self) -> BulbSnapshot {
+		BulbSnapshot {
+			target: self.target,
+			addr: self.addr,
+			online: self.online,
+			last_seen: self.last_seen,
+			label: self.label().map(str::to_owned),
+			location: self.location.as_ref().cloned(),
+			group: self.group.as_ref().cloned(),
+			product: self.product_info,
+			host_firmware: self.host_firmware.as_ref().copied(),
+			wifi_firmware: self.wifi_firmware.as_ref().copied(),
+			wifi_signal: self.wifi_signal(),
+			uptime: self.uptime(),
+			power: self.power(),
+			color: self.color(),
+			zones: self.zones().map(<[_]>::to_vec),
+		}
+	}
+
+	/// Turns the bulb on or off.
+	///
+	/// Instant changes use the device-level [Message::SetPower], which every LIFX device
+	/// understands, while fading over `duration` needs the light-level [Message::LightSetPower].
+	/// The replies to both are folded into the same cached power state by the [Manager].
+	///
+	/// [Manager]: crate::udp::Manager
+	pub fn set_power(&self, on: bool, duration: Duration) -> Result<()> {
+		self.send(Self::power_message(on, duration))
+	}
+
+	/// Changes the brightness by `delta` (a fraction of full brightness, e.g. `-0.1` to dim by
+	/// 10%), keeping the bulb's current hue, saturation and kelvin.
+	///
+	/// The result is clamped to `[0.0, 1.0]`.  This relies on the cached color, so it will fail
+	/// until the bulb has reported its state at least once.
+	pub fn adjust_brightness(&self, delta: f32, duration: Duration) -> Result<()> {
+		let current = self.cached_color()?;
+		let brightness = current.brightness as f32 / u16::MAX as f32 + delta;
+		self.dim_to(brightness, duration)
+	}
+
+	/// Sets the brightness to an absolute level in `[0.0, 1.0]`, keeping the bulb's current hue,
+	/// saturation and kelvin.
+	///
+	/// This relies on the cached color, like [Bulb::adjust_brightness].  The bulb's power isn't
+	/// touched; see [Bulb::dim_to] for a version that turns it on too.
+	pub fn set_brightness(&self, brightness: f32, duration: Duration) -> Result<()> {
+		let current = self.cached_color()?;
+		let brightness = (brightness.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+		self.set_color(HSBK { brightness, ..current }, duration)
+	}
 
-		thread::spawn(move || {
-			sock.send_to(&message, addr).unwrap();
-		});
+	/// Sets the brightness to an absolute level in `[0.0, 1.0]`, keeping the bulb's current hue,
+	/// saturation and kelvin.
+	///
+	/// If the bulb is powered off and the new brightness is non-zero, it is powered on as well.
+	pub fn dim_to(&self, brightness: f32, duration: Duration) -> Result<()> {
+		self.set_brightness(brightness, duration)?;
+		if brightness > 0.0 && self.power() == Some(PowerLevel::Standby) {
+			self.set_power(true, duration)?;
+		}
 
 		Ok(())
 	}
 
-	fn refresh_if_needed<T>(
+	/// Sets the zones in `zones` (inclusive) of a multizone device to `color`.
+	pub fn set_zones(
 		&self,
-		sock: &UdpSocket,
-		data: &RefreshableData<T>,
+		zones: RangeInclusive<u8>,
+		color: HSBK,
+		duration: Duration,
 	) -> Result<()> {
-		if data.needs_refresh() {
+		self.check_multizone()?;
+		self.send(Message::SetColorZones {
+			start_index: *zones.start(),
+			end_index: *zones.end(),
+			color,
+			duration: time::to_millis(duration),
+			apply: ApplicationRequest::Apply,
+		})
+	}
+
+	/// Sets every zone of a multizone device at once, starting from zone 0.
+	///
+	/// Devices that [support extended multizone](Bulb::supports_extended_multizone) are sent one
+	/// [Message::SetExtendedColorZones] per 82 zones.  Otherwise each run of identical colors takes
+	/// one [Message::SetColorZones].  Either way, all but the last message are sent with
+	/// [ApplicationRequest::NoApply], so the device shows the new colors together instead of a
+	/// batch at a time.
+	pub fn set_zone_colors(&self, colors: &[HSBK], duration: Duration) -> Result<()> {
+		self.check_multizone()?;
+		if self.supports_extended_multizone() {
+			return self.set_extended_zone_colors(colors, duration);
+		}
+		if colors.len() > 256 {
+			return Err(anyhow!("{} zones given, but devices have at most 256", colors.len()));
+		}
+
+		let mut runs = Vec::new();
+		for (index, color) in colors.iter().enumerate() {
+			match runs.last_mut() {
+				Some((_, end, last)) if last == color => *end = index as u8,
+				_ => runs.push((index as u8, index as u8, *color)),
+			}
+		}
+		let last = runs.len().saturating_sub(1);
+		for (i, (start_index, end_index, color)) in runs.into_iter().enumerate() {
+			self.send(Message::SetColorZones {
+				start_index,
+				end_index,
+				color,
+				duration: time::to_millis(duration),
+				apply: if i == last {
+					ApplicationRequest::Apply
+				} else {
+					ApplicationRequest::NoApply
+				},
+			})?;
+		}
+
+		Ok(())
+	}
+
+	/// Sets the pixels of one tile on a matrix device, row by row.
+	///
+	/// Tiles narrower than 8 pixels (as reported in the chain) only use the first
+	/// `width * height` colors.
+	pub fn set_tile(&self, tile_index: u8, colors: &[HSBK; 64], duration: Duration) -> Result<()> {
+		let tiles = match self.color {
+			Color::Tile(ref tiles) => Some(tiles),
+			_ => None,
+		};
+		if tiles.is_none() && self.product_info.is_some() {
+			return Err(anyhow!("bulb {} isn't a matrix device", self.target));
+		}
+		let width = tiles
+			.and_then(|tiles| tiles.chain.as_ref())
+			.and_then(|chain| chain.get(tile_index as usize))
+			.map_or(8, |tile| tile.width);
+
+		self.send(Message::Set64 {
+			tile_index,
+			length: 1,
+			reserved: 0,
+			x: 0,
+			y: 0,
+			width,
+			duration: time::to_millis(duration),
+			colors: *colors,
+		})
+	}
+
+	/// Sets the pixels of every tile on a matrix device, starting from the first tile in the
+	/// chain; see [Bulb::set_tile].
+	pub fn set_tiles(&self, tiles: &[[HSBK; 64]], duration: Duration) -> Result<()> {
+		if tiles.len() > 16 {
+			return Err(anyhow!("{} tiles given, but chains have at most 16", tiles.len()));
+		}
+		for (tile_index, colors) in tiles.iter().enumerate() {
+			self.set_tile(tile_index as u8, colors, duration)?;
+		}
+		Ok(())
+	}
+
+	/// Starts a waveform effect, such as a pulse or a breathe; see [WaveformSpec].
+	pub fn set_waveform(&self, spec: WaveformSpec) -> Result<()> {
+		self.send(spec.to_message())
+	}
+
+	/// Sets the brightness of the infrared channel, from 0 (off) to 65535.
+	///
+	/// Fails if the bulb is known not to have an infrared channel.
+	pub fn set_infrared(&self, brightness: u16) -> Result<()> {
+		if let Some(info) = self.product_info.filter(|info| !info.infrared) {
+			return Err(anyhow!("bulb {} ({}) has no infrared channel", self.target, info.name));
+		}
+		self.send(Message::LightSetInfrared { brightness })
+	}
+
+	/// Starts a HEV (germicidal) cleaning cycle lasting `duration`, to the nearest second.
+	///
+	/// A zero `duration` uses the device's default (see [Message::SetHevCycleConfiguration]).
+	/// Fails if the bulb is known not to have a HEV light.
+	pub fn start_clean_cycle(&self, duration: Duration) -> Result<()> {
+		self.check_hev()?;
+		self.send(Message::SetHevCycle {
+			enable: true,
+			duration: u32::try_from(duration.as_secs()).unwrap_or(u32::MAX),
+		})
+	}
+
+	/// Stops the HEV cleaning cycle, if one is running.
+	pub fn stop_clean_cycle(&self) -> Result<()> {
+		self.check_hev()?;
+		self.send(Message::SetHevCycle {
+			enable: false,
+			duration: 0,
+		})
+	}
+
+	/// Renames the bulb.
+	///
+	/// Labels are at most 32 bytes long; longer ones are rejected rather than truncated.
+	pub fn set_label(&self, label: &str) -> Result<()> {
+		let label = LifxString::try_from(label)?;
+		self.send(Message::SetLabel { label })
+	}
+
+	/// Switches the bulb to white at the given color temperature, keeping its current brightness.
+	///
+	/// Saturation is zeroed, which is what the LIFX app does when switching to the "Whites" tab.
+	pub fn to_white(&self, kelvin: u16, duration: Duration) -> Result<()> {
+		let current = self.cached_color()?;
+		self.set_color(
+			HSBK {
+				saturation: 0,
+				kelvin,
+				..current
+			},
+			duration,
+		)
+	}
+
+	/// Switches the bulb to a color, keeping its current brightness (and kelvin, which is ignored
+	/// while displaying colors).
+	///
+	/// `hue` is in degrees and `saturation` is in `[0.0, 1.0]`, as in [HSBK::color].
+	pub fn to_color(&self, hue: u16, saturation: f32, duration: Duration) -> Result<()> {
+		let current = self.cached_color()?;
+		let target = HSBK::color(hue, saturation.clamp(0.0, 1.0), 0.0);
+		self.set_color(
+			HSBK {
+				hue: target.hue,
+				saturation: target.saturation,
+				..current
+			},
+			duration,
+		)
+	}
+
+	/// Flashes the bulb on and off for roughly `duration` so it can be picked out in a room full
+	/// of identically-named bulbs, then puts it back the way it was.
+	///
+	/// The flashing is a transient waveform, so the bulb goes back to its own color by itself once
+	/// it's over.  A bulb that's powered off is switched on for it, and faded back off over the
+	/// same `duration`, so the flashes dim as it goes but it ends up off again without anything
+	/// having to be sent later.
+	pub fn identify(&self, duration: Duration) -> Result<()> {
+		const PERIOD: Duration = Duration::from_millis(500);
+
+		let before = self.snapshot();
+		let was_off = before.power == Some(PowerLevel::Standby);
+		if was_off {
+			self.set_power(true, Duration::from_millis(0))?;
+		}
+
+		let dark = HSBK {
+			brightness: 0,
+			..before.color.unwrap_or_else(|| HSBK::white(Kelvin::Neutral, 1.0))
+		};
+		self.send(Message::set_waveform(
+			true,
+			dark,
+			PERIOD,
+			(duration.as_secs_f32() / PERIOD.as_secs_f32()).max(1.0),
+			0,
+			Waveform::Pulse,
+		))?;
+
+		if was_off {
+			self.send(Message::light_set_power(PowerLevel::Standby, duration))?;
+		}
+		Ok(())
+	}
+
+	/// The message [Bulb::set_power] sends.
+	pub(crate) fn power_message(on: bool, duration: Duration) -> Message {
+		let level = if on { PowerLevel::Enabled } else { PowerLevel::Standby };
+		if duration.as_millis() == 0 {
+			Message::SetPower { level }
+		} else {
+			Message::light_set_power(level, duration)
+		}
+	}
+
+	fn cached_color(&self) -> Result<HSBK> {
+		if !self.online {
+			return Err(anyhow!("bulb {} is offline", self.target));
+		}
+		match self.color {
+			Color::Single(ref d) => d.as_ref().copied(),
+			_ => None,
+		}
+		.ok_or_else(|| anyhow!("no cached color for bulb {}", self.target))
+	}
+
+	fn set_extended_zone_colors(&self, colors: &[HSBK], duration: Duration) -> Result<()> {
+		const BATCH: usize = 82;
+
+		if colors.len() > u16::MAX as usize {
+			return Err(anyhow!("{} zones given, but devices have at most 65535", colors.len()));
+		}
+		let batches = colors.len().div_ceil(BATCH);
+		for (i, chunk) in colors.chunks(BATCH).enumerate() {
+			let mut batch = [HSBK::default(); BATCH];
+			batch[..chunk.len()].copy_from_slice(chunk);
+			self.send(Message::SetExtendedColorZones {
+				duration: time::to_millis(duration),
+				apply: if i + 1 == batches {
+					ApplicationRequest::Apply
+				} else {
+					ApplicationRequest::NoApply
+				},
+				zone_index: (i * BATCH) as u16,
+				colors_count: chunk.len() as u8,
+				colors: batch,
+			})?;
+		}
+
+		Ok(())
+	}
+
+	fn check_hev(&self) -> Result<()> {
+		match self.product_info {
+			Some(info) if !info.hev => {
+				Err(anyhow!("bulb {} ({}) has no HEV light", self.target, info.name))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	/// Fails if the bulb is known not to be a multizone device.  Bulbs whose model hasn't been
+	/// reported yet are given the benefit of the doubt.
+	fn check_multizone(&self) -> Result<()> {
+		match self.product_info {
+			Some(info) if !info.multizone => {
+				Err(anyhow!("bulb {} ({}) has no zones", self.target, info.name))
+			}
+			_ => Ok(()),
+		}
+	}
+
+	fn outgoing(&self, message: Message) -> Result<Outgoing> {
+		Ok(Outgoing {
+			target: self.target,
+			addr: self.addr,
+			typ: message.get_num(),
+			bytes: self.queue.pack(&RawMessage::build(&self.refresh_options(), message)?)?,
+		})
+	}
+
+	fn send(&self, message: Message) -> Result<()> {
+		self.queue.push(Priority::Interactive, self.outgoing(message)?);
+		Ok(())
+	}
+
+	fn refresh_options(&self) -> BuildOptions {
+		BuildOptions {
+			target: Some(self.target),
+			res_required: true,
+			sequence: self.next_sequence(),
+			source: self.source,
+			..Default::default()
+		}
+	}
+}
+
+/// Queues the refresh queries for one bulb (see [Bulb::query_for_missing_info]).
+struct Refresher<'a> {
+	queue: SendQueue,
+	addr: SocketAddr,
+	target: MacAddress,
+	source: u32,
+	sequence: &'a AtomicU8,
+	timeout: Duration,
+}
+
+impl Refresher<'_> {
+	fn refresh<T>(&self, data: &mut RefreshableData<T>) -> Result<()> {
+		if data.needs_refresh() && !data.awaiting_reply(self.timeout) {
 			let options = BuildOptions {
 				target: Some(self.target),
 				res_required: true,
+				sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
 				source: self.source,
 				..Default::default()
 			};
 			let message = RawMessage::build(&options, data.refresh_msg.clone())?;
-			sock.send_to(&message.pack()?, self.addr)?;
+			self.queue.push(Priority::Background, Outgoing {
+				target: self.target,
+				addr: self.addr,
+				typ: message.protocol_header.typ,
+				bytes: self.queue.pack(&message)?,
+			});
+			data.mark_requested();
 		}
 		Ok(())
 	}
@@ -123,7 +917,10 @@ impl Bulb {
 
 impl std::fmt::Debug for Bulb {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{:0>16X}  {:^21}  ", self.target, self.addr)?;
+		write!(f, "{}  {:^21}  ", self.target, self.addr)?;
+		if !self.online {
+			write!(f, "(offline)  ")?;
+		}
 
 		if let Some(group) = self.group.as_ref() {
 			write!(f, "{:<7} / ", group)?;
@@ -132,7 +929,7 @@ impl std::fmt::Debug for Bulb {
 			write!(f, "{:<15}", name)?;
 		}
 		if let Some((vendor, product)) = self.model.as_ref() {
-			if let Some(info) = lifx::get_product_info(*vendor, *product) {
+			if let Some(info) = self.product_info {
 				write!(f, "  {:<11} ", info.name)?;
 			} else {
 				write!(
@@ -161,6 +958,11 @@ impl std::fmt::Debug for Bulb {
 								.unwrap_or_else(|| "??".to_owned()),
 						)?;
 					}
+					Color::Tile(ref tiles) => {
+						if let Some(chain) = tiles.chain.as_ref() {
+							write!(f, "Tiles: {}", chain.len())?;
+						}
+					}
 					Color::Multi(ref color) => {
 						if let Some(vec) = color.as_ref() {
 							write!(f, "Zones: ")?;
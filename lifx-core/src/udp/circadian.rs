@@ -0,0 +1,357 @@
+//! Circadian lighting: white light that follows the time of day, cool and bright around noon and
+//! warm and dim at night.
+//!
+//! A [Circadian] runs as an [Animation] (see [Manager::animate]), adjusting its bulbs every so
+//! often from a [DaySchedule].  Bulbs can be held at another color for a while through a
+//! [CircadianControl], and a bulb that someone changes by hand (from the LIFX app, or a switch) is
+//! left alone for a while rather than being changed straight back.
+//!
+//! ```no_run
+//! use lifx_core::{udp::{Circadian, Manager}, MacAddress};
+//!
+//! let mgr = Manager::new().unwrap();
+//! let circadian = Circadian::sun(51.48, 0.0).target(MacAddress([0xd0, 0x73, 0xd5, 1, 2, 3]));
+//! mgr.animate(circadian);
+//! ```
+//!
+//! [Manager::animate]: crate::udp::Manager::animate
+
+use std::{
+	collections::HashMap,
+	f64::consts::PI,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+
+use crate::{
+	udp::{bulb::Color, Animation, Bulb, Easing},
+	LifxIdent,
+	MacAddress,
+	HSBK,
+};
+
+/// How much daylight there is at any time, from `0.0` (night) to `1.0` (day).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaySchedule {
+	/// Follows the sun at a location (in degrees, north and east positive): night once the sun
+	/// is 6° below the horizon, and full daylight once it's 15° above it.
+	Sun { latitude: f64, longitude: f64 },
+	/// The same times every day, regardless of the season.
+	///
+	/// Each point is a time since midnight and the daylight at that time, with a straight line
+	/// between them (wrapping around at midnight).  Midnight is local to `utc_offset`, in seconds
+	/// east of UTC.
+	Fixed {
+		points: Vec<(Duration, f32)>,
+		utc_offset: i32,
+	},
+}
+
+impl DaySchedule {
+	/// The daylight at `time`.
+	pub fn daylight_at(&self, time: SystemTime) -> f32 {
+		let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+		match self {
+			DaySchedule::Sun {
+				latitude,
+				longitude,
+			} => {
+				let elevation = sun_elevation(*latitude, *longitude, secs);
+				let t = (elevation - NIGHT_ELEVATION) / (DAY_ELEVATION - NIGHT_ELEVATION);
+				Easing::EaseInOut.apply(t as f32)
+			}
+			DaySchedule::Fixed { points, utc_offset } => {
+				let day = DAY.as_secs_f64();
+				let local = (secs + *utc_offset as f64).rem_euclid(day);
+				fixed_daylight(points, local, day)
+			}
+		}
+	}
+}
+
+const NIGHT_ELEVATION: f64 = -6.0;
+const DAY_ELEVATION: f64 = 15.0;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The sun's angle above the horizon in degrees, `secs` after the Unix epoch.
+///
+/// This is the low-precision formula from the Astronomical Almanac, which is good to about a
+/// degree: plenty for dimming lights.
+fn sun_elevation(latitude: f64, longitude: f64, secs: f64) -> f64 {
+	let d = secs / 86400.0 - 10957.5; // days since 2000-01-01 12:00 UTC
+	let mean_longitude = 280.460 + 0.9856474 * d;
+	let anomaly = (357.528 + 0.9856003 * d).to_radians();
+	let ecliptic =
+		(mean_longitude + 1.915 * anomaly.sin() + 0.020 * (2.0 * anomaly).sin()).to_radians();
+	let obliquity = (23.439 - 0.0000004 * d).to_radians();
+
+	let declination = (obliquity.sin() * ecliptic.sin()).asin();
+	let right_ascension = (obliquity.cos() * ecliptic.sin()).atan2(ecliptic.cos());
+	let sidereal = (280.46061837 + 360.98564736629 * d + longitude).to_radians();
+	let hour_angle = (sidereal - right_ascension).rem_euclid(2.0 * PI);
+
+	let latitude = latitude.to_radians();
+	let elevation = latitude.sin() * declination.sin()
+		+ latitude.cos() * declination.cos() * hour_angle.cos();
+	elevation.asin().to_degrees()
+}
+
+/// The daylight `local` seconds after midnight, between `points`.
+fn fixed_daylight(points: &[(Duration, f32)], local: f64, day: f64) -> f32 {
+	let mut points: Vec<(f64, f32)> =
+		points.iter().map(|&(at, level)| (at.as_secs_f64().rem_euclid(day), level)).collect();
+	points.sort_by(|a, b| a.0.total_cmp(&b.0));
+	let (first, last) = match (points.first(), points.last()) {
+		(Some(&first), Some(&last)) => (first, last),
+		_ => return 1.0,
+	};
+
+	// the points before and after `local`, wrapping around midnight
+	let before = points.iter().rev().find(|p| p.0 <= local).copied();
+	let after = points.iter().find(|p| p.0 > local).copied();
+	let (before, after) = match (before, after) {
+		(Some(before), Some(after)) => (before, after),
+		(Some(before), None) => (before, (first.0 + day, first.1)),
+		(None, Some(after)) => ((last.0 - day, last.1), after),
+		(None, None) => unreachable!(),
+	};
+	let t = match after.0 - before.0 {
+		span if span > 0.0 => ((local - before.0) / span) as f32,
+		_ => 0.0,
+	};
+	(before.1 + (after.1 - before.1) * t).clamp(0.0, 1.0)
+}
+
+/// Shares a [Circadian]'s overrides and manual holds with the application, after the Circadian
+/// itself has been handed to the Manager.
+#[derive(Debug, Clone, Default)]
+pub struct CircadianControl {
+	holds: Arc<Mutex<HashMap<MacAddress, Hold>>>,
+}
+
+/// Why a bulb isn't following a [Circadian]'s schedule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hold {
+	/// It's being held at this color, until the given time (if any).
+	Override(HSBK, Option<Instant>),
+	/// Someone changed it by hand, so it's being left alone until the given time (if any).
+	Manual(Option<Instant>),
+}
+
+impl CircadianControl {
+	/// Holds `target` at `color` instead of following the schedule, until `until` or until
+	/// [CircadianControl::release] if that's `None`.
+	pub fn hold(&self, target: MacAddress, color: HSBK, until: Option<Instant>) {
+		self.holds.lock().unwrap().insert(target, Hold::Override(color, until));
+	}
+
+	/// Has `target` follow the schedule again, whether it was held or changed by hand.
+	pub fn release(&self, target: MacAddress) {
+		self.holds.lock().unwrap().remove(&target);
+	}
+
+	/// Why `target` isn't following the schedule, if it isn't.
+	pub fn hold_on(&self, target: MacAddress) -> Option<Hold> {
+		self.holds.lock().unwrap().get(&target).copied()
+	}
+}
+
+/// Keeps bulbs on a white that follows the time of day (see the [module docs](self)).
+#[derive(Debug, Clone)]
+pub struct Circadian {
+	schedule: DaySchedule,
+	targets: Vec<MacAddress>,
+	groups: Vec<LifxIdent>,
+	day: HSBK,
+	night: HSBK,
+	interval: Duration,
+	manual_hold: Option<Duration>,
+	control: CircadianControl,
+	sent: HashMap<MacAddress, Sent>,
+}
+
+/// The color a [Circadian] last sent a bulb, and when the bulb should have got there.
+#[derive(Debug, Clone, Copy)]
+struct Sent {
+	color: HSBK,
+	settled: Instant,
+}
+
+impl Circadian {
+	/// Follows `schedule` on no bulbs yet.
+	///
+	/// By default it moves between a bright 5500K in the day and a dim 2200K at night, updates the
+	/// bulbs every minute, and leaves a bulb changed by hand alone for an hour.
+	pub fn new(schedule: DaySchedule) -> Circadian {
+		Circadian {
+			schedule,
+			targets: Vec::new(),
+			groups: Vec::new(),
+			day: HSBK {
+				hue: 0,
+				saturation: 0,
+				brightness: 65535,
+				kelvin: 5500,
+			},
+			night: HSBK {
+				hue: 0,
+				saturation: 0,
+				brightness: 6554,
+				kelvin: 2200,
+			},
+			interval: Duration::from_secs(60),
+			manual_hold: Some(Duration::from_secs(60 * 60)),
+			control: CircadianControl::default(),
+			sent: HashMap::new(),
+		}
+	}
+
+	/// Follows the sun at a location (see [DaySchedule::Sun]).
+	pub fn sun(latitude: f64, longitude: f64) -> Circadian {
+		Circadian::new(DaySchedule::Sun {
+			latitude,
+			longitude,
+		})
+	}
+
+	/// Follows a fixed schedule (see [DaySchedule::Fixed]).
+	pub fn fixed(points: Vec<(Duration, f32)>, utc_offset: i32) -> Circadian {
+		Circadian::new(DaySchedule::Fixed { points, utc_offset })
+	}
+
+	/// Also adjusts the bulb `target`.
+	pub fn target(mut self, target: MacAddress) -> Circadian {
+		self.targets.push(target);
+		self
+	}
+
+	/// Also adjusts each of `targets`.
+	pub fn targets(mut self, targets: impl IntoIterator<Item = MacAddress>) -> Circadian {
+		self.targets.extend(targets);
+		self
+	}
+
+	/// Also adjusts every bulb in the group with ID `group`, including ones that join it later.
+	pub fn group(mut self, group: LifxIdent) -> Circadian {
+		self.groups.push(group);
+		self
+	}
+
+	/// The color in full daylight.
+	pub fn day(mut self, day: HSBK) -> Circadian {
+		self.day = day;
+		self
+	}
+
+	/// The color at night.
+	pub fn night(mut self, night: HSBK) -> Circadian {
+		self.night = night;
+		self
+	}
+
+	/// How often the bulbs are updated; each update fades over this long.
+	pub fn interval(mut self, interval: Duration) -> Circadian {
+		self.interval = interval.max(Duration::from_secs(1));
+		self
+	}
+
+	/// How long to leave a bulb alone after it's changed by hand, or `None` to leave it until
+	/// [CircadianControl::release].
+	pub fn manual_hold(mut self, manual_hold: Option<Duration>) -> Circadian {
+		self.manual_hold = manual_hold;
+		self
+	}
+
+	/// A handle for overriding bulbs once the Circadian is running.
+	pub fn control(&self) -> CircadianControl {
+		self.control.clone()
+	}
+
+	/// The color the schedule calls for at `time`.
+	pub fn color_at(&self, time: SystemTime) -> HSBK {
+		self.night.lerp(self.day, self.schedule.daylight_at(time))
+	}
+
+	fn is_selected(&self, bulb: &Bulb) -> bool {
+		self.targets.contains(&bulb.target)
+			|| bulb.group_id().is_some_and(|group| self.groups.contains(group))
+	}
+
+	/// Updates one bulb to `color`, unless it's held.
+	fn update(
+		&mut self,
+		bulb: &Bulb,
+		holds: &mut HashMap<MacAddress, Hold>,
+		color: HSBK,
+		now: Instant,
+	) -> Result<()> {
+		let color = match holds.get(&bulb.target).copied() {
+			Some(Hold::Override(_, Some(until)) | Hold::Manual(Some(until))) if until <= now => {
+				holds.remove(&bulb.target);
+				color
+			}
+			Some(Hold::Override(color, _)) => color,
+			Some(Hold::Manual(_)) => return Ok(()),
+			None if self.changed_by_hand(bulb) => {
+				self.sent.remove(&bulb.target);
+				let until = self.manual_hold.map(|hold| now + hold);
+				holds.insert(bulb.target, Hold::Manual(until));
+				return Ok(());
+			}
+			None => color,
+		};
+		let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+
+		let last = self.sent.get(&bulb.target);
+		if last.is_some_and(|last| last.color == color) {
+			return Ok(());
+		}
+		let fade = match last {
+			Some(_) => self.interval,
+			None => Duration::from_secs(1),
+		};
+		bulb.set_color(color, fade)?;
+		self.sent.insert(bulb.target, Sent { color, settled: now + fade });
+		Ok(())
+	}
+
+	/// Whether `bulb` has reported a different color from the one it was last sent, after it
+	/// should have finished fading to it.
+	fn changed_by_hand(&self, bulb: &Bulb) -> bool {
+		let (sent, reported) = match (self.sent.get(&bulb.target), &bulb.color) {
+			(Some(sent), Color::Single(reported)) => (sent, reported),
+			_ => return false,
+		};
+		let close = |a: u16, b: u16, tolerance: u16| a.abs_diff(b) <= tolerance;
+		match reported.as_ref() {
+			Some(color) if reported.last_updated() > sent.settled => {
+				!(close(color.kelvin, sent.color.kelvin, 50)
+					&& close(color.brightness, sent.color.brightness, 656)
+					&& close(color.saturation, sent.color.saturation, 656)
+					&& (sent.color.saturation == 0
+						|| close(color.hue, sent.color.hue, 656)))
+			}
+			_ => false,
+		}
+	}
+}
+
+impl Animation for Circadian {
+	fn frame(
+		&mut self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		now: Instant,
+	) -> Result<Option<Duration>> {
+		let color = self.color_at(SystemTime::now());
+		let control = self.control.clone();
+		let mut holds = control.holds.lock().unwrap();
+		let selected: Vec<&Bulb> =
+			bulbs.values().filter(|bulb| bulb.is_online() && self.is_selected(bulb)).collect();
+		for bulb in selected {
+			self.update(bulb, &mut holds, color, now)?;
+		}
+		Ok(Some(self.interval))
+	}
+}
@@ -0,0 +1,164 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+	udp::{Animation, Bulb, Easing},
+	LifxIdent,
+	MacAddress,
+	HSBK,
+};
+
+/// Rotates a palette of colors across a set of bulbs, each one a little further along than the
+/// last, so the colors appear to chase each other around the room.
+///
+/// Run it with [Manager::animate](crate::udp::Manager::animate).  It runs until cancelled.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use lifx_core::{udp::{ColorCycle, Manager}, LifxIdent, HSBK};
+///
+/// let mgr = Manager::new().unwrap();
+/// let palette = vec![HSBK::color(0, 1.0, 1.0), HSBK::color(120, 1.0, 1.0)];
+/// let cycle = ColorCycle::new(palette).group(LifxIdent([0; 16])).period(Duration::from_secs(30));
+/// let handle = mgr.animate(cycle);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorCycle {
+	palette: Vec<HSBK>,
+	targets: Vec<MacAddress>,
+	groups: Vec<LifxIdent>,
+	period: Duration,
+	step: Option<Duration>,
+	easing: Easing,
+	offset: Option<f32>,
+	elapsed: Duration,
+}
+
+impl ColorCycle {
+	/// Cycles through `palette` on no bulbs yet.
+	///
+	/// By default each trip through the palette takes a minute, easing in and out of each color,
+	/// with the bulbs spread evenly around it.
+	pub fn new(palette: Vec<HSBK>) -> ColorCycle {
+		ColorCycle {
+			palette,
+			targets: Vec::new(),
+			groups: Vec::new(),
+			period: Duration::from_secs(60),
+			step: None,
+			easing: Easing::EaseInOut,
+			offset: None,
+			elapsed: Duration::from_secs(0),
+		}
+	}
+
+	/// Also cycles the bulb `target`.  Bulbs are offset in the order they're added.
+	pub fn target(mut self, target: MacAddress) -> ColorCycle {
+		self.targets.push(target);
+		self
+	}
+
+	/// Also cycles each of `targets`.
+	pub fn targets(mut self, targets: impl IntoIterator<Item = MacAddress>) -> ColorCycle {
+		self.targets.extend(targets);
+		self
+	}
+
+	/// Also cycles every bulb in the group with ID `group`, including ones that join it later.
+	/// These come after the bulbs added with [ColorCycle::target], in order of their addresses.
+	pub fn group(mut self, group: LifxIdent) -> ColorCycle {
+		self.groups.push(group);
+		self
+	}
+
+	/// How long each trip through the whole palette takes.
+	pub fn period(mut self, period: Duration) -> ColorCycle {
+		self.period = period.max(Duration::from_millis(1));
+		self
+	}
+
+	/// How long each fade sent to the bulbs lasts.
+	///
+	/// By default there are four for each color in the palette, but no more than ten a second.
+	pub fn step(mut self, step: Duration) -> ColorCycle {
+		self.step = Some(step.max(Duration::from_millis(1)));
+		self
+	}
+
+	/// How the colors blend from one to the next.
+	pub fn easing(mut self, easing: Easing) -> ColorCycle {
+		self.easing = easing;
+		self
+	}
+
+	/// How far along the palette each bulb is from the one before it, as a fraction of the period
+	/// (so `0.0` keeps them all in step).  By default the bulbs are spread evenly around it.
+	pub fn offset(mut self, offset: f32) -> ColorCycle {
+		self.offset = Some(offset);
+		self
+	}
+
+	/// The color `phase` of the way through the palette (wrapping around at `1.0`).
+	pub fn color_at(&self, phase: f32) -> Option<HSBK> {
+		let count = self.palette.len();
+		let position = phase.rem_euclid(1.0) * count as f32;
+		let index = (position as usize).min(count.checked_sub(1)?);
+		let next = self.palette[(index + 1) % count];
+		Some(self.palette[index].lerp(next, self.easing.apply(position - index as f32)))
+	}
+
+	fn step_or_default(&self) -> Duration {
+		self.step.unwrap_or_else(|| {
+			let per_color = self.period / (self.palette.len() as u32).max(1);
+			(per_color / 4).max(Duration::from_millis(100))
+		})
+	}
+
+	/// The bulbs to cycle, in order.
+	fn selected<'a>(&self, bulbs: &'a HashMap<MacAddress, Bulb>) -> Vec<&'a Bulb> {
+		let mut selected: Vec<&Bulb> =
+			self.targets.iter().filter_map(|target| bulbs.get(target)).collect();
+		let mut grouped: Vec<&Bulb> = bulbs
+			.values()
+			.filter(|bulb| bulb.group_id().is_some_and(|group| self.groups.contains(group)))
+			.filter(|bulb| !self.targets.contains(&bulb.target))
+			.collect();
+		grouped.sort_by_key(|bulb| bulb.target);
+		selected.extend(grouped);
+		selected
+	}
+}
+
+impl Animation for ColorCycle {
+	fn frame(
+		&mut self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		_now: Instant,
+	) -> Result<Option<Duration>> {
+		if self.palette.is_empty() {
+			return Ok(None);
+		}
+
+		let step = self.step_or_default();
+		self.elapsed += step;
+		let phase = self.elapsed.as_secs_f32() / self.period.as_secs_f32();
+
+		// offline bulbs keep their place, so the others don't jump when one drops out
+		let selected = self.selected(bulbs);
+		let offset = self.offset.unwrap_or(1.0 / selected.len().max(1) as f32);
+		for (i, bulb) in selected.into_iter().enumerate() {
+			if !bulb.is_online() {
+				continue;
+			}
+			if let Some(color) = self.color_at(phase + i as f32 * offset) {
+				let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+				bulb.set_color(color, step)?;
+			}
+		}
+		Ok(Some(step))
+	}
+}
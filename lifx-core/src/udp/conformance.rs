@@ -0,0 +1,343 @@
+//! Checks how closely a device follows the documented LAN protocol.
+//!
+//! [run] sends a scripted sequence of requests to a single device (real or emulated) and records,
+//! for every step, whether the device answered as documented.  The resulting [Report] lists the
+//! message types the device supports, the steps where it deviated from the protocol docs, and any
+//! undocumented message types it sent back along the way.
+
+use std::{
+	collections::BTreeSet,
+	net::{SocketAddr, UdpSocket},
+	time::{Duration, Instant},
+};
+use anyhow::Result;
+
+use crate::{
+	BuildOptions,
+	EchoPayload,
+	Error,
+	MacAddress,
+	Message,
+	RawMessage,
+	ResponseMeta,
+	Service,
+};
+
+/// The result of a single conformance check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+	/// The device replied exactly as documented.
+	Passed,
+	/// The device replied, but not as documented.
+	Deviation(String),
+	/// The device explicitly rejected the request, or replied with something unrelated.
+	Unsupported,
+	/// The device did not reply before the timeout.
+	NoResponse,
+}
+
+/// One step of the conformance script.
+#[derive(Debug, Clone)]
+pub struct Check {
+	/// Short, human readable name of the check.
+	pub name: &'static str,
+	/// The message type that was sent.
+	pub request: u16,
+	/// The message types that were received in reply.
+	pub replies: Vec<u16>,
+	pub outcome: Outcome,
+}
+
+/// The structured result of a conformance run.
+#[derive(Debug, Clone)]
+pub struct Report {
+	pub target: MacAddress,
+	pub addr: SocketAddr,
+	pub checks: Vec<Check>,
+	/// Message types received from the device that this crate can't decode.
+	pub unknown_messages: BTreeSet<u16>,
+}
+
+impl Report {
+	/// The message types that the device answered as documented.
+	pub fn supported(&self) -> BTreeSet<u16> {
+		self.checks
+			.iter()
+			.filter(|c| c.outcome == Outcome::Passed)
+			.map(|c| c.request)
+			.collect()
+	}
+
+	/// The checks where the device replied, but not as documented.
+	pub fn deviations(&self) -> impl Iterator<Item = &Check> {
+		self.checks
+			.iter()
+			.filter(|c| matches!(c.outcome, Outcome::Deviation(_)))
+	}
+
+	pub fn passed(&self) -> bool {
+		self.deviations().next().is_none()
+	}
+}
+
+/// Runs the conformance script against the device `target` at `addr`.
+///
+/// `sock` must not be shared with a [Manager] (or anything else reading from it), since replies
+/// are read from it directly.  Each request waits at most `timeout` for its replies.
+///
+/// The script leaves the device as it found it: labels and colors are written back with the
+/// values that were read from the device.
+///
+/// [Manager]: crate::udp::Manager
+pub fn run(sock: &UdpSocket, addr: SocketAddr, target: MacAddress, timeout: Duration) -> Result<Report> {
+	let mut session = Session {
+		sock,
+		addr,
+		target,
+		source: 0x636f_6e66,
+		sequence: 0,
+		timeout,
+		report: Report {
+			target,
+			addr,
+			checks: Vec::new(),
+			unknown_messages: BTreeSet::new(),
+		},
+	};
+
+	session.check_service()?;
+	session.check_echo()?;
+	session.check_label()?;
+	session.check_color()?;
+	session.check_zones()?;
+	session.probe_capabilities()?;
+
+	Ok(session.report)
+}
+
+struct Session<'a> {
+	sock: &'a UdpSocket,
+	addr: SocketAddr,
+	target: MacAddress,
+	source: u32,
+	sequence: u8,
+	timeout: Duration,
+	report: Report,
+}
+
+impl<'a> Session<'a> {
+	/// Sends `msg` and collects every reply carrying the same source and sequence number.
+	fn request(&mut self, msg: Message) -> Result<Vec<Message>> {
+		self.sequence = self.sequence.wrapping_add(1);
+		let options = BuildOptions {
+			target: Some(self.target),
+			res_required: true,
+			sequence: self.sequence,
+			source: self.source,
+			..Default::default()
+		};
+		self.sock
+			.send_to(&RawMessage::build(&options, msg)?.pack()?, self.addr)?;
+
+		let deadline = Instant::now() + self.timeout;
+		let mut replies = Vec::new();
+		let mut buf = [0; 1024];
+		loop {
+			let now = Instant::now();
+			if now >= deadline {
+				break;
+			}
+			self.sock.set_read_timeout(Some(deadline - now))?;
+			let nbytes = match self.sock.recv_from(&mut buf) {
+				Ok((nbytes, _)) => nbytes,
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+				Err(e) if e.kind() == std::io::ErrorKind::TimedOut => break,
+				Err(e) => return Err(e.into()),
+			};
+			let raw = match RawMessage::unpack(&buf[..nbytes]) {
+				Ok(raw) => raw,
+				Err(_) => continue,
+			};
+			let expected = ResponseMeta {
+				source: self.source,
+				sequence: self.sequence,
+				target: self.target,
+			};
+			if raw.response_meta() != expected {
+				continue;
+			}
+			match Message::from_raw(&raw) {
+				Ok(Message::Acknowledgement) => (),
+				Ok(msg) => {
+					replies.push(msg);
+					// multizone replies can span several packets, so keep listening for those
+					if !matches!(replies.last(), Some(Message::StateMultiZone { .. })) {
+						break;
+					}
+				}
+				Err(Error::UnknownMessageType(typ)) => {
+					self.report.unknown_messages.insert(typ);
+				}
+				Err(_) => (),
+			}
+		}
+
+		Ok(replies)
+	}
+
+	/// Sends `msg`, then records the outcome decided by `judge` (which only sees the first reply).
+	fn check<F>(&mut self, name: &'static str, msg: Message, judge: F) -> Result<Option<Message>>
+	where
+		F: FnOnce(&Message) -> Outcome,
+	{
+		let request = msg.get_num();
+		let replies = self.request(msg)?;
+		let outcome = match replies.first() {
+			None => Outcome::NoResponse,
+			Some(reply) => judge(reply),
+		};
+		self.report.checks.push(Check {
+			name,
+			request,
+			replies: replies.iter().map(Message::get_num).collect(),
+			outcome,
+		});
+		Ok(replies.into_iter().next())
+	}
+
+	fn check_service(&mut self) -> Result<()> {
+		let port = self.addr.port() as u32;
+		self.check("discovery", Message::GetService, |reply| match *reply {
+			Message::StateService {
+				service: Service::UDP,
+				port: p,
+			} if p == port => Outcome::Passed,
+			Message::StateService { port: p, .. } => {
+				Outcome::Deviation(format!("advertised port {} but replied from {}", p, port))
+			}
+			_ => Outcome::Unsupported,
+		})?;
+		Ok(())
+	}
+
+	fn check_echo(&mut self) -> Result<()> {
+		let mut payload = [0; 64];
+		for (idx, b) in payload.iter_mut().enumerate() {
+			*b = (idx as u8).wrapping_mul(37) ^ self.sequence;
+		}
+		self.check(
+			"echo",
+			Message::EchoRequest {
+				payload: EchoPayload(payload),
+			},
+			|reply| match reply {
+				Message::EchoResponse { payload: p } if p.0[..] == payload[..] => Outcome::Passed,
+				Message::EchoResponse { .. } => {
+					Outcome::Deviation("echoed payload does not match".to_owned())
+				}
+				_ => Outcome::Unsupported,
+			},
+		)?;
+		Ok(())
+	}
+
+	fn check_label(&mut self) -> Result<()> {
+		let label = match self.check("get label", Message::GetLabel, |reply| match reply {
+			Message::StateLabel { .. } => Outcome::Passed,
+			_ => Outcome::Unsupported,
+		})? {
+			Some(Message::StateLabel { label }) => label,
+			_ => return Ok(()),
+		};
+
+		let expected = label.clone();
+		self.check("label round-trip", Message::SetLabel { label }, |reply| {
+			match reply {
+				Message::StateLabel { label } if *label == expected => Outcome::Passed,
+				Message::StateLabel { label } => Outcome::Deviation(format!(
+					"set label {:?} but device reported {:?}",
+					expected.to_str(),
+					label.to_str()
+				)),
+				_ => Outcome::Unsupported,
+			}
+		})?;
+		Ok(())
+	}
+
+	fn check_color(&mut self) -> Result<()> {
+		let color = match self.check("get color", Message::LightGet, |reply| match reply {
+			Message::LightState { .. } => Outcome::Passed,
+			_ => Outcome::Unsupported,
+		})? {
+			Some(Message::LightState { color, .. }) => color,
+			_ => return Ok(()),
+		};
+
+		self.check(
+			"color set/verify",
+			Message::LightSetColor {
+				reserved: 0,
+				color,
+				duration: 0,
+			},
+			|reply| match reply {
+				Message::LightState { color: c, .. } if *c == color => Outcome::Passed,
+				Message::LightState { color: c, .. } => Outcome::Deviation(format!(
+					"set color {:?} but device reported {:?}",
+					color, c
+				)),
+				_ => Outcome::Unsupported,
+			},
+		)?;
+		Ok(())
+	}
+
+	fn check_zones(&mut self) -> Result<()> {
+		self.check(
+			"zone query",
+			Message::GetColorZones {
+				start_index: 0,
+				end_index: 255,
+			},
+			|reply| match *reply {
+				Message::StateZone { count, index, .. } if index < count => Outcome::Passed,
+				Message::StateMultiZone { count, index, .. } if index < count => Outcome::Passed,
+				Message::StateZone { count, index, .. }
+				| Message::StateMultiZone { count, index, .. } => Outcome::Deviation(format!(
+					"zone index {} is out of range for {} zones",
+					index, count
+				)),
+				_ => Outcome::Unsupported,
+			},
+		)?;
+		Ok(())
+	}
+
+	fn probe_capabilities(&mut self) -> Result<()> {
+		let probes = vec![
+			("host info", Message::GetHostInfo, 13),
+			("host firmware", Message::GetHostFirmware, 15),
+			("wifi info", Message::GetWifiInfo, 17),
+			("wifi firmware", Message::GetWifiFirmware, 19),
+			("power", Message::GetPower, 22),
+			("version", Message::GetVersion, 33),
+			("info", Message::GetInfo, 35),
+			("location", Message::GetLocation, 50),
+			("group", Message::GetGroup, 53),
+			("light power", Message::LightGetPower, 118),
+			("infrared", Message::LightGetInfrared, 121),
+		];
+
+		for (name, msg, expected) in probes {
+			self.check(name, msg, |reply| {
+				if reply.get_num() == expected {
+					Outcome::Passed
+				} else {
+					Outcome::Unsupported
+				}
+			})?;
+		}
+		Ok(())
+	}
+}
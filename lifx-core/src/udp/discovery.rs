@@ -0,0 +1,209 @@
+use std::{
+	iter,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+	str::FromStr,
+	time::{Duration, Instant},
+};
+use anyhow::{anyhow, Error};
+use get_if_addrs::{get_if_addrs, IfAddr, Ifv4Addr};
+use log::info;
+
+/// Which network interfaces discovery broadcasts are sent on.
+///
+/// By default (with both lists empty) every non-loopback IPv4 interface is used.  Otherwise, an
+/// interface is used if its name is in `names`, or if its address is in one of the `networks`.
+///
+/// A filter can also be parsed from a comma-separated list of names and CIDR ranges, like
+/// `"eth0, 192.168.1.0/24"`, which is handy for configuration files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceFilter {
+	/// Interface names, like `eth0` or `wlan0`.
+	pub names: Vec<String>,
+	/// IPv4 networks, as an address and a prefix length (e.g. `(192.168.1.0, 24)`).
+	pub networks: Vec<(Ipv4Addr, u8)>,
+}
+
+impl InterfaceFilter {
+	/// A filter that allows every interface.
+	pub fn all() -> InterfaceFilter {
+		InterfaceFilter::default()
+	}
+
+	/// Also allows the interface called `name`.
+	pub fn with_name(mut self, name: impl Into<String>) -> InterfaceFilter {
+		self.names.push(name.into());
+		self
+	}
+
+	/// Also allows interfaces with an address in `addr/prefix`.
+	pub fn with_network(mut self, addr: Ipv4Addr, prefix: u8) -> InterfaceFilter {
+		self.networks.push((addr, prefix.min(32)));
+		self
+	}
+
+	/// Whether discovery should use the interface called `name`, with address `addr`.
+	pub fn allows(&self, name: &str, addr: Ipv4Addr) -> bool {
+		if self.names.is_empty() && self.networks.is_empty() {
+			return true;
+		}
+		self.names.iter().any(|n| n == name)
+			|| self.networks.iter().any(|&(network, prefix)| {
+				let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+				u32::from(addr) & mask == u32::from(network) & mask
+			})
+	}
+}
+
+impl FromStr for InterfaceFilter {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<InterfaceFilter, Error> {
+		let mut filter = InterfaceFilter::all();
+		for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+			filter = match item.split_once('/') {
+				Some((addr, prefix)) => {
+					let addr = addr.parse()?;
+					let prefix = prefix.parse::<u8>()?;
+					if prefix > 32 {
+						return Err(anyhow!("invalid prefix length in `{}`", item));
+					}
+					filter.with_network(addr, prefix)
+				}
+				None => filter.with_name(item),
+			};
+		}
+		Ok(filter)
+	}
+}
+
+/// How often [Manager::refresh] re-discovers bulbs on its own (see [Manager::auto_discovery]).
+///
+/// Discovery is repeated every `interval` while it keeps finding new bulbs.  Each time it finds
+/// nothing new, the wait before the next one doubles, up to `max_interval`, so a network that
+/// isn't changing isn't broadcast to needlessly.  Calling [Manager::discover] starts the wait
+/// over from `interval`.
+///
+/// [Manager::refresh]: crate::udp::Manager::refresh
+/// [Manager::auto_discovery]: crate::udp::Manager::auto_discovery
+/// [Manager::discover]: crate::udp::Manager::discover
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoDiscovery {
+	/// The wait between discoveries that are finding new bulbs.
+	pub interval: Duration,
+	/// The longest wait between discoveries, however long nothing new has been found.
+	pub max_interval: Duration,
+}
+
+impl Default for AutoDiscovery {
+	fn default() -> AutoDiscovery {
+		AutoDiscovery {
+			interval: Duration::from_secs(60),
+			max_interval: Duration::from_secs(15 * 60),
+		}
+	}
+}
+
+/// When the managers last ran discovery, and how long to wait before the next one.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DiscoverySchedule {
+	pub(crate) last: Instant,
+	/// The current wait, or `None` to use [AutoDiscovery::interval].
+	wait: Option<Duration>,
+	/// How many bulbs were known when discovery last ran.
+	known: usize,
+}
+
+impl DiscoverySchedule {
+	pub(crate) fn new(now: Instant) -> DiscoverySchedule {
+		DiscoverySchedule {
+			last: now,
+			wait: None,
+			known: 0,
+		}
+	}
+
+	/// Records a discovery that was asked for, which starts the backoff over.
+	pub(crate) fn discovered(&mut self, now: Instant, known: usize) {
+		*self = DiscoverySchedule {
+			last: now,
+			wait: None,
+			known,
+		};
+	}
+
+	/// Whether an automatic discovery is due at `now`, when `known` bulbs are known.
+	///
+	/// If it is, this records it as having run, and backs off if nothing new was found since the
+	/// last one.
+	pub(crate) fn due(&mut self, config: &AutoDiscovery, now: Instant, known: usize) -> bool {
+		let wait = self.wait.unwrap_or(config.interval);
+		if now.saturating_duration_since(self.last) < wait {
+			return false;
+		}
+
+		let next = if known > self.known {
+			config.interval
+		} else {
+			wait.saturating_mul(2).min(config.max_interval.max(config.interval))
+		};
+		*self = DiscoverySchedule {
+			last: now,
+			wait: Some(next),
+			known,
+		};
+		true
+	}
+}
+
+/// Where to send discovery requests from a socket bound to `local`.
+///
+/// That's the broadcast address of every allowed IPv4 interface, plus the link-local all-nodes
+/// multicast group for IPv6 sockets (which reach IPv4 networks through v4-mapped addresses).
+pub(super) fn discovery_addrs(local: &SocketAddr, filter: &InterfaceFilter) -> Vec<SocketAddr> {
+	let broadcasts = broadcast_addrs(filter);
+	if local.is_ipv4() {
+		return broadcasts;
+	}
+
+	let all_nodes = SocketAddr::new(IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1)), 56700);
+	broadcasts
+		.into_iter()
+		.map(|addr| reachable_from(local, addr))
+		.chain(iter::once(all_nodes))
+		.collect()
+}
+
+/// `addr`, as seen from a socket bound to `local`: IPv6 sockets reach IPv4 devices through their
+/// v4-mapped addresses.
+pub(super) fn reachable_from(local: &SocketAddr, addr: SocketAddr) -> SocketAddr {
+	match addr.ip() {
+		IpAddr::V4(ip) if local.is_ipv6() => {
+			SocketAddr::new(IpAddr::V6(ip.to_ipv6_mapped()), addr.port())
+		}
+		_ => addr,
+	}
+}
+
+/// The broadcast address of every allowed (non-loopback) IPv4 interface, on the LIFX port.
+#[allow(clippy::single_match)]
+fn broadcast_addrs(filter: &InterfaceFilter) -> Vec<SocketAddr> {
+	let mut targets = Vec::new();
+	for iface in get_if_addrs().unwrap() {
+		match iface.addr {
+			IfAddr::V4(Ifv4Addr {
+				ip,
+				broadcast: Some(bcast),
+				..
+			}) => {
+				if ip.is_loopback() || !filter.allows(&iface.name, ip) {
+					continue;
+				}
+				let addr = SocketAddr::new(IpAddr::V4(bcast), 56700);
+				info!("Discovering bulbs on LAN {:?} ({})", addr, iface.name);
+				targets.push(addr);
+			}
+			_ => {}
+		}
+	}
+	targets
+}
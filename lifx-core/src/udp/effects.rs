@@ -0,0 +1,153 @@
+//! The "breathe" and "pulse" effects from the LIFX HTTP API, built on [WaveformSpec].
+//!
+//! These take the same options as their HTTP counterparts, so code (and muscle memory) can move
+//! between the two.  Run them with [Effect::start], [Manager::run_effect] or
+//! [Manager::run_group_effect].
+//!
+//! [Manager::run_effect]: crate::udp::Manager::run_effect
+//! [Manager::run_group_effect]: crate::udp::Manager::run_group_effect
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{
+	udp::{Bulb, WaveformSpec},
+	Waveform,
+	HSBK,
+};
+
+/// Slowly fades between two colors, like the HTTP API's breathe effect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breathe {
+	/// The color to breathe towards.
+	pub color: HSBK,
+	/// The color to start from, or `None` to start from the bulb's current color.
+	pub from_color: Option<HSBK>,
+	/// How long each breath lasts.
+	pub period: Duration,
+	/// How many breaths to take; fractional cycles are allowed.
+	pub cycles: f32,
+	/// Whether the bulb stays on `color` at the end, rather than going back to where it started.
+	pub persist: bool,
+	/// Whether to turn the bulb on first, if it's off.
+	pub power_on: bool,
+	/// Where in each breath `color` is strongest, from `0.0` (the start) to `1.0` (the end).
+	pub peak: f32,
+}
+
+impl Breathe {
+	/// A single one-second breath towards `color`, with the HTTP API's other defaults.
+	pub fn new(color: HSBK) -> Breathe {
+		Breathe {
+			color,
+			from_color: None,
+			period: Duration::from_secs(1),
+			cycles: 1.0,
+			persist: false,
+			power_on: true,
+			peak: 0.5,
+		}
+	}
+}
+
+/// Switches sharply between two colors, like the HTTP API's pulse effect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pulse {
+	/// The color to pulse to.
+	pub color: HSBK,
+	/// The color to start from, or `None` to start from the bulb's current color.
+	pub from_color: Option<HSBK>,
+	/// How long each pulse lasts.
+	pub period: Duration,
+	/// How many pulses; fractional cycles are allowed.
+	pub cycles: f32,
+	/// Whether the bulb stays on `color` at the end, rather than going back to where it started.
+	pub persist: bool,
+	/// Whether to turn the bulb on first, if it's off.
+	pub power_on: bool,
+}
+
+impl Pulse {
+	/// A single one-second pulse to `color`, with the HTTP API's other defaults.
+	pub fn new(color: HSBK) -> Pulse {
+		Pulse {
+			color,
+			from_color: None,
+			period: Duration::from_secs(1),
+			cycles: 1.0,
+			persist: false,
+			power_on: true,
+		}
+	}
+}
+
+/// Either of the HTTP API's waveform effects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+	Breathe(Breathe),
+	Pulse(Pulse),
+}
+
+impl From<Breathe> for Effect {
+	fn from(breathe: Breathe) -> Effect {
+		Effect::Breathe(breathe)
+	}
+}
+
+impl From<Pulse> for Effect {
+	fn from(pulse: Pulse) -> Effect {
+		Effect::Pulse(pulse)
+	}
+}
+
+impl Effect {
+	/// The waveform that runs the effect, once the bulb is on and showing `from_color`.
+	pub fn waveform(&self) -> WaveformSpec {
+		self.waveform_to(self.color())
+	}
+
+	/// The color the effect moves towards.
+	pub fn color(&self) -> HSBK {
+		match self {
+			Effect::Breathe(b) => b.color,
+			Effect::Pulse(p) => p.color,
+		}
+	}
+
+	/// Like [Effect::waveform], but moving towards `color` instead.
+	fn waveform_to(&self, color: HSBK) -> WaveformSpec {
+		let (waveform, period, cycles, persist, skew) = match *self {
+			Effect::Breathe(b) => {
+				let skew = 2.0 * b.peak.clamp(0.0, 1.0) - 1.0;
+				(Waveform::Sine, b.period, b.cycles, b.persist, skew)
+			}
+			Effect::Pulse(p) => (Waveform::Pulse, p.period, p.cycles, p.persist, 0.0),
+		};
+		WaveformSpec::new(waveform, color)
+			.period(period)
+			.cycles(cycles)
+			.skew(skew)
+			.transient(!persist)
+	}
+
+	/// Starts the effect on `bulb`: sets `from_color` and turns it on if asked to, then sends the
+	/// waveform.
+	///
+	/// The colors are clamped to what the bulb's model supports (see [HSBK::clamped_for]).
+	pub fn start(&self, bulb: &Bulb) -> Result<()> {
+		let (from_color, power_on) = match *self {
+			Effect::Breathe(b) => (b.from_color, b.power_on),
+			Effect::Pulse(p) => (p.from_color, p.power_on),
+		};
+		let clamp = |color: HSBK| bulb.product_info().map_or(color, |info| color.clamped_for(info));
+
+		if let Some(from_color) = from_color {
+			bulb.set_color(clamp(from_color), Duration::from_secs(0))?;
+		}
+		if power_on {
+			bulb.set_power(true, Duration::from_secs(0))?;
+		}
+		bulb.set_waveform(self.waveform_to(clamp(self.color())))
+	}
+}
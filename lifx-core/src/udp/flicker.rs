@@ -0,0 +1,123 @@
+use std::{
+	collections::HashMap,
+	ops::RangeInclusive,
+	time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+	udp::{Animation, Bulb},
+	MacAddress,
+	HSBK,
+};
+
+/// A candle-like flicker: small, random changes of hue and brightness around a warm color.
+///
+/// Run it with [Manager::animate](crate::udp::Manager::animate).  It runs until cancelled.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use lifx_core::{udp::{Flicker, Manager}, MacAddress};
+///
+/// let mgr = Manager::new().unwrap();
+/// let candle = Flicker::new(MacAddress([0xd0, 0x73, 0xd5, 1, 2, 3])).intensity(0.3);
+/// let handle = mgr.animate(candle);
+/// std::thread::sleep(Duration::from_secs(60));
+/// handle.cancel();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flicker {
+	target: MacAddress,
+	zones: Option<RangeInclusive<u8>>,
+	color: HSBK,
+	intensity: f32,
+	tempo: Duration,
+	rng: u64,
+}
+
+impl Flicker {
+	/// A medium flicker around a candle's orange glow, on the bulb `target`.
+	pub fn new(target: MacAddress) -> Flicker {
+		Flicker {
+			target,
+			zones: None,
+			color: HSBK {
+				hue: 4551,
+				saturation: 29491,
+				brightness: 39321,
+				kelvin: 2000,
+			},
+			intensity: 0.5,
+			tempo: Duration::from_millis(150),
+			rng: u64::from(crate::random_source()),
+		}
+	}
+
+	/// Only flickers these zones of a multizone bulb (see [Bulb::set_zones]).
+	pub fn zones(mut self, zones: RangeInclusive<u8>) -> Flicker {
+		self.zones = Some(zones);
+		self
+	}
+
+	/// The color the flicker wanders around, and never gets brighter than.
+	pub fn color(mut self, color: HSBK) -> Flicker {
+		self.color = color;
+		self
+	}
+
+	/// How far the flicker wanders, from `0.0` (not at all) to `1.0` (down to 40% brightness,
+	/// and 8 degrees either side of the hue).  Values outside that are clamped.
+	pub fn intensity(mut self, intensity: f32) -> Flicker {
+		self.intensity = intensity.clamp(0.0, 1.0);
+		self
+	}
+
+	/// The average time between changes; each one takes between half and one and a half times
+	/// this.
+	pub fn tempo(mut self, tempo: Duration) -> Flicker {
+		self.tempo = tempo;
+		self
+	}
+
+	/// The next color, and how long to take fading to it.
+	fn next_flicker(&mut self) -> (HSBK, Duration) {
+		let dim = self.random() * self.intensity * 0.6;
+		let hue_offset = (self.random() * 2.0 - 1.0) * self.intensity * (8.0 / 360.0);
+		let color = HSBK {
+			hue: self.color.hue.wrapping_add((hue_offset * 65536.0) as i16 as u16),
+			brightness: (self.color.brightness as f32 * (1.0 - dim)) as u16,
+			..self.color
+		};
+		let duration = self.tempo.mul_f32(0.5 + self.random());
+		(color, duration)
+	}
+
+	/// A random number in `[0.0, 1.0)`, from an xorshift generator.
+	fn random(&mut self) -> f32 {
+		let mut x = self.rng.max(1);
+		x ^= x << 13;
+		x ^= x >> 7;
+		x ^= x << 17;
+		self.rng = x;
+		(x >> 40) as f32 / (1u64 << 24) as f32
+	}
+}
+
+impl Animation for Flicker {
+	fn frame(
+		&mut self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		_now: Instant,
+	) -> Result<Option<Duration>> {
+		let (color, duration) = self.next_flicker();
+		if let Some(bulb) = bulbs.get(&self.target).filter(|bulb| bulb.is_online()) {
+			let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+			match &self.zones {
+				Some(zones) => bulb.set_zones(zones.clone(), color, duration)?,
+				None => bulb.set_color(color, duration)?,
+			}
+		}
+		Ok(Some(duration))
+	}
+}
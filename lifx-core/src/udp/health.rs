@@ -0,0 +1,145 @@
+use std::{
+	collections::VecDeque,
+	convert::TryInto,
+	time::{Duration, Instant},
+};
+
+use crate::EchoPayload;
+
+/// How (and how often) bulbs are checked with [Message::EchoRequest], when enabled with
+/// [Manager::keepalive].
+///
+/// A bulb is [HealthStatus::Unreachable] after `unreachable_after` echoes in a row go
+/// unanswered, and [HealthStatus::Degraded] if too many of its recent echoes were lost, or if the
+/// last one was slow.
+///
+/// [Message::EchoRequest]: crate::Message::EchoRequest
+/// [Manager::keepalive]: crate::udp::Manager::keepalive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keepalive {
+	/// How often each bulb is sent an echo request.
+	pub interval: Duration,
+	/// How long to wait for each reply before counting the echo as lost.
+	pub timeout: Duration,
+	/// How many of the most recent echoes the loss ratio is worked out from.
+	pub window: usize,
+	/// The loss ratio at or above which a bulb is degraded.
+	pub degraded_loss: f32,
+	/// The round-trip time above which a bulb is degraded.
+	pub degraded_rtt: Duration,
+	/// How many echoes in a row have to be lost for a bulb to be unreachable.
+	pub unreachable_after: usize,
+}
+
+impl Default for Keepalive {
+	fn default() -> Keepalive {
+		Keepalive {
+			interval: Duration::from_secs(10),
+			timeout: Duration::from_secs(2),
+			window: 10,
+			degraded_loss: 0.2,
+			degraded_rtt: Duration::from_millis(500),
+			unreachable_after: 3,
+		}
+	}
+}
+
+/// A bulb's overall health, judged by its recent echoes (see [Keepalive]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+	/// No echo has been answered or lost yet.
+	Unknown,
+	Healthy,
+	Degraded,
+	Unreachable,
+}
+
+/// How a bulb has been answering echo requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health {
+	pub status: HealthStatus,
+	/// The round-trip time of the most recently answered echo.
+	pub last_rtt: Option<Duration>,
+	/// The fraction of recent echoes that went unanswered, in `[0.0, 1.0]`.
+	pub loss: f32,
+}
+
+/// The echoes sent to one bulb, and what became of them.
+#[derive(Debug, Default)]
+pub(crate) struct EchoTracker {
+	next_nonce: u64,
+	last_sent: Option<Instant>,
+	/// Nonces of the echoes still waiting for a reply, and when they were sent.
+	pending: Vec<(u64, Instant)>,
+	/// The round-trip time of each finished echo (or `None` if it was lost), oldest first.
+	results: VecDeque<Option<Duration>>,
+	last_rtt: Option<Duration>,
+}
+
+impl EchoTracker {
+	/// Starts a new echo if one is due at `now`, returning the payload to send.
+	///
+	/// Echoes that have been waiting longer than the timeout are counted as lost first.
+	pub(crate) fn start(&mut self, keepalive: &Keepalive, now: Instant) -> Option<EchoPayload> {
+		let timeout = keepalive.timeout;
+		let age = |sent: &Instant| now.saturating_duration_since(*sent);
+		let lost = self.pending.iter().filter(|(_, sent)| age(sent) >= timeout).count();
+		self.pending.retain(|(_, sent)| age(sent) < timeout);
+		self.results.resize(self.results.len() + lost, None);
+		// enough for both the loss ratio and the run of losses to be worked out
+		let kept = keepalive.window.max(keepalive.unreachable_after).max(1);
+		while self.results.len() > kept {
+			self.results.pop_front();
+		}
+
+		if matches!(self.last_sent, Some(sent) if age(&sent) < keepalive.interval) {
+			return None;
+		}
+		let nonce = self.next_nonce;
+		self.next_nonce += 1;
+		self.last_sent = Some(now);
+		self.pending.push((nonce, now));
+
+		let mut payload = EchoPayload([0; 64]);
+		payload.0[..8].copy_from_slice(&nonce.to_le_bytes());
+		Some(payload)
+	}
+
+	/// Matches a reply to its echo, returning the round-trip time.
+	pub(crate) fn finish(&mut self, payload: &EchoPayload, now: Instant) -> Option<Duration> {
+		let nonce = u64::from_le_bytes(payload.0[..8].try_into().unwrap());
+		let index = self.pending.iter().position(|(n, _)| *n == nonce)?;
+		let (_, sent) = self.pending.remove(index);
+		let rtt = now.saturating_duration_since(sent);
+		self.results.push_back(Some(rtt));
+		self.last_rtt = Some(rtt);
+		Some(rtt)
+	}
+
+	pub(crate) fn health(&self, keepalive: &Keepalive) -> Health {
+		let recent = self.results.iter().rev().take(keepalive.window.max(1));
+		let (total, lost) = recent.fold((0, 0), |(total, lost), rtt| {
+			(total + 1, lost + rtt.is_none() as usize)
+		});
+		let loss = if total == 0 { 0.0 } else { lost as f32 / total as f32 };
+		let lost_in_a_row = self.results.iter().rev().take_while(|rtt| rtt.is_none()).count();
+
+		let status = if total == 0 {
+			HealthStatus::Unknown
+		} else if lost_in_a_row >= keepalive.unreachable_after.max(1) {
+			HealthStatus::Unreachable
+		} else if loss >= keepalive.degraded_loss
+			|| matches!(self.results.back(), Some(Some(rtt)) if *rtt > keepalive.degraded_rtt)
+		{
+			HealthStatus::Degraded
+		} else {
+			HealthStatus::Healthy
+		};
+
+		Health {
+			status,
+			last_rtt: self.last_rtt,
+			loss,
+		}
+	}
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+
+use crate::{udp::Bulb, LifxIdent, MacAddress};
+
+/// A location (usually a home or an office), and the groups of bulbs in it.
+///
+/// Devices don't report locations directly, so these are pieced together from the
+/// [Message::StateLocation](crate::Message::StateLocation) and
+/// [Message::StateGroup](crate::Message::StateGroup) that each bulb sends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+	pub id: LifxIdent,
+	pub label: String,
+	/// Sorted by label.
+	pub groups: Vec<Group>,
+}
+
+/// A group of bulbs (usually a room) within a [Location].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+	pub id: LifxIdent,
+	pub label: String,
+	/// Sorted by address.
+	pub bulbs: Vec<MacAddress>,
+}
+
+impl Location {
+	/// Builds the location hierarchy that `bulbs` belong to, sorted by label.
+	///
+	/// Bulbs that haven't reported both their location and their group yet are left out.
+	pub fn collect<'a>(bulbs: impl IntoIterator<Item = &'a Bulb>) -> Vec<Location> {
+		let mut locations: Vec<Location> = Vec::new();
+		for bulb in bulbs {
+			let (location_id, group_id) = match (bulb.location_id(), bulb.group_id()) {
+				(Some(location_id), Some(group_id)) => (location_id, group_id),
+				_ => continue,
+			};
+
+			let location = match locations.iter().position(|l| &l.id == location_id) {
+				Some(index) => &mut locations[index],
+				None => {
+					locations.push(Location {
+						id: location_id.clone(),
+						label: bulb.location.as_ref().cloned().unwrap_or_default(),
+						groups: Vec::new(),
+					});
+					locations.last_mut().unwrap()
+				}
+			};
+			let group = match location.groups.iter().position(|g| &g.id == group_id) {
+				Some(index) => &mut location.groups[index],
+				None => {
+					location.groups.push(Group {
+						id: group_id.clone(),
+						label: bulb.group.as_ref().cloned().unwrap_or_default(),
+						bulbs: Vec::new(),
+					});
+					location.groups.last_mut().unwrap()
+				}
+			};
+			group.bulbs.push(bulb.target);
+		}
+
+		locations.sort_by(|a, b| a.label.cmp(&b.label));
+		for location in &mut locations {
+			location.groups.sort_by(|a, b| a.label.cmp(&b.label));
+			for group in &mut location.groups {
+				group.bulbs.sort();
+			}
+		}
+		locations
+	}
+
+	/// Every bulb in the location, group by group.
+	pub fn bulbs(&self) -> impl Iterator<Item = MacAddress> + '_ {
+		self.groups.iter().flat_map(|group| group.bulbs.iter().copied())
+	}
+
+	/// The group with the given label, if there is one.
+	pub fn group(&self, label: &str) -> Option<&Group> {
+		self.groups.iter().find(|group| group.label == label)
+	}
+}
+
+/// Calls `f` with each online bulb in the location with ID `location`, stopping at the first
+/// error.
+pub(super) fn for_each_in_location<'a>(
+	bulbs: impl IntoIterator<Item = &'a Bulb>,
+	location: &LifxIdent,
+	f: impl FnMut(&Bulb) -> Result<()>,
+) -> Result<()> {
+	bulbs
+		.into_iter()
+		.filter(|bulb| bulb.is_online() && bulb.location_id() == Some(location))
+		.try_for_each(f)
+}
+
+/// Like [for_each_in_location], but for the bulbs in the group with ID `group`.
+pub(super) fn for_each_in_group<'a>(
+	bulbs: impl IntoIterator<Item = &'a Bulb>,
+	group: &LifxIdent,
+	f: impl FnMut(&Bulb) -> Result<()>,
+) -> Result<()> {
+	bulbs
+		.into_iter()
+		.filter(|bulb| bulb.is_online() && bulb.group_id() == Some(group))
+		.try_for_each(f)
+}
@@ -1,91 +1,350 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "serde")]
+use std::path::Path;
 use std::{
-	collections::HashMap,
-	net::{IpAddr, SocketAddr, UdpSocket},
-	sync::{Arc, Mutex},
-	thread,
+	collections::{hash_map::Entry, HashMap},
+	net::{SocketAddr, UdpSocket},
+	io,
+	sync::{
+		atomic::{AtomicBool, AtomicU8, Ordering},
+		mpsc::{self, Receiver, RecvTimeoutError, Sender},
+		Arc, Mutex, RwLock,
+	},
+	thread::{self, JoinHandle},
 	time::{Duration, Instant},
 };
-use anyhow::Result;
-use get_if_addrs::{get_if_addrs, IfAddr, Ifv4Addr};
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
 
 use crate::{
 	self as lifx,
 	BuildOptions,
+	HSBK,
+	LifxIdent,
+	MacAddress,
 	Message,
+	PowerLevel,
 	RawMessage,
+	ResponseMeta,
 	Service,
-	udp::{Bulb, Color, RefreshableData}
+	udp::{
+		discovery::{discovery_addrs, reachable_from, DiscoverySchedule},
+		location::{for_each_in_group, for_each_in_location},
+		scheduler::Scheduler,
+		Animation,
+		AnimationHandle,
+		AutoDiscovery,
+		Bulb,
+		BulbSnapshot,
+		Color,
+		Effect,
+		Health,
+		HevCycle,
+		InterfaceFilter,
+		Keepalive,
+		Location,
+		ManagerBuilder,
+		ManagerConfig,
+		QueueMetrics,
+		RateLimiter,
+		RateLimits,
+		RefreshPolicies,
+		RetryPolicy,
+		RuntimeInfo,
+		SendQueue,
+		Timeouts,
+		Transport,
+	},
 };
+#[cfg(feature = "serde")]
+use crate::udp::SavedState;
 
-pub struct Manager {
-	pub bulbs: Arc<Mutex<HashMap<u64, Bulb>>>,
-	pub last_discovery: Instant,
-	pub sock: UdpSocket,
+/// Something that happened to one of the [Manager]'s bulbs (see [Manager::subscribe]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagerEvent {
+	/// A bulb replied for the first time (or for the first time since it was lost).
+	DeviceDiscovered(MacAddress),
+	/// One of a bulb's cached values changed.
+	DeviceUpdated(MacAddress, BulbField),
+	/// A bulb stopped replying for [Timeouts::offline_after], and its cached state is no longer
+	/// trusted.
+	DeviceOffline(MacAddress),
+	/// An offline bulb replied again.
+	DeviceOnline(MacAddress),
+	/// A bulb stopped replying for [Timeouts::evict_after], and was removed from
+	/// [Manager::bulbs].
+	DeviceLost(MacAddress),
+}
+
+/// The cached values of a [Bulb] that a [ManagerEvent::DeviceUpdated] can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BulbField {
+	Model,
+	Location,
+	Group,
+	Name,
+	HostFirmware,
+	WifiFirmware,
+	WifiSignal,
+	Runtime,
+	PowerLevel,
+	Infrared,
+	HevCycle,
+	Color,
+}
+
+/// How often the receiving thread checks whether the [Manager] is shutting down.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Messages sent by [Manager::send_with_ack] or [Manager::request] that are waiting for a reply,
+/// which is handed over even if it can't be decoded.
+type PendingReplies = Arc<Mutex<HashMap<ResponseMeta, Sender<Result<Message, lifx::Error>>>>>;
+
+/// Something that went wrong in the [Manager]'s background work (see [Manager::errors]).
+#[derive(Debug, thiserror::Error)]
+pub enum ManagerError {
+	/// The socket failed, and the receiving thread has stopped: no more replies will arrive, so
+	/// the Manager should be replaced.
+	#[error("error receiving, no longer listening for replies: {0}")]
+	Receive(io::Error),
+	/// A queued message or a re-sent broadcast couldn't be sent.
+	#[error("error sending to {addr}: {error}")]
+	Send { addr: SocketAddr, error: io::Error },
+	/// A packet that isn't a valid LIFX message arrived.
+	#[error("error unpacking message from {addr}: {error}")]
+	Unpack { addr: SocketAddr, error: lifx::Error },
+	/// A message arrived that couldn't be decoded.  [lifx::Error::UnknownMessageType] is common,
+	/// since devices send some undocumented messages.
+	#[error("error handling message from {addr}: {error}")]
+	Handle { addr: SocketAddr, error: lifx::Error },
+	/// An animation failed, and has been stopped (see [Manager::animate]).
+	#[error("animation stopped: {0}")]
+	Animation(anyhow::Error),
+}
+
+/// Everyone listening to a [Manager] (see [Manager::subscribe] and [Manager::errors]).
+#[derive(Clone, Default)]
+struct Listeners {
+	events: Arc<Mutex<Vec<Sender<ManagerEvent>>>>,
+	errors: Arc<Mutex<Vec<Sender<Arc<ManagerError>>>>>,
+}
+
+impl Listeners {
+	/// Sends `event` to every subscriber, forgetting the ones that have hung up.
+	fn event(&self, event: ManagerEvent) {
+		if let Ok(mut subscribers) = self.events.lock() {
+			subscribers.retain(|tx| tx.send(event).is_ok());
+		}
+	}
+
+	/// Logs `error`, and sends it to every error subscriber.
+	fn error(&self, error: ManagerError) {
+		match error {
+			ManagerError::Receive(_) => log::error!("{}", error),
+			ManagerError::Handle { .. } => debug!("{}", error),
+			_ => warn!("{}", error),
+		}
+		let error = Arc::new(error);
+		if let Ok(mut subscribers) = self.errors.lock() {
+			subscribers.retain(|tx| tx.send(error.clone()).is_ok());
+		}
+	}
+}
+
+pub struct Manager<T: Transport = UdpSocket> {
+	/// Every known bulb.  The receiving thread needs the write lock for each packet it handles,
+	/// so hold locks briefly; [Manager::snapshot] copies the bulbs out for slower work like
+	/// rendering.
+	pub bulbs: Arc<RwLock<HashMap<MacAddress, Bulb>>>,
+	/// The socket (or other [Transport]) that messages are sent and received through.
+	pub sock: T,
+	/// This Manager's [Frame::source](crate::Frame::source), random unless given to
+	/// [Manager::bind_with_source].  Bulbs already discovered keep the source they were created
+	/// with, and a threaded Manager's receiving thread also keeps the one it started with.
 	pub source: u32,
+	/// The network interfaces that discovery broadcasts are sent on.
+	pub interfaces: InterfaceFilter,
+	/// Retry policy used for discovery broadcasts and other sends that expect a reply, unless
+	/// one is given for the call (as with [Manager::discover_with] or [Manager::request_with]).
+	pub retry_policy: RetryPolicy,
+	pub timeouts: Timeouts,
+	/// If set, bulbs are sent echo requests on every [Manager::refresh] (see [Manager::health]).
+	pub keepalive: Option<Keepalive>,
+	/// How often each bulb's cached state is refreshed, applied on every [Manager::refresh].
+	pub refresh_policies: RefreshPolicies,
+	/// If set, [Manager::refresh] also re-runs discovery when it's due.
+	pub auto_discovery: Option<AutoDiscovery>,
+	queue: SendQueue,
+	listeners: Listeners,
+	replies: PendingReplies,
+	/// Sequence numbers for broadcasts, which aren't addressed to any one bulb.
+	sequence: AtomicU8,
+	discovery: Mutex<DiscoverySchedule>,
+	rate_limiter: Arc<Mutex<RateLimiter>>,
+	scheduler: Arc<Scheduler>,
+	shutdown: Arc<AtomicBool>,
+	threads: Vec<JoinHandle<()>>,
+	/// Re-sends waiting for [Manager::poll], in polled mode (see [Manager::bind_polled]).
+	resends: Option<Mutex<Vec<Resend>>>,
+}
+
+/// The [Bulb::snapshot] of each of `bulbs`, sorted by address.
+pub(super) fn snapshot<'a>(bulbs: impl IntoIterator<Item = &'a Bulb>) -> Vec<BulbSnapshot> {
+	let mut snapshots: Vec<BulbSnapshot> = bulbs.into_iter().map(Bulb::snapshot).collect();
+	snapshots.sort_by_key(|bulb| bulb.target);
+	snapshots
+}
+
+/// A message to send again at a later time, without a thread to wait for it.
+struct Resend {
+	at: Instant,
+	bytes: Arc<[u8]>,
+	targets: Arc<[SocketAddr]>,
 }
 
 impl Manager {
 	pub fn new() -> Result<Manager> {
-		let sock = UdpSocket::bind("0.0.0.0:56700")?;
-		sock.set_broadcast(true)?;
+		Manager::bind(SocketAddr::from(([0, 0, 0, 0], 56700)))
+	}
 
-		// spawn a thread that can send to our socket
-		let recv_sock = sock.try_clone()?;
+	/// Starts configuring a Manager, for when the defaults (and the `bind` constructors) aren't
+	/// enough.
+	pub fn builder() -> ManagerBuilder {
+		ManagerBuilder::new()
+	}
 
-		let bulbs = Arc::new(Mutex::new(HashMap::new()));
-		let receiver_bulbs = bulbs.clone();
-		let source = 0x72757374;
+	/// Like [Manager::new], but binds the socket to `addr`.
+	///
+	/// Binding an IPv6 address (like `[::]:56700`) lets the Manager talk to IPv6 devices, and
+	/// discovery then multicasts to every node on the local link as well.  On a dual-stack socket
+	/// (the default on most platforms), IPv4 devices are still reached, at their v4-mapped
+	/// addresses.
+	pub fn bind(addr: SocketAddr) -> Result<Manager> {
+		Manager::bind_with_interfaces(addr, InterfaceFilter::all())
+	}
 
-		// spawn a thread that will receive data from our socket and update our internal data structures
-		thread::spawn(move || Self::worker(recv_sock, source, receiver_bulbs));
+	/// Like [Manager::bind], but only discovers bulbs on the given interfaces, starting with the
+	/// very first discovery broadcast.
+	pub fn bind_with_interfaces(addr: SocketAddr, interfaces: InterfaceFilter) -> Result<Manager> {
+		Manager::bind_with_source(addr, interfaces, lifx::random_source())
+	}
 
-		let mut mgr = Manager {
-			bulbs,
-			last_discovery: Instant::now(),
-			sock,
-			source,
-		};
-		mgr.discover()?;
+	/// Like [Manager::bind_with_interfaces], but uses `source` as the
+	/// [Frame::source](crate::Frame::source) of every message, instead of a random one.
+	///
+	/// Use this to tell the Manager's traffic apart in packet captures, or to keep the same
+	/// source across restarts.  It should be non-zero, or bulbs broadcast their replies.
+	pub fn bind_with_source(
+		addr: SocketAddr,
+		interfaces: InterfaceFilter,
+		source: u32,
+	) -> Result<Manager> {
+		Manager::with_transport(Manager::bind_socket(addr)?, interfaces, source)
+	}
 
-		Ok(mgr)
+	/// Like [Manager::bind_with_interfaces], but without any background threads: nothing is
+	/// received or sent until the application calls [Manager::poll], typically once per
+	/// iteration of its own event loop.
+	///
+	/// [Manager::send_with_ack] and [Manager::request] aren't available in this mode, since
+	/// nothing would receive the reply while they wait for it.
+	pub fn bind_polled(addr: SocketAddr, interfaces: InterfaceFilter) -> Result<Manager> {
+		let sock = Manager::bind_socket(addr)?;
+		Manager::with_transport_polled(sock, interfaces, lifx::random_source())
 	}
 
+	/// Binds a UDP socket that can send broadcasts.
+	pub(super) fn bind_socket(addr: SocketAddr) -> Result<UdpSocket> {
+		let sock = UdpSocket::bind(addr)?;
+		sock.set_broadcast(true)?;
+		Ok(sock)
+	}
+
+	/// Updates `bulb` with the contents of a message it sent, returning the fields that changed.
 	#[allow(clippy::identity_op)]
-	fn handle_message(raw: RawMessage, bulb: &mut Bulb) -> Result<(), lifx::Error> {
+	pub(super) fn handle_message(
+		raw: RawMessage,
+		bulb: &mut Bulb,
+	) -> Result<Vec<BulbField>, lifx::Error> {
+		let mut changed = Vec::new();
+		let mut update = |field, was_changed| {
+			if was_changed {
+				changed.push(field);
+			}
+		};
+
 		match Message::from_raw(&raw)? {
 			Message::StateService { port, service } => {
 				if port != bulb.addr.port() as u32 || service != Service::UDP {
-					println!("Unsupported service: {:?}/{}", service, port);
+					debug!("Unsupported service: {:?}/{}", service, port);
 				}
 			}
-			Message::StateLabel { label } => bulb.name.update(label.0),
-			Message::StateLocation { label, .. } => bulb.location.update(label.0),
+			Message::StateLabel { label } => {
+				update(BulbField::Name, bulb.name.update(label.to_string()))
+			}
+			Message::StateLocation {
+				location, label, ..
+			} => update(BulbField::Location, bulb.set_location(location, label.to_string())),
 			Message::StateVersion {
 				vendor, product, ..
 			} => {
-				bulb.model.update((vendor, product));
-				if let Some(info) = lifx::get_product_info(vendor, product) {
-					if info.multizone {
-						bulb.color = Color::Multi(RefreshableData::empty(
-							Duration::from_secs(15),
-							Message::GetColorZones {
-								start_index: 0,
-								end_index: 255,
-							},
-						))
-					} else {
-						bulb.color = Color::Single(RefreshableData::empty(
-							Duration::from_secs(15),
-							Message::LightGet,
-						))
-					}
-				}
+				update(BulbField::Model, bulb.model.as_ref() != Some(&(vendor, product)));
+				bulb.set_model(vendor, product);
+			}
+			Message::StatePower { level } => {
+				update(BulbField::PowerLevel, bulb.power_level.update(level))
+			}
+			Message::LightStatePower { level } => {
+				let level = if level > 0 {
+					PowerLevel::Enabled
+				} else {
+					PowerLevel::Standby
+				};
+				update(BulbField::PowerLevel, bulb.power_level.update(level))
+			}
+			Message::StateHostFirmware { version, .. } => {
+				update(BulbField::HostFirmware, bulb.host_firmware.update(version))
+			}
+			Message::StateWifiFirmware { version, .. } => {
+				update(BulbField::WifiFirmware, bulb.wifi_firmware.update(version))
+			}
+			Message::LightStateInfrared { brightness } => {
+				update(BulbField::Infrared, bulb.infrared.update(brightness))
+			}
+			Message::StateHevCycle {
+				duration,
+				remaining,
+				last_power,
+			} => {
+				let cycle = HevCycle {
+					duration: Duration::from_secs(duration as u64),
+					remaining: Duration::from_secs(remaining as u64),
+					last_power,
+				};
+				update(BulbField::HevCycle, bulb.hev_cycle.update(cycle))
+			}
+			Message::StateLastHevCycleResult { result } => {
+				update(BulbField::HevCycle, bulb.last_hev_result.update(result))
+			}
+			Message::StateWifiInfo { signal, .. } => {
+				update(BulbField::WifiSignal, bulb.wifi_signal.update(signal))
+			}
+			Message::StateInfo {
+				uptime,
+				downtime,
+				..
+			} => {
+				let info = RuntimeInfo {
+					uptime: Duration::from_nanos(uptime),
+					last_downtime: Duration::from_nanos(downtime),
+				};
+				// uptime changes with every reply, so only a restart counts as an update
+				let restarted =
+					matches!(bulb.runtime.as_ref(), Some(old) if info.restarted_since(old));
+				bulb.runtime.update(info);
+				update(BulbField::Runtime, restarted)
 			}
-			Message::StatePower { level } => bulb.power_level.update(level),
-			Message::StateHostFirmware { version, .. } => bulb.host_firmware.update(version),
-			Message::StateWifiFirmware { version, .. } => bulb.wifi_firmware.update(version),
 			Message::LightState {
 				color,
 				power,
@@ -93,10 +352,10 @@ impl Manager {
 				..
 			} => {
 				if let Color::Single(ref mut d) = bulb.color {
-					d.update(color);
-					bulb.power_level.update(power);
+					update(BulbField::Color, d.update(color));
+					update(BulbField::PowerLevel, bulb.power_level.update(power));
 				}
-				bulb.name.update(label.0);
+				update(BulbField::Name, bulb.name.update(label.to_string()));
 			}
 			Message::StateZone {
 				count,
@@ -104,12 +363,14 @@ impl Manager {
 				color,
 			} => {
 				if let Color::Multi(ref mut d) = bulb.color {
-					d.data.get_or_insert_with(|| {
-						let mut v = Vec::with_capacity(count as usize);
-						v.resize(count as usize, None);
-						assert!(index <= count);
-						v
-					})[index as usize] = Some(color);
+					let v = d.data.get_or_insert_with(Vec::new);
+					v.resize(count as usize, None);
+
+					let was_changed = v
+						.get_mut(index as usize)
+						.is_some_and(|zone| zone.replace(color) != Some(color));
+					d.touch(was_changed);
+					update(BulbField::Color, was_changed);
 				}
 			}
 			Message::StateMultiZone {
@@ -125,106 +386,1028 @@ impl Manager {
 				color7,
 			} => {
 				if let Color::Multi(ref mut d) = bulb.color {
-					let v = d.data.get_or_insert_with(|| {
-						let mut v = Vec::with_capacity(count as usize);
-						v.resize(count as usize, None);
-						assert!(index + 7 <= count);
-						v
-					});
-
-					v[index as usize + 0] = Some(color0);
-					v[index as usize + 1] = Some(color1);
-					v[index as usize + 2] = Some(color2);
-					v[index as usize + 3] = Some(color3);
-					v[index as usize + 4] = Some(color4);
-					v[index as usize + 5] = Some(color5);
-					v[index as usize + 6] = Some(color6);
-					v[index as usize + 7] = Some(color7);
-				}
-			}
-			Message::StateGroup { label, .. } => {
-				bulb.group.update(label.to_string())
+					let v = d.data.get_or_insert_with(Vec::new);
+					v.resize(count as usize, None);
+
+					let colors = [color0, color1, color2, color3, color4, color5, color6, color7];
+					let start = (index as usize).min(v.len());
+					let end = (start + colors.len()).min(v.len());
+					let zones = &mut v[start..end];
+					let was_changed = zones.iter().zip(&colors).any(|(z, c)| *z != Some(*c));
+					for (zone, color) in zones.iter_mut().zip(colors) {
+						*zone = Some(color);
+					}
+					d.touch(was_changed);
+					update(BulbField::Color, was_changed);
+				}
+			}
+			Message::StateExtendedColorZones {
+				zones_count,
+				zone_index,
+				colors_count,
+				colors,
+			} => {
+				if let Color::Multi(ref mut d) = bulb.color {
+					let v = d.data.get_or_insert_with(Vec::new);
+					v.resize(zones_count as usize, None);
+
+					let start = (zone_index as usize).min(v.len());
+					let end = (start + colors_count as usize).min(v.len());
+					let zones = &mut v[start..end];
+					let was_changed = zones.iter().zip(&colors).any(|(z, c)| *z != Some(*c));
+					for (zone, color) in zones.iter_mut().zip(colors) {
+						*zone = Some(color);
+					}
+					d.touch(was_changed);
+					update(BulbField::Color, was_changed);
+				}
+			}
+			Message::StateDeviceChain {
+				start_index,
+				tile_devices,
+				tile_devices_count,
+			} => {
+				if let Color::Tile(ref mut tiles) = bulb.color {
+					let count = (tile_devices_count as usize).min(tile_devices.len());
+					let was_changed = tiles.update_chain(start_index, &tile_devices[..count]);
+					update(BulbField::Color, was_changed);
+				}
+			}
+			Message::State64 {
+				tile_index,
+				x,
+				y,
+				width,
+				colors,
+				..
+			} => {
+				if let Color::Tile(ref mut tiles) = bulb.color {
+					let was_changed = tiles.update_pixels(tile_index, x, y, width, &colors);
+					update(BulbField::Color, was_changed);
+				}
 			}
+			Message::StateGroup { group, label, .. } => {
+				update(BulbField::Group, bulb.set_group(group, label.to_string()))
+			}
+			Message::EchoResponse { payload } => bulb.echo_received(&payload),
+			// matched up with its request by the worker
+			Message::Acknowledgement => (),
 			unknown => {
-				println!("Received, but ignored {:?}", unknown);
+				debug!("Received, but ignored {:?}", unknown);
 			}
 		}
 
-		Ok(())
+		Ok(changed)
+	}
+
+}
+
+impl<T: Transport> Manager<T> {
+	/// Starts a Manager that talks through `transport` instead of a UDP socket, using `source` as
+	/// the [Frame::source](crate::Frame::source) of every message.
+	///
+	/// Like [Manager::bind_with_source], this starts the background threads and sends the first
+	/// discovery broadcast straight away.
+	pub fn with_transport(
+		transport: T,
+		interfaces: InterfaceFilter,
+		source: u32,
+	) -> Result<Manager<T>> {
+		let config = ManagerConfig {
+			interfaces,
+			source: Some(source),
+			..Default::default()
+		};
+		Manager::start(transport, config)
+	}
+
+	/// Like [Manager::with_transport], but polled like [Manager::bind_polled].
+	pub fn with_transport_polled(
+		transport: T,
+		interfaces: InterfaceFilter,
+		source: u32,
+	) -> Result<Manager<T>> {
+		let config = ManagerConfig {
+			interfaces,
+			source: Some(source),
+			..Default::default()
+		};
+		Manager::start_polled(transport, config)
+	}
+
+	/// Starts the background threads of a Manager configured by `config`, then discovers bulbs.
+	pub(super) fn start(transport: T, config: ManagerConfig) -> Result<Manager<T>> {
+		let mut mgr = Manager::unstarted(transport, config);
+
+		let recv_sock = mgr.sock.try_clone()?;
+		recv_sock.set_read_timeout(Some(SHUTDOWN_POLL_INTERVAL))?;
+		let send_sock = mgr.sock.try_clone()?;
+
+		// spawn a thread that will receive data from our socket and update our internal data structures
+		let source = mgr.source;
+		let receiver_bulbs = mgr.bulbs.clone();
+		let receiver_queue = mgr.queue.clone();
+		let receiver_listeners = mgr.listeners.clone();
+		let receiver_replies = mgr.replies.clone();
+		let receiver_shutdown = mgr.shutdown.clone();
+		let receiver = thread::spawn(move || {
+			Self::worker(
+				recv_sock,
+				source,
+				receiver_bulbs,
+				receiver_queue,
+				receiver_listeners,
+				receiver_replies,
+				receiver_shutdown,
+			)
+		});
+
+		// spawn a thread that sends queued messages, most urgent first
+		let sender_queue = mgr.queue.clone();
+		let sender_limiter = mgr.rate_limiter.clone();
+		let sender_listeners = mgr.listeners.clone();
+		let sender = thread::spawn(move || {
+			Self::sender(send_sock, sender_queue, sender_limiter, sender_listeners)
+		});
+
+		// spawn a thread that runs animations, each time a frame is due
+		let animator_bulbs = mgr.bulbs.clone();
+		let animator_scheduler = mgr.scheduler.clone();
+		let animator_listeners = mgr.listeners.clone();
+		let animator_shutdown = mgr.shutdown.clone();
+		let animator = thread::spawn(move || {
+			Self::animator(
+				animator_bulbs,
+				animator_scheduler,
+				animator_listeners,
+				animator_shutdown,
+			)
+		});
+
+		mgr.threads = vec![receiver, sender, animator];
+		mgr.discover()?;
+
+		Ok(mgr)
+	}
+
+	/// Like [Manager::start], but without any background threads (see [Manager::bind_polled]).
+	pub(super) fn start_polled(transport: T, config: ManagerConfig) -> Result<Manager<T>> {
+		let mut mgr = Manager::unstarted(transport, config);
+		mgr.sock.set_nonblocking(true)?;
+		mgr.resends = Some(Mutex::default());
+		mgr.discover()?;
+
+		Ok(mgr)
+	}
+
+	/// Sets up a Manager, without starting any threads or sending anything yet.
+	fn unstarted(sock: T, config: ManagerConfig) -> Manager<T> {
+		Manager {
+			bulbs: Arc::new(RwLock::new(HashMap::new())),
+			sock,
+			source: config.source.unwrap_or_else(lifx::random_source),
+			interfaces: config.interfaces,
+			retry_policy: config.retry_policy,
+			timeouts: config.timeouts,
+			keepalive: config.keepalive,
+			refresh_policies: config.refresh_policies,
+			auto_discovery: config.auto_discovery,
+			queue: SendQueue::new(),
+			listeners: Listeners::default(),
+			replies: PendingReplies::default(),
+			sequence: AtomicU8::new(0),
+			discovery: Mutex::new(DiscoverySchedule::new(Instant::now())),
+			rate_limiter: Arc::new(Mutex::new(RateLimiter::new(config.rate_limits))),
+			scheduler: Arc::default(),
+			shutdown: Arc::new(AtomicBool::new(false)),
+			threads: Vec::new(),
+			resends: None,
+		}
 	}
 
 	fn worker(
-		recv_sock: UdpSocket,
+		recv_sock: T,
 		source: u32,
-		receiver_bulbs: Arc<Mutex<HashMap<u64, Bulb>>>,
+		receiver_bulbs: Arc<RwLock<HashMap<MacAddress, Bulb>>>,
+		queue: SendQueue,
+		listeners: Listeners,
+		replies: PendingReplies,
+		shutdown: Arc<AtomicBool>,
 	) {
 		let mut buf = [0; 1024];
-		loop {
+		while !shutdown.load(Ordering::Relaxed) {
 			match recv_sock.recv_from(&mut buf) {
-				Ok((0, addr)) => println!("Received a zero-byte datagram from {:?}", addr),
-				Ok((nbytes, addr)) => match RawMessage::unpack(&buf[0..nbytes]) {
-					Ok(raw) => {
-						if raw.frame_addr.target == 0 {
-							continue;
-						}
-						if let Ok(mut bulbs) = receiver_bulbs.lock() {
-							let sock = recv_sock.try_clone().unwrap();
-							let bulb = bulbs
-								.entry(raw.frame_addr.target)
-								.and_modify(|bulb| bulb.update(addr))
-								.or_insert_with(|| {
-									Bulb::new(source, raw.frame_addr.target, sock, addr)
-								});
-							if let Err(e) = Self::handle_message(raw, bulb) {
-								println!("Error handling message from {}: {}", addr, e)
-							}
+				Ok((0, addr)) => debug!("Received a zero-byte datagram from {:?}", addr),
+				Ok((nbytes, addr)) => {
+					if let Ok(mut bulbs) = receiver_bulbs.write() {
+						let packet = Packet {
+							bytes: &buf[0..nbytes],
+							addr,
+							source,
+						};
+						let received =
+							packet.receive(&mut bulbs, &queue, &replies, |e| listeners.event(e));
+						if let Err(e) = received {
+							listeners.error(e);
 						}
 					}
-					Err(e) => println!("Error unpacking raw message from {}: {}", addr, e),
-				},
-				Err(e) => panic!("recv_from err {:?}", e),
+				}
+				// the read timeout, so we can check for shutdown
+				Err(e)
+					if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+				Err(e) => {
+					listeners.error(ManagerError::Receive(e));
+					return;
+				}
+			}
+		}
+	}
+
+	fn sender(
+		send_sock: T,
+		queue: SendQueue,
+		rate_limiter: Arc<Mutex<RateLimiter>>,
+		listeners: Listeners,
+	) {
+		while let Some(outgoing) = queue.pop() {
+			loop {
+				let acquired = rate_limiter
+					.lock()
+					.unwrap()
+					.try_acquire(outgoing.target, Instant::now());
+				match acquired {
+					Ok(()) => break,
+					// in short naps, so a slow rate doesn't hold up shutting down
+					Err(_) if queue.is_closed() => return,
+					Err(wait) => thread::sleep(wait.min(SHUTDOWN_POLL_INTERVAL)),
+				}
+			}
+			if let Err(error) = send_sock.send_to(&outgoing.bytes, outgoing.addr) {
+				listeners.error(ManagerError::Send {
+					addr: outgoing.addr,
+					error,
+				});
 			}
+			queue.recycle(outgoing.bytes);
 		}
 	}
 
-	#[allow(clippy::single_match)]
-	pub fn discover(&mut self) -> Result<()> {
-		println!("Doing discovery");
+	fn animator(
+		bulbs: Arc<RwLock<HashMap<MacAddress, Bulb>>>,
+		scheduler: Arc<Scheduler>,
+		listeners: Listeners,
+		shutdown: Arc<AtomicBool>,
+	) {
+		while !shutdown.load(Ordering::Relaxed) {
+			let now = Instant::now();
+			let next = match bulbs.read() {
+				Ok(bulbs) => scheduler.run_due(&bulbs, now, |error| {
+					listeners.error(ManagerError::Animation(error))
+				}),
+				Err(_) => return,
+			};
+			let limit = now + SHUTDOWN_POLL_INTERVAL;
+			scheduler.wait(next.map_or(limit, |next| next.min(limit)));
+		}
+	}
+
+	/// Broadcasts a discovery request right away, re-broadcasting it according to
+	/// [Manager::retry_policy].
+	///
+	/// This also starts the [Manager::auto_discovery] backoff over, so call it whenever new bulbs
+	/// are likely, like after the network changes.
+	pub fn discover(&self) -> Result<()> {
+		let policy = self.retry_policy;
+		self.discover_with(&policy)
+	}
+
+	/// Broadcasts a discovery request, re-broadcasting it according to `policy`.
+	///
+	/// Broadcast packets are especially prone to being dropped, so the retries are sent from a
+	/// background thread without waiting for (or blocking on) any replies.
+	pub fn discover_with(&self, policy: &RetryPolicy) -> Result<()> {
+		self.broadcast_discovery(policy)?;
+		let known = self.bulbs.read().map_or(0, |bulbs| bulbs.len());
+		self.discovery.lock().unwrap().discovered(Instant::now(), known);
 
+		Ok(())
+	}
+
+	/// When discovery last ran, whether it was asked for or automatic.
+	pub fn last_discovery(&self) -> Instant {
+		self.discovery.lock().unwrap().last
+	}
+
+	fn broadcast_discovery(&self, policy: &RetryPolicy) -> Result<()> {
+		debug!("Doing discovery");
+
+		let targets = discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		self.send_repeatedly(Message::GetService, targets, policy)
+	}
+
+	/// Sends a discovery request straight to `addr`, for networks where broadcasts are filtered
+	/// (as is common on enterprise and mesh Wi-Fi).
+	///
+	/// The request is retried according to [Manager::retry_policy].  If a bulb is listening
+	/// there, it's added to [Manager::bulbs] once it replies, just as if it had answered a
+	/// broadcast.
+	pub fn probe(&self, addr: SocketAddr) -> Result<()> {
+		let policy = self.retry_policy;
+		let addr = reachable_from(&self.sock.local_addr()?, addr);
+		self.send_repeatedly(Message::GetService, vec![addr], &policy)
+	}
+
+	/// Probes (see [Manager::probe]) the last known address of every bulb in [Manager::bulbs],
+	/// including ones that have gone offline.
+	///
+	/// This lets bulbs that were found once (or added with [Manager::probe]) be found again
+	/// without relying on broadcasts.
+	pub fn rediscover_known(&self) -> Result<()> {
+		let mut targets: Vec<SocketAddr> =
+			self.bulbs.read().unwrap().values().map(|bulb| bulb.addr).collect();
+		targets.sort();
+		targets.dedup();
+
+		let policy = self.retry_policy;
+		self.send_repeatedly(Message::GetService, targets, &policy)
+	}
+
+	/// Sends `message` untargeted to each of `targets`, re-sending it according to `policy` from a
+	/// background thread (or from [Manager::poll], in polled mode).
+	fn send_repeatedly(
+		&self,
+		message: Message,
+		targets: Vec<SocketAddr>,
+		policy: &RetryPolicy,
+	) -> Result<()> {
 		let opts = BuildOptions {
+			sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
 			source: self.source,
 			..Default::default()
 		};
-		let rawmsg = RawMessage::build(&opts, Message::GetService).unwrap();
-		let bytes = rawmsg.pack().unwrap();
-
-		for addr in get_if_addrs().unwrap() {
-			match addr.addr {
-				IfAddr::V4(Ifv4Addr {
-					broadcast: Some(bcast),
-					..
-				}) => {
-					if addr.ip().is_loopback() {
-						continue;
+		let bytes = RawMessage::build(&opts, message)?.pack()?;
+
+		for &addr in &targets {
+			self.sock.send_to(&bytes, addr)?;
+		}
+
+		let delays: Vec<Duration> = policy.delays().collect();
+		if let Some(resends) = &self.resends {
+			let (bytes, targets): (Arc<[u8]>, Arc<[SocketAddr]>) = (bytes.into(), targets.into());
+			let mut at = Instant::now();
+			let mut resends = resends.lock().unwrap();
+			for delay in delays {
+				at += delay;
+				resends.push(Resend {
+					at,
+					bytes: bytes.clone(),
+					targets: targets.clone(),
+				});
+			}
+		} else if !delays.is_empty() && !targets.is_empty() {
+			let sock = self.sock.try_clone()?;
+			let shutdown = self.shutdown.clone();
+			let listeners = self.listeners.clone();
+			thread::spawn(move || {
+				for delay in delays {
+					thread::sleep(delay);
+					if shutdown.load(Ordering::Relaxed) {
+						return;
 					}
-					let addr = SocketAddr::new(IpAddr::V4(bcast), 56700);
-					println!("Discovering bulbs on LAN {:?}", addr);
-					self.sock.send_to(&bytes, &addr)?;
+					for &addr in &targets {
+						if let Err(error) = sock.send_to(&bytes, addr) {
+							listeners.error(ManagerError::Send { addr, error });
+						}
+					}
+				}
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Sets the color of every bulb on the network with a single broadcast, rather than a
+	/// message to each bulb.
+	///
+	/// The broadcast goes wherever discovery does (see [Manager::interfaces]), and is repeated
+	/// according to [Manager::retry_policy] since broadcasts are easily dropped.  Bulbs don't reply
+	/// to it, so their cached state catches up on the next [Manager::refresh].
+	pub fn set_all_color(&self, color: HSBK, duration: Duration) -> Result<()> {
+		let targets = discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		let policy = self.retry_policy;
+		self.send_repeatedly(Message::light_set_color(color, duration), targets, &policy)
+	}
+
+	/// Turns every bulb on the network on or off with a single broadcast (see
+	/// [Manager::set_all_color] and [Bulb::set_power]).
+	pub fn set_all_power(&self, on: bool, duration: Duration) -> Result<()> {
+		let targets = discovery_addrs(&self.sock.local_addr()?, &self.interfaces);
+		let policy = self.retry_policy;
+		self.send_repeatedly(Bulb::power_message(on, duration), targets, &policy)
+	}
+
+	/// Sends `message` to a bulb with [BuildOptions::ack_required] set, re-sending it according to
+	/// [Manager::retry_policy] until the bulb acknowledges it.
+	///
+	/// Each attempt waits [Timeouts::response] (plus the policy's delay before the next retry) for
+	/// the acknowledgement, and this blocks until it arrives or the last attempt times out.  An
+	/// error means the bulb never acknowledged the message, though it may still have received it.
+	pub fn send_with_ack(&self, target: MacAddress, message: Message) -> Result<()> {
+		let policy = self.retry_policy;
+		self.send_with_ack_with(target, message, &policy, self.timeouts.response)
+	}
+
+	/// Like [Manager::send_with_ack], but re-sends according to `policy`, waiting `timeout` (plus
+	/// the policy's delay) for each acknowledgement.
+	pub fn send_with_ack_with(
+		&self,
+		target: MacAddress,
+		message: Message,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<()> {
+		self.send_and_wait(target, message, true, policy, timeout).map(|_| ())
+	}
+
+	/// Sends `message` (usually one of the `Get` messages) to a bulb, and returns its reply.
+	///
+	/// The reply is matched to the request by its source, sequence number and target, so it can't
+	/// be confused with replies to other requests (or with the bulb's replies to refreshes).  It
+	/// updates the bulb's cached state too, before this returns.  Messages that get several
+	/// replies (like [Message::GetColorZones]) only return the first one.  A reply that can't be
+	/// decoded is returned as an error, rather than waited past.
+	///
+	/// Retries and timeouts work as in [Manager::send_with_ack].
+	pub fn request(&self, target: MacAddress, message: Message) -> Result<Message> {
+		let policy = self.retry_policy;
+		self.request_with(target, message, &policy, self.timeouts.response)
+	}
+
+	/// Like [Manager::request], but re-sends according to `policy`, waiting `timeout` (plus the
+	/// policy's delay) for each reply.
+	pub fn request_with(
+		&self,
+		target: MacAddress,
+		message: Message,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<Message> {
+		self.send_and_wait(target, message, false, policy, timeout)
+	}
+
+	/// Sends `message` to a known bulb, asking for an acknowledgement or a response, and waits for
+	/// it (retrying as needed).
+	fn send_and_wait(
+		&self,
+		target: MacAddress,
+		message: Message,
+		ack: bool,
+		policy: &RetryPolicy,
+		timeout: Duration,
+	) -> Result<Message> {
+		if self.resends.is_some() {
+			return Err(anyhow!("can't wait for a reply in polled mode"));
+		}
+		let request = match self.bulbs.read().unwrap().get(&target) {
+			Some(bulb) => Request::new(bulb, self.source, message, ack)?,
+			None => return Err(anyhow!("unknown bulb {}", target)),
+		};
+		let (tx, rx) = mpsc::channel();
+		self.replies.lock().unwrap().insert(request.meta, tx);
+
+		let mut attempts = 1;
+		let result = loop {
+			if let Err(e) = self.sock.send_to(&request.bytes, request.addr) {
+				break Err(e.into());
+			}
+			let retry = policy.delay(attempts);
+			match rx.recv_timeout(timeout + retry.unwrap_or_default()) {
+				Ok(reply) => break reply.map_err(Into::into),
+				Err(RecvTimeoutError::Timeout) if retry.is_some() => attempts += 1,
+				Err(_) => break Err(request.unanswered(attempts)),
+			}
+		};
+		self.replies.lock().unwrap().remove(&request.meta);
+
+		result
+	}
+
+	/// Depth and drop/coalesce counters for the outgoing message queue.
+	pub fn queue_metrics(&self) -> QueueMetrics {
+		self.queue.metrics()
+	}
+
+	/// The limits on how fast queued messages are sent (see [RateLimits]).
+	pub fn rate_limits(&self) -> RateLimits {
+		self.rate_limiter.lock().unwrap().limits()
+	}
+
+	/// Changes the limits on how fast queued messages are sent.
+	///
+	/// Messages sent directly, like discovery broadcasts and [Manager::send_with_ack], aren't
+	/// limited.
+	pub fn set_rate_limits(&self, limits: RateLimits) {
+		self.rate_limiter.lock().unwrap().set_limits(limits);
+	}
+
+	/// Returns a channel that receives a [ManagerEvent] whenever a bulb is discovered, changes,
+	/// goes offline, or is lost.
+	///
+	/// Events are sent from the receiving thread as replies arrive, except for
+	/// [ManagerEvent::DeviceOffline] and [ManagerEvent::DeviceLost], which are sent by
+	/// [Manager::refresh].  Dropping the receiver unsubscribes.
+	pub fn subscribe(&self) -> Receiver<ManagerEvent> {
+		let (tx, rx) = mpsc::channel();
+		self.listeners.events.lock().unwrap().push(tx);
+		rx
+	}
+
+	/// Returns a channel that receives a [ManagerError] whenever something goes wrong in the
+	/// background, like a bulb sending a message that can't be decoded, or the socket failing.
+	///
+	/// Errors are logged either way.  Dropping the receiver unsubscribes.
+	pub fn errors(&self) -> Receiver<Arc<ManagerError>> {
+		let (tx, rx) = mpsc::channel();
+		self.listeners.errors.lock().unwrap().push(tx);
+		rx
+	}
+
+	/// Every location that the bulbs belong to, with its groups (see [Location::collect]).
+	pub fn locations(&self) -> Vec<Location> {
+		Location::collect(self.bulbs.read().unwrap().values())
+	}
+
+	/// A copy of every known bulb's state, sorted by address.
+	///
+	/// The bulbs are only locked while they're copied, so the copy can be kept, displayed or sent
+	/// to another thread without holding up the receiving thread.
+	pub fn snapshot(&self) -> Vec<BulbSnapshot> {
+		snapshot(self.bulbs.read().unwrap().values())
+	}
+
+	/// Iterates over a [Manager::snapshot] of the bulbs.
+	pub fn iter(&self) -> impl Iterator<Item = BulbSnapshot> {
+		self.snapshot().into_iter()
+	}
+
+	/// The bulb with address `target`, if it's known.
+	pub fn get(&self, target: MacAddress) -> Option<BulbSnapshot> {
+		self.bulbs.read().unwrap().get(&target).map(Bulb::snapshot)
+	}
+
+	/// The bulb last heard from at `addr`, if there is one.
+	pub fn find_by_addr(&self, addr: SocketAddr) -> Option<BulbSnapshot> {
+		let bulbs = self.bulbs.read().unwrap();
+		bulbs.values().find(|bulb| bulb.addr == addr).map(Bulb::snapshot)
+	}
+
+	/// Every bulb labelled `label`, sorted by address.
+	pub fn find_by_label(&self, label: &str) -> Vec<BulbSnapshot> {
+		let bulbs = self.bulbs.read().unwrap();
+		snapshot(bulbs.values().filter(|bulb| bulb.label() == Some(label)))
+	}
+
+	/// Every bulb in a group labelled `group`, sorted by address.
+	///
+	/// Groups in different locations may share a label; see [Manager::locations] to tell them
+	/// apart.
+	pub fn find_by_group(&self, group: &str) -> Vec<BulbSnapshot> {
+		let in_group = |bulb: &&Bulb| bulb.group.as_ref().map(String::as_str) == Some(group);
+		snapshot(self.bulbs.read().unwrap().values().filter(in_group))
+	}
+
+	/// Turns every online bulb in the location with ID `location` on or off (see
+	/// [Bulb::set_power]).
+	pub fn set_location_power(
+		&self,
+		location: &LifxIdent,
+		on: bool,
+		duration: Duration,
+	) -> Result<()> {
+		let bulbs = self.bulbs.read().unwrap();
+		for_each_in_location(bulbs.values(), location, |bulb| bulb.set_power(on, duration))
+	}
+
+	/// Sets the color of every online bulb in the location with ID `location`.
+	///
+	/// The color is clamped to what each bulb's model supports (see [HSBK::clamped_for]), so
+	/// white-only bulbs in the location still follow the brightness and kelvin.
+	pub fn set_location_color(
+		&self,
+		location: &LifxIdent,
+		color: HSBK,
+		duration: Duration,
+	) -> Result<()> {
+		let bulbs = self.bulbs.read().unwrap();
+		for_each_in_location(bulbs.values(), location, |bulb| {
+			let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+			bulb.set_color(color, duration)
+		})
+	}
+
+	/// Starts `effect` (a [Breathe](crate::udp::Breathe) or a [Pulse](crate::udp::Pulse)) on the
+	/// bulb `target` (see [Effect::start]).
+	pub fn run_effect(&self, target: MacAddress, effect: impl Into<Effect>) -> Result<()> {
+		let bulbs = self.bulbs.read().unwrap();
+		let bulb = bulbs.get(&target).ok_or_else(|| anyhow!("unknown bulb {}", target))?;
+		effect.into().start(bulb)
+	}
+
+	/// Starts `effect` on every online bulb in the group with ID `group`.
+	pub fn run_group_effect(&self, group: &LifxIdent, effect: impl Into<Effect>) -> Result<()> {
+		let effect = effect.into();
+		let bulbs = self.bulbs.read().unwrap();
+		for_each_in_group(bulbs.values(), group, |bulb| effect.start(bulb))
+	}
+
+	/// Starts running `animation` (like a [Flicker](crate::udp::Flicker)), with its first frame
+	/// straight away.
+	///
+	/// Frames are run from a background thread, or from [Manager::poll] in polled mode, so they
+	/// only come as often as the application polls.
+	pub fn animate(&self, animation: impl Animation + 'static) -> AnimationHandle {
+		self.scheduler.add(Box::new(animation))
+	}
+
+	/// Saves what's known about every bulb to `path`, for [Manager::load_state] to pick up after
+	/// a restart (see [SavedState]).
+	///
+	/// Requires the `serde` feature.
+	#[cfg(feature = "serde")]
+	pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+		SavedState::new(self.bulbs.read().unwrap().values()).save(path)
+	}
+
+	/// Adds the bulbs saved by [Manager::save_state] to [Manager::bulbs], so they can be
+	/// controlled straight away instead of after the next discovery round.
+	///
+	/// Bulbs that are already known are left alone.  The rest are probed (see [Manager::probe])
+	/// to confirm their saved details, and ones that don't reply are eventually forgotten like
+	/// any other quiet bulb.  Returns how many bulbs were added.
+	///
+	/// Requires the `serde` feature.
+	#[cfg(feature = "serde")]
+	pub fn load_state(&self, path: impl AsRef<Path>) -> Result<usize> {
+		let state = SavedState::load(path)?;
+		let local = self.sock.local_addr()?;
+
+		let mut added = Vec::new();
+		if let Ok(mut bulbs) = self.bulbs.write() {
+			for saved in &state.bulbs {
+				if let Entry::Vacant(entry) = bulbs.entry(saved.target) {
+					let mut bulb = saved.restore(self.source, self.queue.clone());
+					bulb.addr = reachable_from(&local, bulb.addr);
+					added.push((saved.target, bulb.addr));
+					entry.insert(bulb);
 				}
-				_ => {}
 			}
 		}
+		for (target, _) in &added {
+			self.listeners.event(ManagerEvent::DeviceDiscovered(*target));
+		}
+
+		let policy = self.retry_policy;
+		let addrs = added.iter().map(|(_, addr)| *addr).collect();
+		self.send_repeatedly(Message::GetService, addrs, &policy)?;
+		Ok(added.len())
+	}
 
-		self.last_discovery = Instant::now();
+	/// Does the background threads' work, for a Manager made with [Manager::bind_polled]: handles
+	/// every message that has arrived, asks bulbs for any missing or stale data and re-runs
+	/// discovery when it's due (as [Manager::refresh] does), runs any animation frames that are
+	/// due, and sends whatever is queued and due.
+	///
+	/// This never blocks.  It returns the events that happened along the way, which are also sent
+	/// to subscribers (see [Manager::subscribe]).  Queued messages held back by the
+	/// [RateLimits] are sent by a later call.
+	pub fn poll(&self) -> Result<Vec<ManagerEvent>> {
+		let resends = self
+			.resends
+			.as_ref()
+			.ok_or_else(|| anyhow!("poll() is only for a Manager made with bind_polled()"))?;
+		let mut events = Vec::new();
 
+		let mut known = None;
+		if let Ok(mut bulbs) = self.bulbs.write() {
+			let mut buf = [0; 1024];
+			loop {
+				match self.sock.recv_from(&mut buf) {
+					Ok((0, addr)) => debug!("Received a zero-byte datagram from {:?}", addr),
+					Ok((nbytes, addr)) => {
+						let packet = Packet {
+							bytes: &buf[0..nbytes],
+							addr,
+							source: self.source,
+						};
+						let received = packet.receive(&mut bulbs, &self.queue, &self.replies, |e| {
+							events.push(e)
+						});
+						if let Err(e) = received {
+							self.listeners.error(e);
+						}
+					}
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+					Err(e) => return Err(e.into()),
+				}
+			}
+
+			sweep_offline(&mut bulbs, &self.timeouts, |event| events.push(event));
+			for bulb in bulbs.values_mut() {
+				bulb.set_refresh_policies(self.refresh_policies);
+				bulb.query_for_missing_info(self.timeouts.refresh)?;
+			}
+			self.send_echoes(self.echo_requests(&mut bulbs));
+			self.scheduler.run_due(&bulbs, Instant::now(), |error| {
+				self.listeners.error(ManagerError::Animation(error))
+			});
+			known = Some(bulbs.len());
+		}
+		if let Some(known) = known {
+			self.discover_if_due(known);
+		}
+
+		let now = Instant::now();
+		let due: Vec<Resend> = {
+			let mut resends = resends.lock().unwrap();
+			let (due, later) = resends.drain(..).partition(|resend| resend.at <= now);
+			*resends = later;
+			due
+		};
+		for resend in due {
+			for &addr in resend.targets.iter() {
+				if let Err(error) = self.sock.send_to(&resend.bytes, addr) {
+					self.listeners.error(ManagerError::Send { addr, error });
+				}
+			}
+		}
+
+		let mut rate_limiter = self.rate_limiter.lock().unwrap();
+		while let Some(outgoing) =
+			self.queue.pop_if(|outgoing| rate_limiter.try_acquire(outgoing.target, now).is_ok())
+		{
+			if let Err(error) = self.sock.send_to(&outgoing.bytes, outgoing.addr) {
+				self.listeners.error(ManagerError::Send {
+					addr: outgoing.addr,
+					error,
+				});
+			}
+			self.queue.recycle(outgoing.bytes);
+		}
+
+		for event in &events {
+			self.listeners.event(*event);
+		}
+		Ok(events)
+	}
+
+	/// Stops the background threads, waiting for them to finish.
+	///
+	/// Messages that are still queued are discarded.  Dropping the `Manager` does this too (and
+	/// closes its socket); calling it more than once does nothing.
+	pub fn shutdown(&mut self) {
+		self.shutdown.store(true, Ordering::Relaxed);
+		self.queue.close();
+		for thread in self.threads.drain(..) {
+			let _ = thread.join();
+		}
+	}
+
+	/// Asks every bulb for any missing or stale data.
+	///
+	/// Bulbs that have gone quiet are marked offline or forgotten first (see
+	/// [Timeouts::offline_after] and [Timeouts::evict_after]), so this should be called regularly
+	/// for those timeouts to be meaningful.  Discovery is re-run from here too, when
+	/// [Manager::auto_discovery] says it's due.
+	///
+	/// The queries are queued (and echo requests built) while the bulbs are locked, but nothing
+	/// is sent until they're released.
+	pub fn refresh(&self) -> Result<()> {
+		let (known, echoes) = {
+			let mut bulbs =
+				self.bulbs.write().map_err(|_| anyhow!("the bulbs' lock is poisoned"))?;
+			sweep_offline(&mut bulbs, &self.timeouts, |event| self.listeners.event(event));
+			for bulb in bulbs.values_mut() {
+				bulb.set_refresh_policies(self.refresh_policies);
+				bulb.query_for_missing_info(self.timeouts.refresh)?;
+			}
+			(bulbs.len(), self.echo_requests(&mut bulbs))
+		};
+		self.discover_if_due(known);
+		self.send_echoes(echoes);
 		Ok(())
 	}
 
-	pub fn refresh(&self) {
-		if let Ok(bulbs) = self.bulbs.lock() {
-			for bulb in bulbs.values() {
-				bulb.query_for_missing_info(&self.sock).unwrap();
+	/// Re-runs discovery if [Manager::auto_discovery] says it's due.
+	fn discover_if_due(&self, known: usize) {
+		let config = match self.auto_discovery {
+			Some(config) => config,
+			None => return,
+		};
+		if self.discovery.lock().unwrap().due(&config, Instant::now(), known) {
+			let policy = self.retry_policy;
+			if let Err(error) = self.broadcast_discovery(&policy) {
+				warn!("Automatic discovery failed: {}", error);
 			}
 		}
 	}
+
+	/// How a bulb has been answering echo requests, if it's known.
+	///
+	/// This is only meaningful with [Manager::keepalive] set (its thresholds decide the
+	/// [HealthStatus](crate::udp::HealthStatus)); otherwise, no echoes are sent and the status
+	/// stays unknown.
+	pub fn health(&self, target: MacAddress) -> Option<Health> {
+		let keepalive = self.keepalive.unwrap_or_default();
+		let bulbs = self.bulbs.read().unwrap();
+		bulbs.get(&target).map(|bulb| bulb.health(&keepalive))
+	}
+
+	/// Builds an echo request for every bulb that's due one, if [Manager::keepalive] is set.
+	fn echo_requests(&self, bulbs: &mut HashMap<MacAddress, Bulb>) -> Vec<(SocketAddr, Vec<u8>)> {
+		let keepalive = match &self.keepalive {
+			Some(keepalive) => keepalive,
+			None => return Vec::new(),
+		};
+		let mut echoes = Vec::new();
+		for bulb in bulbs.values_mut() {
+			let echo = match bulb.start_echo(keepalive) {
+				Some(echo) => echo,
+				None => continue,
+			};
+			let options = BuildOptions {
+				target: Some(bulb.target),
+				sequence: bulb.next_sequence(),
+				source: self.source,
+				..Default::default()
+			};
+			let bytes = match RawMessage::build(&options, echo).and_then(|raw| raw.pack()) {
+				Ok(bytes) => bytes,
+				Err(e) => {
+					warn!("Error building echo request for {}: {}", bulb.target, e);
+					continue;
+				}
+			};
+			echoes.push((bulb.addr, bytes));
+		}
+		echoes
+	}
+
+	/// Sends the requests built by [Manager::echo_requests].
+	///
+	/// These go straight out rather than through the queue, so queueing delays don't count
+	/// towards the round-trip time.
+	fn send_echoes(&self, echoes: Vec<(SocketAddr, Vec<u8>)>) {
+		for (addr, bytes) in echoes {
+			if let Err(error) = self.sock.send_to(&bytes, addr) {
+				self.listeners.error(ManagerError::Send { addr, error });
+			}
+		}
+	}
+}
+
+/// Marks bulbs that have gone quiet as offline, and forgets the ones that have been quiet for
+/// too long.
+pub(super) fn sweep_offline(
+	bulbs: &mut HashMap<MacAddress, Bulb>,
+	timeouts: &Timeouts,
+	mut on_event: impl FnMut(ManagerEvent),
+) {
+	bulbs.retain(|target, bulb| {
+		let silent = bulb.last_seen.elapsed();
+		if silent >= timeouts.evict_after {
+			on_event(ManagerEvent::DeviceLost(*target));
+			return false;
+		}
+		if silent >= timeouts.offline_after && bulb.is_online() {
+			bulb.mark_offline();
+			on_event(ManagerEvent::DeviceOffline(*target));
+		}
+		true
+	});
+}
+
+/// A message sent to one bulb with [Manager::send_with_ack] or [Manager::request] (or their
+/// [AsyncManager](crate::udp::AsyncManager) equivalents), ready to be sent and re-sent.
+pub(super) struct Request {
+	pub(super) addr: SocketAddr,
+	pub(super) bytes: Vec<u8>,
+	/// What the bulb's acknowledgement or reply will carry.
+	pub(super) meta: ResponseMeta,
+	typ: u16,
+	ack: bool,
+}
+
+impl Request {
+	/// Numbers `message` for `bulb`, asking for an acknowledgement if `ack` is set or a response
+	/// otherwise.
+	pub(super) fn new(bulb: &Bulb, source: u32, message: Message, ack: bool) -> Result<Request> {
+		let options = BuildOptions {
+			target: Some(bulb.target),
+			ack_required: ack,
+			res_required: !ack,
+			sequence: bulb.next_sequence(),
+			source,
+		};
+		let typ = message.get_num();
+		let raw = RawMessage::build(&options, message)?;
+		Ok(Request {
+			addr: bulb.addr,
+			bytes: raw.pack()?,
+			meta: raw.response_meta(),
+			typ,
+			ack,
+		})
+	}
+
+	/// The error returned once `attempts` sends have gone unanswered.
+	pub(super) fn unanswered(&self, attempts: u32) -> anyhow::Error {
+		anyhow!(
+			"{} didn't {} message type {} after {} attempts",
+			self.meta.target,
+			if self.ack { "acknowledge" } else { "reply to" },
+			self.typ,
+			attempts
+		)
+	}
+}
+
+/// A datagram received by a [Manager] or an [AsyncManager](crate::udp::AsyncManager).
+pub(super) struct Packet<'a> {
+	pub(super) bytes: &'a [u8],
+	pub(super) addr: SocketAddr,
+	/// The Manager's own [Frame::source](crate::Frame::source).
+	pub(super) source: u32,
+}
+
+impl Packet<'_> {
+	/// Updates the bulb that sent the packet (adding it if it's new), and hands the message to
+	/// anyone waiting for it as a reply.
+	fn receive(
+		&self,
+		bulbs: &mut HashMap<MacAddress, Bulb>,
+		queue: &SendQueue,
+		replies: &PendingReplies,
+		on_event: impl FnMut(ManagerEvent),
+	) -> Result<(), ManagerError> {
+		let raw = match self.unpack()? {
+			Some(raw) => raw,
+			None => return Ok(()),
+		};
+		// decoded before the cache takes the message, and handed over after
+		let reply = replies
+			.lock()
+			.unwrap()
+			.remove(&raw.response_meta())
+			.map(|tx| (tx, Message::from_raw(&raw)));
+
+		let handled = self.update(bulbs, queue, raw, on_event);
+		if let Some((tx, message)) = reply {
+			let _ = tx.send(message);
+		}
+		handled
+	}
+
+	/// The message in the packet, unless it wasn't sent by a single bulb.
+	pub(super) fn unpack(&self) -> Result<Option<RawMessage>, ManagerError> {
+		let addr = self.addr;
+		let raw =
+			RawMessage::unpack(self.bytes).map_err(|error| ManagerError::Unpack { addr, error })?;
+		Ok(Some(raw).filter(|raw| !raw.frame_addr.target.is_all()))
+	}
+
+	/// Updates the bulb that sent `raw` (adding it if it's new).
+	pub(super) fn update(
+		&self,
+		bulbs: &mut HashMap<MacAddress, Bulb>,
+		queue: &SendQueue,
+		raw: RawMessage,
+		mut on_event: impl FnMut(ManagerEvent),
+	) -> Result<(), ManagerError> {
+		let (addr, target) = (self.addr, raw.frame_addr.target);
+		let bulb = match bulbs.entry(target) {
+			Entry::Occupied(entry) => {
+				let bulb = entry.into_mut();
+				if !bulb.is_online() {
+					on_event(ManagerEvent::DeviceOnline(target));
+				}
+				bulb.update(addr);
+				bulb
+			}
+			Entry::Vacant(entry) => {
+				on_event(ManagerEvent::DeviceDiscovered(target));
+				entry.insert(Bulb::new(self.source, target, queue.clone(), addr))
+			}
+		};
+		let handled = Manager::handle_message(raw, bulb);
+		for field in handled.iter().flatten() {
+			on_event(ManagerEvent::DeviceUpdated(target, *field));
+		}
+		handled.map(|_| ()).map_err(|error| ManagerError::Handle { addr, error })
+	}
+}
+
+impl<T: Transport> Drop for Manager<T> {
+	fn drop(&mut self) {
+		self.shutdown();
+	}
 }
@@ -1,7 +1,48 @@
 pub mod refreshable_data;
 pub mod bulb;
+pub mod builder;
+pub mod circadian;
+pub mod color_cycle;
+pub mod conformance;
+pub mod discovery;
+pub mod effects;
+pub mod flicker;
+pub mod health;
+pub mod location;
 pub mod manager;
+pub mod queue;
+pub mod rate_limit;
+pub mod retry;
+pub mod scheduler;
+pub mod transition;
+pub mod transport;
+pub mod waveform;
+pub mod wifi;
+#[cfg(feature = "serde")]
+pub mod state;
+#[cfg(feature = "tokio")]
+pub mod async_manager;
 
 pub use refreshable_data::*;
 pub use bulb::*;
+pub use builder::*;
+pub use circadian::*;
+pub use color_cycle::*;
+pub use discovery::*;
+pub use effects::*;
+pub use flicker::*;
+pub use health::*;
+pub use location::*;
 pub use manager::*;
+pub use queue::*;
+pub use rate_limit::*;
+pub use retry::*;
+pub use scheduler::*;
+pub use transition::*;
+pub use transport::*;
+pub use waveform::*;
+pub use wifi::*;
+#[cfg(feature = "serde")]
+pub use state::*;
+#[cfg(feature = "tokio")]
+pub use async_manager::*;
@@ -0,0 +1,275 @@
+use std::{
+	collections::VecDeque,
+	convert::TryFrom,
+	net::SocketAddr,
+	sync::{Arc, Condvar, Mutex},
+};
+
+use crate::{Error, MacAddress, MessageType, RawMessage};
+
+/// How urgently an outgoing message needs to be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+	/// Periodic polling and other traffic that nobody is actively waiting on.
+	Background,
+	/// Commands issued by a user (e.g. SetColor, SetPower), which should never wait behind a
+	/// refresh burst.
+	Interactive,
+}
+
+/// The default maximum number of queued messages, per [Priority].
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+/// A packed message waiting to be sent to a device.
+#[derive(Debug, Clone)]
+pub struct Outgoing {
+	pub target: MacAddress,
+	pub addr: SocketAddr,
+	/// The message type (see [Message::get_num]), used to coalesce redundant messages.
+	///
+	/// [Message::get_num]: crate::Message::get_num
+	pub typ: u16,
+	pub bytes: Vec<u8>,
+}
+
+impl Outgoing {
+	/// True if a newer message of the same type to the same device makes this one redundant.
+	///
+	/// Queries are always redundant (the reply would be the same), as are commands that set the
+	/// whole state of a device: LightSetColor (102), SetPower (21) and LightSetPower (117).
+	/// Commands that only affect part of a device, like SetColorZones, are never coalesced.
+	fn superseded_by(&self, priority: Priority, other: &Outgoing) -> bool {
+		self.target == other.target
+			&& self.typ == other.typ
+			&& (priority == Priority::Background
+				|| matches!(
+					MessageType::try_from(self.typ),
+					Ok(MessageType::SetPower
+						| MessageType::LightSetColor
+						| MessageType::LightSetPower)
+				))
+	}
+}
+
+/// A point-in-time view of the send queue, for monitoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+	/// Interactive messages waiting to be sent.
+	pub interactive_depth: usize,
+	/// Background messages waiting to be sent.
+	pub background_depth: usize,
+	/// Total number of queued messages that were replaced by a newer, equivalent message.
+	pub coalesced: u64,
+	/// Total number of messages dropped because their queue was full.
+	pub dropped: u64,
+}
+
+#[derive(Debug)]
+struct Queues {
+	interactive: VecDeque<Outgoing>,
+	background: VecDeque<Outgoing>,
+	/// Buffers of messages that have been sent (or discarded), ready to be packed into again
+	free: Vec<Vec<u8>>,
+	capacity: usize,
+	coalesced: u64,
+	dropped: u64,
+	closed: bool,
+}
+
+/// The queue of messages waiting to be sent by the [Manager]'s sender thread.
+///
+/// [Priority::Interactive] messages are always sent before any [Priority::Background] ones, in
+/// the order they were queued.  Cloning a `SendQueue` gives another handle to the same queue.
+///
+/// Each priority level is bounded.  A message that supersedes one already waiting in the queue
+/// (for example a second LightSetColor to the same bulb) replaces it in place, so a fast-running
+/// effect can never build up a backlog of stale colors.  Otherwise, if the queue is full, the
+/// oldest message at that priority is dropped to make room.
+///
+/// The queue also keeps a pool of packet buffers.  Messages packed with [SendQueue::pack] reuse
+/// the buffers of messages that have already been sent (see [SendQueue::recycle]), so steady-state
+/// polling doesn't allocate.
+///
+/// [Manager]: crate::udp::Manager
+#[derive(Debug, Clone)]
+pub struct SendQueue {
+	inner: Arc<(Mutex<Queues>, Condvar)>,
+}
+
+impl SendQueue {
+	pub fn new() -> SendQueue {
+		SendQueue::with_capacity(DEFAULT_QUEUE_CAPACITY)
+	}
+
+	/// Creates a queue holding at most `capacity` messages per priority level.
+	pub fn with_capacity(capacity: usize) -> SendQueue {
+		let queues = Queues {
+			interactive: VecDeque::new(),
+			background: VecDeque::new(),
+			free: Vec::new(),
+			capacity: capacity.max(1),
+			coalesced: 0,
+			dropped: 0,
+			closed: false,
+		};
+		SendQueue {
+			inner: Arc::new((Mutex::new(queues), Condvar::new())),
+		}
+	}
+
+	/// Queues a message to be sent, waking up the sender if it's idle.
+	///
+	/// Messages pushed after the queue is [closed](SendQueue::close) are discarded.
+	pub fn push(&self, priority: Priority, outgoing: Outgoing) {
+		let (lock, cvar) = &*self.inner;
+		let mut guard = lock.lock().unwrap();
+		let queues = &mut *guard;
+		if queues.closed {
+			return;
+		}
+		let queue = match priority {
+			Priority::Interactive => &mut queues.interactive,
+			Priority::Background => &mut queues.background,
+		};
+
+		let discarded = if let Some(queued) =
+			queue.iter_mut().find(|q| q.superseded_by(priority, &outgoing))
+		{
+			queues.coalesced += 1;
+			Some(std::mem::replace(queued, outgoing))
+		} else {
+			let dropped = if queue.len() >= queues.capacity {
+				queues.dropped += 1;
+				queue.pop_front()
+			} else {
+				None
+			};
+			queue.push_back(outgoing);
+			dropped
+		};
+		if let Some(discarded) = discarded {
+			queues.recycle(discarded.bytes);
+		}
+		cvar.notify_one();
+	}
+
+	/// Packs `msg`, reusing a buffer from the pool if one is available.
+	pub fn pack(&self, msg: &RawMessage) -> Result<Vec<u8>, Error> {
+		let mut buf = self.inner.0.lock().unwrap().free.pop().unwrap_or_default();
+		buf.clear();
+		buf.resize(msg.packed_size(), 0);
+		msg.pack_into(&mut buf)?;
+		Ok(buf)
+	}
+
+	/// Returns the buffer of a message that has been sent to the pool, for [SendQueue::pack] to
+	/// reuse.
+	pub fn recycle(&self, buf: Vec<u8>) {
+		self.inner.0.lock().unwrap().recycle(buf);
+	}
+
+	/// Takes the next message to send, blocking until one is available.
+	///
+	/// Returns `None` once the queue is [closed](SendQueue::close).
+	pub fn pop(&self) -> Option<Outgoing> {
+		let (lock, cvar) = &*self.inner;
+		let mut queues = lock.lock().unwrap();
+		loop {
+			if queues.closed {
+				return None;
+			}
+			if let Some(outgoing) = queues.interactive.pop_front() {
+				return Some(outgoing);
+			}
+			if let Some(outgoing) = queues.background.pop_front() {
+				return Some(outgoing);
+			}
+			queues = cvar.wait(queues).unwrap();
+		}
+	}
+
+	/// Takes the next message to send, if there is one, without blocking.
+	pub fn try_pop(&self) -> Option<Outgoing> {
+		let mut queues = self.inner.0.lock().unwrap();
+		if queues.closed {
+			return None;
+		}
+		queues
+			.interactive
+			.pop_front()
+			.or_else(|| queues.background.pop_front())
+	}
+
+	/// Takes the next message to send, without blocking, but only if `ready` approves of it.
+	///
+	/// If it doesn't, the message stays at the front of its queue.
+	pub fn pop_if(&self, ready: impl FnOnce(&Outgoing) -> bool) -> Option<Outgoing> {
+		let mut guard = self.inner.0.lock().unwrap();
+		let queues = &mut *guard;
+		if queues.closed {
+			return None;
+		}
+		let queue = if queues.interactive.is_empty() {
+			&mut queues.background
+		} else {
+			&mut queues.interactive
+		};
+		if ready(queue.front()?) {
+			queue.pop_front()
+		} else {
+			None
+		}
+	}
+
+	/// Discards every queued message, and wakes up anyone waiting in [SendQueue::pop].
+	///
+	/// This is permanent: a closed queue stays empty.
+	pub fn close(&self) {
+		let (lock, cvar) = &*self.inner;
+		let mut queues = lock.lock().unwrap();
+		queues.closed = true;
+		queues.interactive.clear();
+		queues.background.clear();
+		cvar.notify_all();
+	}
+
+	/// The number of messages currently waiting to be sent.
+	pub fn len(&self) -> usize {
+		let queues = self.inner.0.lock().unwrap();
+		queues.interactive.len() + queues.background.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Whether [SendQueue::close] has been called.
+	pub fn is_closed(&self) -> bool {
+		self.inner.0.lock().unwrap().closed
+	}
+
+	pub fn metrics(&self) -> QueueMetrics {
+		let queues = self.inner.0.lock().unwrap();
+		QueueMetrics {
+			interactive_depth: queues.interactive.len(),
+			background_depth: queues.background.len(),
+			coalesced: queues.coalesced,
+			dropped: queues.dropped,
+		}
+	}
+}
+
+impl Queues {
+	fn recycle(&mut self, buf: Vec<u8>) {
+		// every queued message can have a buffer waiting for it, but there's no use keeping more
+		if self.free.len() < self.capacity * 2 {
+			self.free.push(buf);
+		}
+	}
+}
+
+impl Default for SendQueue {
+	fn default() -> SendQueue {
+		SendQueue::new()
+	}
+}
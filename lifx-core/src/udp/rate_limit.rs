@@ -0,0 +1,147 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use crate::MacAddress;
+
+/// How fast messages may be sent, to avoid overwhelming the devices (and the Wi-Fi network).
+///
+/// LIFX recommends sending no more than 20 messages per second to any one device.  Use
+/// `f32::INFINITY` to disable a limit; a rate that isn't positive (or is NaN) disables it too,
+/// rather than stopping messages altogether.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimits {
+	/// Messages per second to any one device.
+	pub per_device: f32,
+	/// Messages per second to all devices combined.
+	pub global: f32,
+	/// How many messages can be sent back-to-back, before the rates kick in.
+	pub burst: u32,
+}
+
+impl RateLimits {
+	/// These limits, with any rate that isn't positive replaced by `f32::INFINITY`.
+	fn sanitized(self) -> RateLimits {
+		let rate = |rate: f32| if rate > 0.0 { rate } else { f32::INFINITY };
+		RateLimits {
+			per_device: rate(self.per_device),
+			global: rate(self.global),
+			..self
+		}
+	}
+
+	/// No limits at all.
+	pub const NONE: RateLimits = RateLimits {
+		per_device: f32::INFINITY,
+		global: f32::INFINITY,
+		burst: 1,
+	};
+}
+
+impl Default for RateLimits {
+	fn default() -> RateLimits {
+		RateLimits {
+			per_device: 20.0,
+			global: 100.0,
+			burst: 5,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+	tokens: f32,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn full(burst: u32, now: Instant) -> TokenBucket {
+		TokenBucket {
+			tokens: burst.max(1) as f32,
+			last_refill: now,
+		}
+	}
+
+	fn refill(&mut self, rate: f32, burst: u32, now: Instant) {
+		let capacity = burst.max(1) as f32;
+		if rate.is_infinite() {
+			self.tokens = capacity;
+		} else {
+			let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f32();
+			self.tokens = (self.tokens + elapsed * rate).min(capacity);
+		}
+		self.last_refill = now;
+	}
+
+	/// How long until there's a whole token to take.
+	fn wait(&self, rate: f32) -> Duration {
+		if self.tokens >= 1.0 {
+			Duration::from_secs(0)
+		} else {
+			Duration::try_from_secs_f32((1.0 - self.tokens) / rate).unwrap_or(Duration::MAX)
+		}
+	}
+}
+
+/// Token buckets enforcing [RateLimits], one per device plus one shared by all of them.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+	limits: RateLimits,
+	global: TokenBucket,
+	devices: HashMap<MacAddress, TokenBucket>,
+}
+
+impl RateLimiter {
+	/// Enforces `limits`, treating a rate that isn't positive as no limit (see [RateLimits]).
+	pub fn new(limits: RateLimits) -> RateLimiter {
+		let limits = limits.sanitized();
+		RateLimiter {
+			limits,
+			global: TokenBucket::full(limits.burst, Instant::now()),
+			devices: HashMap::new(),
+		}
+	}
+
+	pub fn limits(&self) -> RateLimits {
+		self.limits
+	}
+
+	/// Changes the limits, starting over with full buckets.
+	pub fn set_limits(&mut self, limits: RateLimits) {
+		*self = RateLimiter::new(limits);
+	}
+
+	/// Takes a token for a message to `target`, if both its bucket and the global one have one
+	/// available at `now`.
+	///
+	/// Otherwise nothing is taken, and the error is how long to wait before trying again.
+	pub fn try_acquire(&mut self, target: MacAddress, now: Instant) -> Result<(), Duration> {
+		let RateLimits {
+			per_device,
+			global,
+			burst,
+		} = self.limits;
+		let device = self
+			.devices
+			.entry(target)
+			.or_insert_with(|| TokenBucket::full(burst, now));
+
+		device.refill(per_device, burst, now);
+		self.global.refill(global, burst, now);
+		let wait = device.wait(per_device).max(self.global.wait(global));
+		if wait > Duration::from_secs(0) {
+			return Err(wait);
+		}
+
+		device.tokens -= 1.0;
+		self.global.tokens -= 1.0;
+		Ok(())
+	}
+}
+
+impl Default for RateLimiter {
+	fn default() -> RateLimiter {
+		RateLimiter::new(RateLimits::default())
+	}
+}
@@ -2,11 +2,73 @@ use std::time::{Duration, Instant};
 
 use crate::Message;
 
+/// When a [RefreshableData] is asked for again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshPolicy {
+	/// Whenever the cached value is older than this.
+	Every(Duration),
+	/// Once, when there's no value yet; after that the value is only updated by whatever the
+	/// device happens to send.
+	Never,
+	/// Only after [RefreshableData::mark_stale] is called.
+	OnDemand,
+}
+
+/// How often each kind of [Bulb] state is refreshed.
+///
+/// The defaults refresh rarely-changing details (like labels and firmware) hourly, and the
+/// light's state (like power and color) every 15 seconds.
+///
+/// [Bulb]: crate::udp::Bulb
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshPolicies {
+	/// The model, and for matrix devices the layout of the tile chain.
+	pub model: RefreshPolicy,
+	pub location: RefreshPolicy,
+	pub group: RefreshPolicy,
+	pub name: RefreshPolicy,
+	pub host_firmware: RefreshPolicy,
+	pub wifi_firmware: RefreshPolicy,
+	pub wifi_signal: RefreshPolicy,
+	pub runtime: RefreshPolicy,
+	pub power: RefreshPolicy,
+	/// The color, zones or tile pixels, depending on the kind of device.
+	pub color: RefreshPolicy,
+	pub infrared: RefreshPolicy,
+	/// The HEV cycle, and the result of the last one.
+	pub hev_cycle: RefreshPolicy,
+}
+
+impl Default for RefreshPolicies {
+	fn default() -> RefreshPolicies {
+		let hourly = RefreshPolicy::Every(Duration::from_secs(60 * 60));
+		let minutely = RefreshPolicy::Every(Duration::from_secs(60));
+		let often = RefreshPolicy::Every(Duration::from_secs(15));
+		RefreshPolicies {
+			model: hourly,
+			location: hourly,
+			group: hourly,
+			name: hourly,
+			host_firmware: hourly,
+			wifi_firmware: hourly,
+			wifi_signal: minutely,
+			runtime: minutely,
+			power: often,
+			color: often,
+			infrared: often,
+			hev_cycle: often,
+		}
+	}
+}
+
 #[derive(Debug)]
 pub struct RefreshableData<T> {
 	pub data: Option<T>,
-	max_age: Duration,
+	policy: RefreshPolicy,
 	last_updated: Instant,
+	last_changed: Option<Instant>,
+	requested_at: Option<Instant>,
+	stale: bool,
 	pub refresh_msg: Message,
 }
 
@@ -14,19 +76,83 @@ impl<T> RefreshableData<T> {
 	pub fn empty(max_age: Duration, refresh_msg: Message) -> RefreshableData<T> {
 		RefreshableData {
 			data: None,
-			max_age,
+			policy: RefreshPolicy::Every(max_age),
 			last_updated: Instant::now(),
+			last_changed: None,
+			requested_at: None,
+			stale: false,
 			refresh_msg,
 		}
 	}
 
-	pub fn update(&mut self, data: T) {
+	pub fn policy(&self) -> RefreshPolicy {
+		self.policy
+	}
+
+	pub fn set_policy(&mut self, policy: RefreshPolicy) {
+		self.policy = policy;
+	}
+
+	/// Stores a newly reported value, returning whether it differs from the cached one.
+	pub fn update(&mut self, data: T) -> bool
+	where
+		T: PartialEq,
+	{
+		let changed = self.data.as_ref() != Some(&data);
 		self.data = Some(data);
-		self.last_updated = Instant::now()
+		self.touch(changed);
+		changed
+	}
+
+	/// Records that the device just reported the value, after it has been patched in place
+	/// through `data` (e.g. a few zones at a time).  `changed` says whether the patch made any
+	/// difference.
+	pub fn touch(&mut self, changed: bool) {
+		let now = Instant::now();
+		self.last_updated = now;
+		if changed {
+			self.last_changed = Some(now);
+		}
+		self.requested_at = None;
+		self.stale = false;
+	}
+
+	/// When the device last reported the value, whether or not it had changed.
+	///
+	/// Before the first report, this is when the `RefreshableData` was created.
+	pub fn last_updated(&self) -> Instant {
+		self.last_updated
+	}
+
+	/// When the value last actually changed, if it has ever been reported.
+	pub fn last_changed(&self) -> Option<Instant> {
+		self.last_changed
+	}
+
+	/// Keeps the current value, but treats it as needing a refresh (e.g. because it was loaded
+	/// from disk rather than reported by the device).
+	pub fn mark_stale(&mut self) {
+		self.stale = true;
+	}
+
+	/// Records that `refresh_msg` was just sent, so it isn't re-sent while the reply is in flight.
+	pub fn mark_requested(&mut self) {
+		self.requested_at = Some(Instant::now());
+	}
+
+	/// True if a refresh was requested less than `timeout` ago and hasn't been answered yet.
+	pub fn awaiting_reply(&self, timeout: Duration) -> bool {
+		matches!(self.requested_at, Some(t) if t.elapsed() < timeout)
 	}
 
 	pub fn needs_refresh(&self) -> bool {
-		self.data.is_none() || self.last_updated.elapsed() > self.max_age
+		match self.policy {
+			RefreshPolicy::Every(max_age) => {
+				self.data.is_none() || self.stale || self.last_updated.elapsed() > max_age
+			}
+			RefreshPolicy::Never => self.data.is_none() || self.stale,
+			RefreshPolicy::OnDemand => self.stale,
+		}
 	}
 
 	pub fn as_ref(&self) -> Option<&T> {
@@ -0,0 +1,99 @@
+use std::{
+	collections::hash_map::RandomState,
+	hash::{BuildHasher, Hasher},
+	time::Duration,
+};
+
+/// How often, and how patiently, an unanswered message is re-sent.
+///
+/// UDP gives no delivery guarantees, and busy Wi-Fi networks drop a surprising number of
+/// packets, so anything that expects a reply is sent up to `attempts` times.  The delay before
+/// each retry starts at `backoff` and doubles every time, plus a random amount of up to `jitter`
+/// so that many clients (or many bulbs) don't all retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+	/// Total number of sends, including the first one.  Zero is treated as one.
+	pub attempts: u32,
+	/// Delay before the first retry.
+	pub backoff: Duration,
+	/// Upper bound of the random delay added to every retry.
+	pub jitter: Duration,
+}
+
+impl RetryPolicy {
+	/// Send exactly once, never retry.
+	pub const NONE: RetryPolicy = RetryPolicy {
+		attempts: 1,
+		backoff: Duration::from_millis(0),
+		jitter: Duration::from_millis(0),
+	};
+
+	/// The delay to wait before the given retry (`1` is the first retry, i.e. the second send).
+	///
+	/// Returns `None` once `retry` exceeds the number of allowed attempts.
+	pub fn delay(&self, retry: u32) -> Option<Duration> {
+		if retry == 0 || retry >= self.attempts.max(1) {
+			return None;
+		}
+
+		let backoff = self
+			.backoff
+			.checked_mul(2u32.saturating_pow(retry - 1))
+			.unwrap_or(Duration::MAX);
+		let jitter = if self.jitter.as_nanos() > 0 {
+			let random = RandomState::new().build_hasher().finish();
+			Duration::from_nanos(random % self.jitter.as_nanos() as u64)
+		} else {
+			Duration::from_millis(0)
+		};
+
+		Some(backoff.saturating_add(jitter))
+	}
+
+	/// All of the retry delays for this policy, in order.
+	pub fn delays(&self) -> impl Iterator<Item = Duration> + '_ {
+		(1..self.attempts.max(1)).filter_map(move |retry| self.delay(retry))
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> RetryPolicy {
+		RetryPolicy {
+			attempts: 3,
+			backoff: Duration::from_millis(250),
+			jitter: Duration::from_millis(50),
+		}
+	}
+}
+
+/// How long to wait for replies before giving up on them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeouts {
+	/// How long to wait for the reply to a single request (or acknowledgement) before it is
+	/// considered lost.
+	pub response: Duration,
+	/// How long a refresh query may remain unanswered before it is sent again.
+	pub refresh: Duration,
+	/// How long a bulb may go without sending anything before [Manager::refresh] marks it offline
+	/// (see [Bulb::is_online]).
+	///
+	/// [Manager::refresh]: crate::udp::Manager::refresh
+	/// [Bulb::is_online]: crate::udp::Bulb::is_online
+	pub offline_after: Duration,
+	/// How long a bulb may go without sending anything before [Manager::refresh] forgets it
+	/// altogether.
+	///
+	/// [Manager::refresh]: crate::udp::Manager::refresh
+	pub evict_after: Duration,
+}
+
+impl Default for Timeouts {
+	fn default() -> Timeouts {
+		Timeouts {
+			response: Duration::from_millis(500),
+			refresh: Duration::from_secs(2),
+			offline_after: Duration::from_secs(60),
+			evict_after: Duration::from_secs(10 * 60),
+		}
+	}
+}
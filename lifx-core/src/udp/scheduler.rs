@@ -0,0 +1,163 @@
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Condvar, Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{udp::Bulb, MacAddress};
+
+/// Something that changes bulbs over time, like a [Flicker](crate::udp::Flicker), without the
+/// application running its own timing loop (see [Manager::animate]).
+///
+/// A threaded Manager runs every animation from one background thread; a polled one runs them
+/// from [Manager::poll].  With an [AsyncManager](crate::udp::AsyncManager), call
+/// [Animation::frame] from the application's own loop instead.
+///
+/// [Manager::animate]: crate::udp::Manager::animate
+/// [Manager::poll]: crate::udp::Manager::poll
+pub trait Animation: Send {
+	/// Queues the next frame for `bulbs`, returning how long to wait before the one after, or
+	/// `None` once the animation has finished.
+	///
+	/// Bulbs that are unknown or offline should be skipped rather than treated as an error, since
+	/// they may come back.  An error stops the animation.
+	fn frame(
+		&mut self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		now: Instant,
+	) -> Result<Option<Duration>>;
+}
+
+/// Controls an animation started by [Manager::animate](crate::udp::Manager::animate).
+///
+/// Dropping the handle leaves the animation running.
+#[derive(Debug, Clone)]
+pub struct AnimationHandle {
+	state: Arc<AnimationState>,
+}
+
+#[derive(Debug, Default)]
+struct AnimationState {
+	cancelled: AtomicBool,
+	paused: AtomicBool,
+	finished: AtomicBool,
+}
+
+impl AnimationHandle {
+	/// Stops the animation before its next frame, leaving the bulbs as its last frame left them.
+	pub fn cancel(&self) {
+		self.state.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	/// Holds the animation after its current frame, until [AnimationHandle::resume].
+	///
+	/// Animations that keep track of their own progress, like a [Transition], pick up where they
+	/// left off.
+	///
+	/// [Transition]: crate::udp::Transition
+	pub fn pause(&self) {
+		self.state.paused.store(true, Ordering::Relaxed);
+	}
+
+	/// Carries on with a paused animation.  Its next frame is run shortly afterwards.
+	pub fn resume(&self) {
+		self.state.paused.store(false, Ordering::Relaxed);
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.state.paused.load(Ordering::Relaxed)
+	}
+
+	/// Whether the animation has stopped, because it finished, failed or was cancelled.
+	pub fn is_finished(&self) -> bool {
+		self.state.finished.load(Ordering::Relaxed)
+	}
+}
+
+struct Scheduled {
+	animation: Box<dyn Animation>,
+	due: Instant,
+	state: Arc<AnimationState>,
+}
+
+impl Scheduled {
+	fn is_paused(&self) -> bool {
+		self.state.paused.load(Ordering::Relaxed)
+	}
+}
+
+/// The [Manager](crate::udp::Manager)'s running animations, and when each one's next frame is
+/// due.
+#[derive(Default)]
+pub(super) struct Scheduler {
+	scheduled: Mutex<Vec<Scheduled>>,
+	added: Condvar,
+}
+
+impl Scheduler {
+	/// Schedules `animation`, with its first frame due straight away.
+	pub(super) fn add(&self, animation: Box<dyn Animation>) -> AnimationHandle {
+		let state = Arc::new(AnimationState::default());
+		self.scheduled.lock().unwrap().push(Scheduled {
+			animation,
+			due: Instant::now(),
+			state: state.clone(),
+		});
+		self.added.notify_all();
+		AnimationHandle { state }
+	}
+
+	/// Runs every frame that's due at `now`, returning when the next one is.
+	///
+	/// Animations that fail are dropped, and their errors passed to `on_error`.  Paused ones are
+	/// skipped, and aren't counted for the next frame, so whoever waits for it should also wake
+	/// up every so often to notice them being resumed.
+	pub(super) fn run_due(
+		&self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		now: Instant,
+		mut on_error: impl FnMut(anyhow::Error),
+	) -> Option<Instant> {
+		let mut scheduled = self.scheduled.lock().unwrap();
+		scheduled.retain_mut(|scheduled| {
+			let running = if scheduled.state.cancelled.load(Ordering::Relaxed) {
+				false
+			} else if scheduled.due > now || scheduled.is_paused() {
+				return true;
+			} else {
+				match scheduled.animation.frame(bulbs, now) {
+					Ok(Some(wait)) => {
+						scheduled.due = now + wait;
+						true
+					}
+					Ok(None) => false,
+					Err(error) => {
+						on_error(error);
+						false
+					}
+				}
+			};
+			if !running {
+				scheduled.state.finished.store(true, Ordering::Relaxed);
+			}
+			running
+		});
+		let running = scheduled.iter().filter(|scheduled| !scheduled.is_paused());
+		running.map(|scheduled| scheduled.due).min()
+	}
+
+	/// Waits until `until`, or until an animation with an earlier frame is added.
+	pub(super) fn wait(&self, until: Instant) {
+		let scheduled = self.scheduled.lock().unwrap();
+		if scheduled.iter().any(|scheduled| !scheduled.is_paused() && scheduled.due < until) {
+			return;
+		}
+		let timeout = until.saturating_duration_since(Instant::now());
+		let _ = self.added.wait_timeout(scheduled, timeout);
+	}
+}
@@ -0,0 +1,107 @@
+use std::{fs, net::SocketAddr, path::Path};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	udp::{Bulb, SendQueue},
+	LifxIdent,
+	MacAddress,
+};
+
+/// What's known about one bulb, as saved by [Manager::save_state](crate::udp::Manager::save_state).
+///
+/// Only the slowly-changing details are kept; power and color are always asked for afresh.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBulb {
+	pub target: MacAddress,
+	pub addr: SocketAddr,
+	pub name: Option<String>,
+	/// The location's ID and label.
+	pub location: Option<(LifxIdent, String)>,
+	/// The group's ID and label.
+	pub group: Option<(LifxIdent, String)>,
+	/// The vendor and product IDs, which the bulb's capabilities are looked up from.
+	pub model: Option<(u32, u32)>,
+	pub host_firmware: Option<u32>,
+	pub wifi_firmware: Option<u32>,
+}
+
+impl SavedBulb {
+	pub fn new(bulb: &Bulb) -> SavedBulb {
+		SavedBulb {
+			target: bulb.target,
+			addr: bulb.addr,
+			name: bulb.name.as_ref().cloned(),
+			location: bulb.location_id().cloned().zip(bulb.location.as_ref().cloned()),
+			group: bulb.group_id().cloned().zip(bulb.group.as_ref().cloned()),
+			model: bulb.model.as_ref().copied(),
+			host_firmware: bulb.host_firmware.as_ref().copied(),
+			wifi_firmware: bulb.wifi_firmware.as_ref().copied(),
+		}
+	}
+
+	/// Recreates the bulb, with every restored value marked stale so the next refresh confirms
+	/// it with the bulb itself.
+	pub fn restore(&self, source: u32, queue: SendQueue) -> Bulb {
+		let mut bulb = Bulb::new(source, self.target, queue, self.addr);
+		if let Some(name) = &self.name {
+			bulb.name.update(name.clone());
+			bulb.name.mark_stale();
+		}
+		if let Some((id, label)) = &self.location {
+			bulb.set_location(id.clone(), label.clone());
+			bulb.location.mark_stale();
+		}
+		if let Some((id, label)) = &self.group {
+			bulb.set_group(id.clone(), label.clone());
+			bulb.group.mark_stale();
+		}
+		if let Some((vendor, product)) = self.model {
+			bulb.set_model(vendor, product);
+			bulb.model.mark_stale();
+		}
+		if let Some(version) = self.host_firmware {
+			bulb.host_firmware.update(version);
+			bulb.host_firmware.mark_stale();
+		}
+		if let Some(version) = self.wifi_firmware {
+			bulb.wifi_firmware.update(version);
+			bulb.wifi_firmware.mark_stale();
+		}
+		bulb
+	}
+}
+
+/// The contents of a state file written by
+/// [Manager::save_state](crate::udp::Manager::save_state).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedState {
+	pub bulbs: Vec<SavedBulb>,
+}
+
+impl SavedState {
+	pub fn new<'a>(bulbs: impl IntoIterator<Item = &'a Bulb>) -> SavedState {
+		let mut bulbs: Vec<SavedBulb> = bulbs.into_iter().map(SavedBulb::new).collect();
+		bulbs.sort_by_key(|bulb| bulb.target);
+		SavedState { bulbs }
+	}
+
+	/// Reads a state file, as JSON.
+	pub fn load(path: impl AsRef<Path>) -> Result<SavedState> {
+		let file = fs::File::open(path)?;
+		Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+	}
+
+	/// Writes a state file, as JSON.
+	///
+	/// The file is written next to `path` and then moved into place, so a crash part-way
+	/// through never leaves a truncated file behind.
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+		let path = path.as_ref();
+		let mut tmp = path.as_os_str().to_owned();
+		tmp.push(".tmp");
+		fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+		fs::rename(&tmp, path)?;
+		Ok(())
+	}
+}
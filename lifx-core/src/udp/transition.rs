@@ -0,0 +1,204 @@
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+	udp::{Animation, Bulb},
+	MacAddress,
+	HSBK,
+};
+
+/// How an animation speeds up and slows down along the way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+	/// The same speed all the way.
+	Linear,
+	/// Starts slowly and speeds up.
+	EaseIn,
+	/// Starts quickly and slows down.
+	EaseOut,
+	/// Starts and ends slowly.
+	EaseInOut,
+}
+
+impl Easing {
+	/// How far along the animation is (from `0.0` to `1.0`) when `t` of its time has passed.
+	pub fn apply(&self, t: f32) -> f32 {
+		let t = t.clamp(0.0, 1.0);
+		match self {
+			Easing::Linear => t,
+			Easing::EaseIn => t * t,
+			Easing::EaseOut => t * (2.0 - t),
+			Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+		}
+	}
+}
+
+/// A slow change from one color to another, like a wake-up light's sunrise.
+///
+/// The bulbs can only fade in a straight line, so a long transition is sent as a series of
+/// shorter fades (one every [Transition::step]) that follow the [Easing] curve.  Run it with
+/// [Manager::animate](crate::udp::Manager::animate); [AnimationHandle::pause] holds it after
+/// the current fade, and [AnimationHandle::resume] carries on from there.
+///
+/// [AnimationHandle::pause]: crate::udp::AnimationHandle::pause
+/// [AnimationHandle::resume]: crate::udp::AnimationHandle::resume
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+	targets: Vec<MacAddress>,
+	from: HSBK,
+	to: HSBK,
+	duration: Duration,
+	step: Duration,
+	easing: Easing,
+	power_on: bool,
+	elapsed: Duration,
+	started: bool,
+}
+
+impl Transition {
+	/// A transition from `from` to `to` over `duration`, with no bulbs yet.
+	///
+	/// By default it eases in and out, sends a fade for every 60th of the duration (but no more
+	/// than one a second), and turns the bulbs on when it starts.
+	pub fn new(from: HSBK, to: HSBK, duration: Duration) -> Transition {
+		Transition {
+			targets: Vec::new(),
+			from,
+			to,
+			duration,
+			step: (duration / 60).max(Duration::from_secs(1)),
+			easing: Easing::EaseInOut,
+			power_on: true,
+			elapsed: Duration::from_secs(0),
+			started: false,
+		}
+	}
+
+	/// From off to bright daylight, starting with a warm glow.
+	pub fn sunrise(duration: Duration) -> Transition {
+		Transition::new(SUNRISE_START, SUNRISE_END, duration).easing(Easing::EaseIn)
+	}
+
+	/// From bright daylight down to off, through a warm glow.
+	pub fn sunset(duration: Duration) -> Transition {
+		Transition::new(SUNRISE_END, SUNRISE_START, duration).easing(Easing::EaseOut)
+	}
+
+	/// Also changes the bulb `target`.
+	pub fn target(mut self, target: MacAddress) -> Transition {
+		self.targets.push(target);
+		self
+	}
+
+	/// Also changes each of `targets`.
+	pub fn targets(mut self, targets: impl IntoIterator<Item = MacAddress>) -> Transition {
+		self.targets.extend(targets);
+		self
+	}
+
+	/// How long each of the fades that make up the transition lasts.
+	pub fn step(mut self, step: Duration) -> Transition {
+		self.step = step.max(Duration::from_millis(1));
+		self
+	}
+
+	pub fn easing(mut self, easing: Easing) -> Transition {
+		self.easing = easing;
+		self
+	}
+
+	/// Whether to turn the bulbs on when the transition starts.
+	pub fn power_on(mut self, power_on: bool) -> Transition {
+		self.power_on = power_on;
+		self
+	}
+
+	/// Skips the first `elapsed` of the transition, e.g. to carry on with one that was running
+	/// before the application restarted.
+	pub fn starting_at(mut self, elapsed: Duration) -> Transition {
+		self.elapsed = elapsed.min(self.duration);
+		self
+	}
+
+	/// How much of the transition has been sent to the bulbs so far.
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+
+	/// The color `elapsed` into the transition.
+	pub fn color_at(&self, elapsed: Duration) -> HSBK {
+		let t = match self.duration.as_secs_f32() {
+			total if total > 0.0 => elapsed.as_secs_f32() / total,
+			_ => 1.0,
+		};
+		let (mut from, mut to) = (self.from, self.to);
+		// fading to or from white shouldn't sweep through other hues
+		if from.saturation == 0 {
+			from.hue = to.hue;
+		} else if to.saturation == 0 {
+			to.hue = from.hue;
+		}
+		from.lerp(to, self.easing.apply(t))
+	}
+}
+
+/// How long to leave between setting the starting color and the first fade.
+const START_DELAY: Duration = Duration::from_millis(200);
+
+const SUNRISE_START: HSBK = HSBK {
+	hue: 0,
+	saturation: 0,
+	brightness: 0,
+	kelvin: 2000,
+};
+
+const SUNRISE_END: HSBK = HSBK {
+	hue: 0,
+	saturation: 0,
+	brightness: 65535,
+	kelvin: 4500,
+};
+
+impl Animation for Transition {
+	fn frame(
+		&mut self,
+		bulbs: &HashMap<MacAddress, Bulb>,
+		_now: Instant,
+	) -> Result<Option<Duration>> {
+		if self.started && self.elapsed >= self.duration {
+			return Ok(None);
+		}
+
+		// the starting color goes out on its own, since the first fade would replace it in the
+		// send queue (see Outgoing::superseded_by)
+		let (color, fade) = match self.started {
+			false => (self.color_at(self.elapsed), Duration::from_secs(0)),
+			true => {
+				let fade = self.step.min(self.duration - self.elapsed);
+				self.elapsed += fade;
+				(self.color_at(self.elapsed), fade)
+			}
+		};
+
+		let online = |target| bulbs.get(target).filter(|bulb: &&Bulb| bulb.is_online());
+		for bulb in self.targets.iter().filter_map(online) {
+			let color = bulb.product_info().map_or(color, |info| color.clamped_for(info));
+			bulb.set_color(color, fade)?;
+			if !self.started && self.power_on {
+				bulb.set_power(true, Duration::from_secs(0))?;
+			}
+		}
+
+		match self.started {
+			false => {
+				self.started = true;
+				Ok(Some(START_DELAY))
+			}
+			true => Ok(Some(fade)),
+		}
+	}
+}
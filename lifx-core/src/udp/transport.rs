@@ -0,0 +1,61 @@
+use std::{
+	io,
+	net::{SocketAddr, UdpSocket},
+	time::Duration,
+};
+
+/// A datagram socket that a [Manager](crate::udp::Manager) sends and receives messages through.
+///
+/// This is implemented for [UdpSocket], which is what [Manager::new](crate::udp::Manager::new)
+/// and the other `bind` constructors use.  Anything else, like an in-memory network for tests or
+/// a socket from a platform's own networking stack, can be given to
+/// [Manager::with_transport](crate::udp::Manager::with_transport) instead.
+pub trait Transport: Send + Sized + 'static {
+	/// Sends one datagram to `addr`, returning how many bytes were sent.
+	fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize>;
+
+	/// Receives one datagram into `buf`, returning its length and where it came from.
+	///
+	/// This should give up with [io::ErrorKind::WouldBlock] or [io::ErrorKind::TimedOut] as
+	/// [Transport::set_read_timeout] and [Transport::set_nonblocking] ask, so the Manager can
+	/// check for shutdown and its polled mode doesn't stall.
+	fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+
+	/// Another handle to the same socket, for use on another thread.
+	fn try_clone(&self) -> io::Result<Self>;
+
+	/// The address this socket is bound to, which decides where discovery is broadcast.
+	fn local_addr(&self) -> io::Result<SocketAddr>;
+
+	/// How long [Transport::recv_from] waits for a datagram; `None` waits forever.
+	fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+
+	/// Whether [Transport::recv_from] returns straight away when nothing has arrived.
+	fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Transport for UdpSocket {
+	fn send_to(&self, buf: &[u8], addr: SocketAddr) -> io::Result<usize> {
+		UdpSocket::send_to(self, buf, addr)
+	}
+
+	fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+		UdpSocket::recv_from(self, buf)
+	}
+
+	fn try_clone(&self) -> io::Result<UdpSocket> {
+		UdpSocket::try_clone(self)
+	}
+
+	fn local_addr(&self) -> io::Result<SocketAddr> {
+		UdpSocket::local_addr(self)
+	}
+
+	fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+		UdpSocket::set_read_timeout(self, timeout)
+	}
+
+	fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+		UdpSocket::set_nonblocking(self, nonblocking)
+	}
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use crate::{time, Message, Waveform, HSBK};
+
+/// A waveform effect, such as a pulse or a slow breathe, for [Bulb::set_waveform].
+///
+/// By default the effect runs once over one second, with no skew, and leaves the bulb on `color`
+/// when it finishes.
+///
+/// [Bulb::set_waveform]: crate::udp::Bulb::set_waveform
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveformSpec {
+	waveform: Waveform,
+	color: HSBK,
+	period: Duration,
+	cycles: f32,
+	skew: f32,
+	transient: bool,
+	set_hue: bool,
+	set_saturation: bool,
+	set_brightness: bool,
+	set_kelvin: bool,
+}
+
+impl WaveformSpec {
+	/// An effect that moves the bulb towards `color` in the shape of `waveform`.
+	pub fn new(waveform: Waveform, color: HSBK) -> WaveformSpec {
+		WaveformSpec {
+			waveform,
+			color,
+			period: Duration::from_secs(1),
+			cycles: 1.0,
+			skew: 0.0,
+			transient: false,
+			set_hue: true,
+			set_saturation: true,
+			set_brightness: true,
+			set_kelvin: true,
+		}
+	}
+
+	/// How long each cycle lasts.
+	pub fn period(mut self, period: Duration) -> WaveformSpec {
+		self.period = period;
+		self
+	}
+
+	/// How many cycles to run; fractional cycles are allowed.
+	pub fn cycles(mut self, cycles: f32) -> WaveformSpec {
+		self.cycles = cycles;
+		self
+	}
+
+	/// Skews each cycle towards its start (`-1.0`) or its end (`1.0`).
+	///
+	/// This only changes the shape of [Waveform::Pulse], where it sets the duty cycle.  Values
+	/// outside `[-1.0, 1.0]` are clamped.
+	pub fn skew(mut self, skew: f32) -> WaveformSpec {
+		self.skew = skew.clamp(-1.0, 1.0);
+		self
+	}
+
+	/// Whether the bulb goes back to its original color once the effect finishes.
+	pub fn transient(mut self, transient: bool) -> WaveformSpec {
+		self.transient = transient;
+		self
+	}
+
+	/// Leaves the bulb's hue alone, ignoring the hue of the effect's color.
+	pub fn keep_hue(mut self) -> WaveformSpec {
+		self.set_hue = false;
+		self
+	}
+
+	/// Leaves the bulb's saturation alone, ignoring the saturation of the effect's color.
+	pub fn keep_saturation(mut self) -> WaveformSpec {
+		self.set_saturation = false;
+		self
+	}
+
+	/// Leaves the bulb's brightness alone, ignoring the brightness of the effect's color.
+	pub fn keep_brightness(mut self) -> WaveformSpec {
+		self.set_brightness = false;
+		self
+	}
+
+	/// Leaves the bulb's kelvin alone, ignoring the kelvin of the effect's color.
+	pub fn keep_kelvin(mut self) -> WaveformSpec {
+		self.set_kelvin = false;
+		self
+	}
+
+	/// The skew as the protocol expresses it, where `i16::MIN` and `i16::MAX` are the extremes.
+	fn skew_ratio(&self) -> i16 {
+		(self.skew * i16::MAX as f32).round() as i16
+	}
+
+	/// Builds the message for this effect.
+	///
+	/// This is a [Message::SetWaveform] unless a channel is being kept, in which case it's a
+	/// [Message::SetWaveformOptional].
+	pub fn to_message(&self) -> Message {
+		if self.set_hue && self.set_saturation && self.set_brightness && self.set_kelvin {
+			return Message::set_waveform(
+				self.transient,
+				self.color,
+				self.period,
+				self.cycles,
+				self.skew_ratio(),
+				self.waveform,
+			);
+		}
+
+		Message::SetWaveformOptional {
+			reserved: 0,
+			transient: self.transient,
+			color: self.color,
+			period: time::to_millis(self.period),
+			cycles: self.cycles,
+			skew_ratio: self.skew_ratio(),
+			waveform: self.waveform,
+			set_hue: self.set_hue,
+			set_saturation: self.set_saturation,
+			set_brightness: self.set_brightness,
+			set_kelvin: self.set_kelvin,
+		}
+	}
+}
@@ -0,0 +1,44 @@
+/// How good a bulb's Wi-Fi reception is, using the same categories as the LIFX app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignalQuality {
+	NoSignal,
+	VeryBad,
+	SomewhatBad,
+	Alright,
+	Good,
+}
+
+/// A bulb's Wi-Fi signal strength, worked out from the `signal` of a
+/// [Message::StateWifiInfo](crate::Message::StateWifiInfo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WifiSignal {
+	/// The reported signal in dB: an RSSI if negative (or exactly 200, meaning no signal), and a
+	/// signal-to-noise ratio otherwise, depending on the device's firmware.
+	pub rssi: i32,
+	pub quality: SignalQuality,
+}
+
+impl WifiSignal {
+	/// Interprets a reported `signal` (in mW), as described in the LIFX LAN protocol docs.
+	pub fn from_signal(signal: f32) -> WifiSignal {
+		let rssi = (10.0 * signal.log10() + 0.5).floor() as i32;
+		let quality = if rssi < 0 || rssi == 200 {
+			match rssi {
+				200 => SignalQuality::NoSignal,
+				i32::MIN..=-80 => SignalQuality::VeryBad,
+				-79..=-70 => SignalQuality::SomewhatBad,
+				-69..=-60 => SignalQuality::Alright,
+				_ => SignalQuality::Good,
+			}
+		} else {
+			match rssi {
+				4..=6 => SignalQuality::VeryBad,
+				7..=11 => SignalQuality::SomewhatBad,
+				12..=16 => SignalQuality::Alright,
+				17.. => SignalQuality::Good,
+				_ => SignalQuality::NoSignal,
+			}
+		};
+		WifiSignal { rssi, quality }
+	}
+}
@@ -0,0 +1,67 @@
+use std::sync::RwLock;
+
+use crate::product::ProductInfo;
+
+/// The maker of a device, as reported by [Message::StateVersion](crate::Message::StateVersion).
+///
+/// Only LIFX's own products are built in to [get_product_info](crate::get_product_info).  Devices
+/// from other vendors that speak the LAN protocol can be described with [register_product].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Vendor {
+	/// LIFX itself (vendor ID 1)
+	Lifx,
+	/// Any other vendor, by ID
+	Other(u32),
+}
+
+impl From<u32> for Vendor {
+	fn from(id: u32) -> Vendor {
+		match id {
+			1 => Vendor::Lifx,
+			id => Vendor::Other(id),
+		}
+	}
+}
+
+impl From<Vendor> for u32 {
+	fn from(vendor: Vendor) -> u32 {
+		match vendor {
+			Vendor::Lifx => 1,
+			Vendor::Other(id) => id,
+		}
+	}
+}
+
+static REGISTRY: RwLock<Vec<(u32, u32, &'static ProductInfo)>> = RwLock::new(Vec::new());
+
+/// Makes [get_product_info](crate::get_product_info) return `info` for a product it doesn't
+/// already know about.
+///
+/// This is meant for devices from other vendors, but also works for LIFX products that are newer
+/// than this crate.  Products in the built-in table can't be overridden.  If the product was
+/// already registered, the previous info is replaced and returned.
+pub fn register_product(
+	vendor: Vendor,
+	product: u32,
+	info: &'static ProductInfo,
+) -> Option<&'static ProductInfo> {
+	let vendor = u32::from(vendor);
+	let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+	match registry.iter_mut().find(|(v, p, _)| *v == vendor && *p == product) {
+		Some(entry) => Some(std::mem::replace(&mut entry.2, info)),
+		None => {
+			registry.push((vendor, product, info));
+			None
+		}
+	}
+}
+
+/// Looks up a product added with [register_product].
+pub(crate) fn registered_product(vendor: u32, product: u32) -> Option<&'static ProductInfo> {
+	let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+	registry
+		.iter()
+		.find(|(v, p, _)| *v == vendor && *p == product)
+		.map(|(_, _, info)| *info)
+}
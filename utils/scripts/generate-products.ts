@@ -13,15 +13,65 @@ pub struct ProductInfo {
 	pub chain: bool,
 	pub matrix: bool,
 	pub infrared: bool,
+	/// Whether the device has a germicidal (HEV) light, controlled with the [SetHevCycle] family
+	/// of messages.
+	///
+	/// [SetHevCycle]: crate::Message::SetHevCycle
+	pub hev: bool,
+	/// Whether the device has relays (e.g. a Switch), which are toggled with the relay power
+	/// messages rather than [SetPower](crate::Message::SetPower).
+	pub relays: bool,
+	/// Whether the device has physical buttons, configured with
+	/// [SetButton](crate::Message::SetButton).
+	pub buttons: bool,
 	pub multizone: bool,
+	/// Whether the device understands [SetExtendedColorZones] and friends, which update up to 82
+	/// zones at once.  Some devices only gained support in a firmware update; see
+	/// [ProductInfo::supports_extended_multizone].
+	///
+	/// [SetExtendedColorZones]: crate::Message::SetExtendedColorZones
+	pub extended_multizone: bool,
+	/// The build time of the first host firmware with extended multizone support, if it wasn't
+	/// supported from the start.
+	pub min_ext_mz_firmware: Option<u32>,
+	/// The \`(major, minor)\` version of the first host firmware with extended multizone support, if
+	/// it wasn't supported from the start.
+	pub min_ext_mz_firmware_components: Option<(u16, u16)>,
+	/// The lowest and highest color temperatures (in kelvin) the device can display, or \`None\` if
+	/// it doesn't emit light (e.g. a Switch).  Some white bulbs have only one temperature.
 	pub temperature_range: Option<(u16, u16)>,
 }
 
+impl ProductInfo {
+	/// Clamps \`kelvin\` to [ProductInfo::temperature_range], if there is one.
+	pub fn clamp_kelvin(&self, kelvin: u16) -> u16 {
+		match self.temperature_range {
+			Some((low, high)) => kelvin.clamp(low, high),
+			None => kelvin,
+		}
+	}
+
+	/// Whether a device running host firmware \`version\` (as reported by
+	/// [StateHostFirmware](crate::Message::StateHostFirmware)) supports extended multizone
+	/// messages.
+	pub fn supports_extended_multizone(&self, version: u32) -> bool {
+		match self.min_ext_mz_firmware_components {
+			Some((major, minor)) => {
+				self.extended_multizone && version >= (major as u32) << 16 | minor as u32
+			}
+			None => self.extended_multizone,
+		}
+	}
+}
+
 /// Look up info about what a LIFX product supports.
 ///
 /// You can get the vendor and product IDs from a bulb by receiving a [Message::StateVersion] message
 ///
-/// Data is taken from https://github.com/LIFX/products/blob/master/products.json
+/// Data is taken from https://github.com/LIFX/products/blob/master/products.json.  Products that
+/// aren't in that list (including other vendors' devices) can be added with [register_product].
+///
+/// [register_product]: crate::register_product
 pub fn get_product_info(vendor: u32, product: u32) -> Option<&'static ProductInfo> {
 	match (vendor, product) {
 `;
@@ -31,7 +81,17 @@ const POSTFIX = `
 `;
 const SOURCE_URL = "https://raw.githubusercontent.com/LIFX/products/master/products.json";
 
-const FEATURE_KEYS = ["color", "chain", "matrix", "infrared", "multizone"];
+const FEATURE_KEYS = [
+	"color",
+	"chain",
+	"matrix",
+	"infrared",
+	"hev",
+	"relays",
+	"buttons",
+	"multizone",
+	"extended_multizone",
+];
 
 type FeatureKey = typeof FEATURE_KEYS[number];
 
@@ -40,6 +100,8 @@ interface IProductInfo {
 	name: string;
 	features: {
 		temperature_range?: [number, number];
+		min_ext_mz_firmware?: number;
+		min_ext_mz_firmware_components?: [number, number];
 	} & Record<FeatureKey, boolean>;
 }
 
@@ -50,13 +112,26 @@ interface IProductInfo {
 	products.forEach(p => {
 		content += [
 			`		(1, ${p.pid}) => Some(&ProductInfo {\n`,
-			`			name: "${p.name}",\n`,
+			`			name: ${JSON.stringify(p.name)},\n`,
 		].join("")
 
 		FEATURE_KEYS.forEach(key => {
-			content += `			${key}: ${p.features[key]},\n`
+			content += `			${key}: ${!!p.features[key]},\n`
 		})
 
+		if (p.features.min_ext_mz_firmware) {
+			content += `			min_ext_mz_firmware: Some(${p.features.min_ext_mz_firmware}),\n`
+		} else {
+			content += `			min_ext_mz_firmware: None,\n`
+		}
+
+		if (p.features.min_ext_mz_firmware_components) {
+			const [major, minor] = p.features.min_ext_mz_firmware_components
+			content += `			min_ext_mz_firmware_components: Some((${major}, ${minor})),\n`
+		} else {
+			content += `			min_ext_mz_firmware_components: None,\n`
+		}
+
 		if (p.features.temperature_range) {
 			const [low, high] = p.features.temperature_range
 			content += `			temperature_range: Some((${low}, ${high})),\n`
@@ -67,7 +142,7 @@ interface IProductInfo {
 		content += `		}),\n`
 	})
 
-	content += `		(_, _) => None,`
+	content += `		(vendor, product) => crate::vendor::registered_product(vendor, product),`
 	content += POSTFIX
 
 	let fpath = path.resolve(process.cwd(), "../../lifx-core/src/product.rs")